@@ -0,0 +1,80 @@
+//! Throughput of the batch functions against a loop over single codes.
+//!
+//! Run with `cargo bench --bench batch`, the best of several runs is printed.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use morton_code::{decode_batch, encode_batch, wrapping_add_batch, Morton3D};
+
+const LEN: u32 = 10_000_000;
+const RUNS: usize = 15;
+
+/// best time of `f` over the runs
+fn best(mut f: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, single: Duration, batch: Duration) {
+    println!(
+        "{:<14} single {:>8.2?}  batch {:>8.2?}  speedup {:.2}",
+        name,
+        single,
+        batch,
+        single.as_secs_f64() / batch.as_secs_f64()
+    );
+}
+
+fn main() {
+    let points: Vec<_> = (0..LEN)
+        .map(|i| [i.wrapping_mul(2_654_435_761), i.wrapping_mul(40_503), i])
+        .collect();
+    let mut codes = vec![Morton3D::default(); points.len()];
+    let mut decoded = vec![[0; 3]; points.len()];
+    let mut moved = vec![Morton3D::default(); points.len()];
+
+    let single = best(|| {
+        for (&[x, y, z], code) in black_box(&points).iter().zip(codes.iter_mut()) {
+            *code = Morton3D::from_coords(x, y, z);
+        }
+        black_box(&codes);
+    });
+    let batch = best(|| {
+        encode_batch(black_box(&points), &mut codes);
+        black_box(&codes);
+    });
+    report("encode", single, batch);
+
+    let single = best(|| {
+        for (&code, point) in black_box(&codes).iter().zip(decoded.iter_mut()) {
+            let (x, y, z) = code.to_coords();
+            *point = [x, y, z];
+        }
+        black_box(&decoded);
+    });
+    let batch = best(|| {
+        decode_batch(black_box(&codes), &mut decoded);
+        black_box(&decoded);
+    });
+    report("decode", single, batch);
+
+    let offset = Morton3D::FACE_OFFSETS[0];
+    let single = best(|| {
+        for (&code, out) in black_box(&codes).iter().zip(moved.iter_mut()) {
+            *out = code.wrapping_add(offset);
+        }
+        black_box(&moved);
+    });
+    let batch = best(|| {
+        wrapping_add_batch(black_box(&codes), offset, &mut moved);
+        black_box(&moved);
+    });
+    report("wrapping_add", single, batch);
+}
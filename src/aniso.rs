@@ -0,0 +1,251 @@
+use crate::MortonError;
+
+/// Scatter the lower bits of `v` into the set bits of `mask`, from the lowest.
+const fn deposit(v: u32, mask: u64) -> u64 {
+    let mut result = 0;
+    let mut mask = mask;
+    let mut bit = 0;
+    while mask != 0 {
+        let lowest = mask & mask.wrapping_neg();
+        if (v >> bit) & 1 == 1 {
+            result |= lowest;
+        }
+        mask &= mask - 1;
+        bit += 1;
+    }
+    result
+}
+
+/// Gather the bits of `v` at the set bits of `mask` into the lower bits, from the lowest.
+const fn extract(v: u64, mask: u64) -> u32 {
+    let mut result = 0;
+    let mut mask = mask;
+    let mut bit = 0;
+    while mask != 0 {
+        let lowest = mask & mask.wrapping_neg();
+        if v & lowest != 0 {
+            result |= 1 << bit;
+        }
+        mask &= mask - 1;
+        bit += 1;
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// 3-dimension morton code(zyx) backed by `u64` with `BX`, `BY` and `BZ` bits for x, y and z.
+///
+/// The lower levels interleave all three axes, once an axis runs out of bits
+/// the higher levels interleave only the remaining axes,
+/// e.g. `MortonAniso<24, 24, 16>` is 16 levels of zyx followed by 8 levels of yx.
+/// `BX + BY + BZ` must not exceed 64 and each budget must not exceed 32; there is no flag bit.
+pub struct MortonAniso<const BX: u32, const BY: u32, const BZ: u32>(u64);
+
+impl<const BX: u32, const BY: u32, const BZ: u32> MortonAniso<BX, BY, BZ> {
+    /// Fail to compile if the bit budgets are not supported.
+    const VALID_BUDGET: () = assert!(
+        BX <= 32 && BY <= 32 && BZ <= 32 && BX + BY + BZ <= 64,
+        "each budget must be at most 32 bits and the total at most 64 bits"
+    );
+
+    /// Bits of each axis.
+    const MASKS: [u64; 3] = {
+        let budgets = [BX, BY, BZ];
+        let mut masks = [0; 3];
+        let mut pos = 0;
+        let mut level = 0;
+        while level < 32 {
+            let mut n = 0;
+            while n < 3 {
+                if level < budgets[n] {
+                    masks[n] |= 1 << pos;
+                    pos += 1;
+                }
+                n += 1;
+            }
+            level += 1;
+        }
+        masks
+    };
+
+    /// Max coordinate of x, y and z.
+    pub const MAX_COORDS: [u32; 3] = [
+        (((1_u64) << BX) - 1) as u32,
+        (((1_u64) << BY) - 1) as u32,
+        (((1_u64) << BZ) - 1) as u32,
+    ];
+
+    /// encode coordinates into morton code,
+    /// only the lower `BX`, `BY`, `BZ` bits of each coordinate are used.
+    pub const fn from_coords(x: u32, y: u32, z: u32) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_BUDGET;
+        Self(deposit(x, Self::MASKS[0]) | deposit(y, Self::MASKS[1]) | deposit(z, Self::MASKS[2]))
+    }
+
+    /// encode coordinates into morton code,
+    /// return error if any coordinate exceeds its budget.
+    pub const fn try_from_coords(x: u32, y: u32, z: u32) -> Result<Self, MortonError> {
+        let coords = [x, y, z];
+        let mut axis = 0;
+        while axis < 3 {
+            if coords[axis] > Self::MAX_COORDS[axis] {
+                return Err(MortonError::CoordinateOutOfRange {
+                    axis,
+                    value: coords[axis] as u64,
+                    max: Self::MAX_COORDS[axis] as u64,
+                });
+            }
+            axis += 1;
+        }
+        Ok(Self::from_coords(x, y, z))
+    }
+
+    /// decode morton code into coordinates `(x, y, z)`.
+    pub const fn to_coords(self) -> (u32, u32, u32) {
+        (self.x(), self.y(), self.z())
+    }
+
+    /// decrease n-th dim (0: x, 1: y, 2: z) morton code,
+    /// panic if it can't
+    const fn decrease_nth_dim(self, n: usize) -> Self {
+        let mask = Self::MASKS[n];
+        Self((((self.0 & mask) - 1) & mask) | (self.0 & !mask))
+    }
+    /// increase n-th dim (0: x, 1: y, 2: z) morton code,
+    /// panic if it can't
+    const fn increase_nth_dim(self, n: usize) -> Self {
+        let mask = Self::MASKS[n];
+        Self((((self.0 | !mask) + 1) & mask) | (self.0 & !mask))
+    }
+
+    /// replace n-th dim (0: x, 1: y, 2: z) coordinate, other dims are kept
+    const fn with_nth_dim(self, n: usize, v: u32) -> Self {
+        Self((self.0 & !Self::MASKS[n]) | deposit(v, Self::MASKS[n]))
+    }
+
+    pub const fn x(self) -> u32 {
+        extract(self.0, Self::MASKS[0])
+    }
+    pub const fn y(self) -> u32 {
+        extract(self.0, Self::MASKS[1])
+    }
+    pub const fn z(self) -> u32 {
+        extract(self.0, Self::MASKS[2])
+    }
+
+    pub const fn with_x(self, x: u32) -> Self {
+        self.with_nth_dim(0, x)
+    }
+    pub const fn with_y(self, y: u32) -> Self {
+        self.with_nth_dim(1, y)
+    }
+    pub const fn with_z(self, z: u32) -> Self {
+        self.with_nth_dim(2, z)
+    }
+
+    pub const fn decrease_x(self) -> Self {
+        self.decrease_nth_dim(0)
+    }
+    pub const fn decrease_y(self) -> Self {
+        self.decrease_nth_dim(1)
+    }
+    pub const fn decrease_z(self) -> Self {
+        self.decrease_nth_dim(2)
+    }
+
+    pub const fn increase_x(self) -> Self {
+        self.increase_nth_dim(0)
+    }
+    pub const fn increase_y(self) -> Self {
+        self.increase_nth_dim(1)
+    }
+    pub const fn increase_z(self) -> Self {
+        self.increase_nth_dim(2)
+    }
+}
+
+impl<const BX: u32, const BY: u32, const BZ: u32> From<u64> for MortonAniso<BX, BY, BZ> {
+    fn from(v: u64) -> Self {
+        Self(v)
+    }
+}
+
+impl<const BX: u32, const BY: u32, const BZ: u32> From<MortonAniso<BX, BY, BZ>> for u64 {
+    fn from(v: MortonAniso<BX, BY, BZ>) -> Self {
+        v.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MortonAniso;
+    use crate::Morton3D64;
+
+    type Terrain = MortonAniso<24, 24, 16>;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(
+            Terrain::MASKS,
+            [
+                0x5555_2492_4924_9249,
+                0xaaaa_4924_9249_2492,
+                0x0000_9249_2492_4924
+            ]
+        );
+        assert_eq!(
+            Terrain::MASKS[0] | Terrain::MASKS[1] | Terrain::MASKS[2],
+            u64::MAX
+        );
+        assert_eq!(
+            Terrain::MAX_COORDS,
+            [(1 << 24) - 1, (1 << 24) - 1, (1 << 16) - 1]
+        );
+
+        // same bit budgets is the same layout as isotropic morton code (without flag)
+        let morton = MortonAniso::<21, 21, 21>::from_coords(12345, 678, 1 << 20);
+        assert_eq!(
+            u64::from(morton),
+            u64::from(Morton3D64::from_coords(12345, 678, 1 << 20))
+        );
+    }
+
+    #[test]
+    fn test_coords() {
+        let max = Terrain::MAX_COORDS;
+        for &(x, y, z) in &[
+            (0, 0, 0),
+            (1, 2, 3),
+            (max[0], 0, max[2]),
+            (max[0], max[1], max[2]),
+        ] {
+            let morton = Terrain::from_coords(x, y, z);
+            assert_eq!(morton.to_coords(), (x, y, z));
+        }
+        assert_eq!(
+            Terrain::from_coords(u32::MAX, u32::MAX, u32::MAX),
+            Terrain::from(u64::MAX)
+        );
+        assert!(Terrain::try_from_coords(0, 0, 1 << 16).is_err());
+        assert!(Terrain::try_from_coords(1 << 23, 0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_inc_dec() {
+        let morton = Terrain::from_coords(0xffff, 0xffff, 0xff);
+        assert_eq!(
+            morton.increase_x(),
+            Terrain::from_coords(0x10000, 0xffff, 0xff)
+        );
+        assert_eq!(
+            morton.increase_y().decrease_z(),
+            Terrain::from_coords(0xffff, 0x10000, 0xfe)
+        );
+        assert_eq!(morton.with_z(7).z(), 7);
+        assert_eq!(
+            Terrain::from_coords(1 << 20, 0, 0).decrease_x(),
+            Terrain::from_coords((1 << 20) - 1, 0, 0)
+        );
+    }
+}
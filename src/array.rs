@@ -0,0 +1,262 @@
+use alloc::vec::Vec;
+use core::iter::Enumerate;
+use core::ops::{Index, IndexMut};
+use core::slice;
+
+use crate::{Morton3D, MortonBox};
+
+/// Dense `size_x * size_y * size_z` array of values laid out in Morton order.
+///
+/// The value of the cell `(x, y, z)` is at the index of its code, so neighbours in space
+/// are close in memory at every scale. When the sizes are not the same power of two,
+/// the storage has padding slots for the codes below the last one which are outside of the array.
+/// The padding grows with how much the sizes differ, so shapes needing more than
+/// [`MAX_PADDING`](Self::MAX_PADDING) slots per cell are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MortonArray<T> {
+    bounds: MortonBox,
+    data: Vec<T>,
+}
+
+impl<T> MortonArray<T> {
+    /// largest number of storage slots per cell of the array.
+    ///
+    /// Arrays whose sizes are within a factor 2 of each other, cubes included, always fit,
+    /// while a `1024 * 1 * 1` array would need 153,391,690 slots and is rejected.
+    pub const MAX_PADDING: u64 = 32;
+
+    /// create array of `size_x * size_y * size_z` copies of `value`, padding slots included.
+    ///
+    /// panic if any size is 0 or exceeds `Morton3D::MAX_COORD + 1`,
+    /// or the storage would have more than [`MAX_PADDING`](Self::MAX_PADDING) slots per cell.
+    pub fn new(size_x: u32, size_y: u32, size_z: u32, value: T) -> Self
+    where
+        T: Clone,
+    {
+        let max = Morton3D::MAX_COORD as u64 + 1;
+        assert!(
+            [size_x, size_y, size_z]
+                .iter()
+                .all(|&size| size > 0 && size as u64 <= max),
+            "size must be in 1..={}",
+            max
+        );
+        let bounds = MortonBox::new(
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(size_x - 1, size_y - 1, size_z - 1),
+        );
+        let slots = usize::from(bounds.max()) + 1;
+        let cells = u64::from(size_x) * u64::from(size_y) * u64::from(size_z);
+        assert!(
+            slots as u64 <= cells.saturating_mul(Self::MAX_PADDING),
+            "padding too large"
+        );
+        Self {
+            bounds,
+            data: alloc::vec![value; slots],
+        }
+    }
+
+    /// sizes along each axis
+    pub fn size(&self) -> (u32, u32, u32) {
+        let (x, y, z) = self.bounds.max().to_coords();
+        (x + 1, y + 1, z + 1)
+    }
+
+    /// number of cells of the array, padding slots excluded
+    pub fn len(&self) -> usize {
+        let (x, y, z) = self.size();
+        x as usize * y as usize * z as usize
+    }
+    /// never true, as sizes are not 0
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// storage in Morton order, padding slots included
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// index of the cell in the storage, `None` if it is outside of the array, flag is ignored
+    fn index_of(&self, code: Morton3D) -> Option<usize> {
+        if !self.bounds.contains(code) {
+            return None;
+        }
+        let mut code = code;
+        code.unset_flag();
+        Some(usize::from(code))
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> Option<&T> {
+        self.get_morton(Morton3D::try_from_coords(x, y, z).ok()?)
+    }
+    pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> Option<&mut T> {
+        self.get_morton_mut(Morton3D::try_from_coords(x, y, z).ok()?)
+    }
+    pub fn get_morton(&self, code: Morton3D) -> Option<&T> {
+        let i = self.index_of(code)?;
+        Some(&self.data[i])
+    }
+    pub fn get_morton_mut(&mut self, code: Morton3D) -> Option<&mut T> {
+        let i = self.index_of(code)?;
+        Some(&mut self.data[i])
+    }
+
+    /// iterate over the cells of the array in Morton order, padding slots excluded.
+    pub fn iter(&self) -> ArrayIter<'_, T> {
+        ArrayIter {
+            bounds: self.bounds,
+            data: self.data.iter().enumerate(),
+        }
+    }
+    /// iterate mutably over the cells of the array in Morton order, padding slots excluded.
+    pub fn iter_mut(&mut self) -> ArrayIterMut<'_, T> {
+        ArrayIterMut {
+            bounds: self.bounds,
+            data: self.data.iter_mut().enumerate(),
+        }
+    }
+}
+
+impl<T> Index<Morton3D> for MortonArray<T> {
+    type Output = T;
+    /// panic if the cell is outside of the array.
+    fn index(&self, code: Morton3D) -> &T {
+        self.get_morton(code).expect("cell out of array")
+    }
+}
+
+impl<T> IndexMut<Morton3D> for MortonArray<T> {
+    fn index_mut(&mut self, code: Morton3D) -> &mut T {
+        self.get_morton_mut(code).expect("cell out of array")
+    }
+}
+
+impl<T> Index<(u32, u32, u32)> for MortonArray<T> {
+    type Output = T;
+    /// panic if the cell is outside of the array.
+    fn index(&self, (x, y, z): (u32, u32, u32)) -> &T {
+        self.get(x, y, z).expect("cell out of array")
+    }
+}
+
+impl<T> IndexMut<(u32, u32, u32)> for MortonArray<T> {
+    fn index_mut(&mut self, (x, y, z): (u32, u32, u32)) -> &mut T {
+        self.get_mut(x, y, z).expect("cell out of array")
+    }
+}
+
+/// Iterator over the cells of a [`MortonArray`] with their codes, in Morton order.
+#[derive(Debug, Clone)]
+pub struct ArrayIter<'a, T> {
+    bounds: MortonBox,
+    data: Enumerate<slice::Iter<'a, T>>,
+}
+
+impl<'a, T> Iterator for ArrayIter<'a, T> {
+    type Item = (Morton3D, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let bounds = self.bounds;
+        self.data
+            .find(|&(i, _)| bounds.contains(Morton3D::from(i)))
+            .map(|(i, v)| (Morton3D::from(i), v))
+    }
+}
+
+/// Mutable iterator over the cells of a [`MortonArray`] with their codes, in Morton order.
+#[derive(Debug)]
+pub struct ArrayIterMut<'a, T> {
+    bounds: MortonBox,
+    data: Enumerate<slice::IterMut<'a, T>>,
+}
+
+impl<'a, T> Iterator for ArrayIterMut<'a, T> {
+    type Item = (Morton3D, &'a mut T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let bounds = self.bounds;
+        self.data
+            .find(|(i, _)| bounds.contains(Morton3D::from(*i)))
+            .map(|(i, v)| (Morton3D::from(i), v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MortonArray;
+    use crate::Morton3D;
+
+    #[test]
+    fn test_array() {
+        let mut array = MortonArray::new(4, 4, 4, 0u32);
+        assert_eq!(array.size(), (4, 4, 4));
+        assert_eq!(array.as_slice().len(), 64);
+        array[(1, 2, 3)] = 7;
+        assert_eq!(array.get(1, 2, 3), Some(&7));
+        assert_eq!(array[Morton3D::from_coords(1, 2, 3)], 7);
+        assert_eq!(
+            array.as_slice()[usize::from(Morton3D::from_coords(1, 2, 3))],
+            7
+        );
+        *array
+            .get_morton_mut(Morton3D::from_coords(0, 0, 1))
+            .unwrap() = 3;
+        assert_eq!(array.get(0, 0, 1), Some(&3));
+        assert_eq!(array.get(4, 0, 0), None);
+        assert_eq!(array.get(u32::MAX, 0, 0), None);
+
+        let mut flagged = Morton3D::from_coords(1, 2, 3);
+        flagged.set_flag();
+        assert_eq!(array.get_morton(flagged), Some(&7));
+    }
+
+    #[test]
+    fn test_array_padding() {
+        let mut array = MortonArray::new(3, 5, 2, 1u32);
+        assert_eq!(array.len(), 30);
+        assert!(array.as_slice().len() > 30);
+        for (code, v) in array.iter_mut() {
+            let (x, y, z) = code.to_coords();
+            *v = x + 10 * y + 100 * z;
+        }
+        let cells: Vec<_> = array.iter().collect();
+        assert_eq!(cells.len(), 30);
+        assert!(cells.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(cells.iter().all(|&(code, &v)| {
+            let (x, y, z) = code.to_coords();
+            x < 3 && y < 5 && z < 2 && v == x + 10 * y + 100 * z
+        }));
+        assert_eq!(array[(2, 4, 1)], 142);
+        assert_eq!(array.get(3, 0, 0), None);
+    }
+
+    #[test]
+    fn test_array_max_padding() {
+        for x in 1..=16u32 {
+            for y in x.div_ceil(2)..=(2 * x).min(16) {
+                for z in x.max(y).div_ceil(2)..=(2 * x.min(y)).min(16) {
+                    let array = MortonArray::new(x, y, z, ());
+                    assert!(array.as_slice().len() as u64 <= 32 * array.len() as u64);
+                }
+            }
+        }
+        let cube = MortonArray::new(1 << 7, 1 << 7, 1 << 7, 0u8);
+        assert_eq!(cube.as_slice().len(), cube.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "padding too large")]
+    fn test_array_skewed() {
+        MortonArray::new(1024, 1, 1, 0u8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_array_out_of_bounds() {
+        let array = MortonArray::new(2, 2, 2, 0u8);
+        let _ = array[(2, 0, 0)];
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use crate::MortonKey;
+
+/// leaf of sorted `leaves` containing `v` (or `v` itself), `None` if `v` is subdivided or not covered.
+fn containing_leaf(leaves: &[MortonKey], v: MortonKey) -> Option<MortonKey> {
+    let i = leaves.partition_point(|&k| k <= v);
+    leaves[..i]
+        .last()
+        .copied()
+        .filter(|&k| k == v || k.is_ancestor_of(v))
+}
+
+/// whether leaves of a linear octree are 2:1 balanced, i.e. depths of any two leaves
+/// touching each other (by a face, an edge or a corner) differ at most by 1.
+///
+/// `leaves` must be sorted and must not overlap each other.
+pub fn is_balanced(leaves: &[MortonKey]) -> bool {
+    // enough to check coarser neighbors, finer ones check this leaf from their side
+    leaves.iter().all(|&leaf| {
+        leaf.moore_neighbors()
+            .all(|n| match containing_leaf(leaves, n) {
+                Some(coarse) => coarse.depth() + 1 >= leaf.depth(),
+                None => true,
+            })
+    })
+}
+
+/// refine leaves of a linear octree until it is 2:1 balanced (see [`is_balanced`]),
+/// return the sorted balanced leaves.
+///
+/// `leaves` must not overlap each other. Leaves are only split, never merged,
+/// so every input leaf is covered by the output leaves.
+#[cfg(feature = "alloc")]
+pub fn balance(leaves: &[MortonKey]) -> Vec<MortonKey> {
+    let mut set: BTreeSet<MortonKey> = leaves.iter().copied().collect();
+    let max_depth = leaves.iter().map(|k| k.depth()).max().unwrap_or(0);
+    // splitting only creates leaves coarser than the current depth, so each depth is visited once
+    for depth in (2..=max_depth).rev() {
+        let fine: Vec<_> = set.iter().copied().filter(|k| k.depth() == depth).collect();
+        for leaf in fine {
+            for n in leaf.moore_neighbors() {
+                while let Some(coarse) = set
+                    .range(..=n)
+                    .next_back()
+                    .copied()
+                    .filter(|&k| k.is_ancestor_of(n))
+                {
+                    if coarse.depth() + 1 >= depth {
+                        break;
+                    }
+                    set.remove(&coarse);
+                    set.extend(coarse.children().iter());
+                }
+            }
+        }
+    }
+    set.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_balanced;
+    use crate::MortonKey;
+
+    #[test]
+    fn test_is_balanced() {
+        let root = MortonKey::ROOT;
+        let uniform: Vec<_> = root.children().to_vec();
+        assert!(is_balanced(&uniform));
+        assert!(is_balanced(&[root]));
+
+        // one octant refined twice in the corner touching the others
+        let mut leaves: Vec<_> = root.children()[1..].to_vec();
+        leaves.extend(root.child(0).children()[..7].iter());
+        leaves.extend(root.child(0).child(7).children().iter());
+        leaves.sort();
+        assert!(!is_balanced(&leaves));
+
+        // refined at the far corner, not touching the other octants
+        let mut leaves: Vec<_> = root.children()[1..].to_vec();
+        leaves.extend(root.child(0).children()[1..].iter());
+        leaves.extend(root.child(0).child(0).children().iter());
+        leaves.sort();
+        assert!(is_balanced(&leaves));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_balance() {
+        use super::balance;
+
+        let root = MortonKey::ROOT;
+        let mut leaves: Vec<_> = root.children()[1..].to_vec();
+        leaves.extend(root.child(0).children()[..7].iter());
+        leaves.extend(root.child(0).child(7).children()[..7].iter());
+        leaves.extend(root.child(0).child(7).child(7).children().iter());
+        leaves.sort();
+        assert!(!is_balanced(&leaves));
+
+        let balanced = balance(&leaves);
+        assert!(is_balanced(&balanced));
+        assert!(balanced.windows(2).all(|w| w[0] < w[1]));
+        // input leaves are still covered
+        assert!(leaves.iter().all(|&leaf| balanced
+            .iter()
+            .any(|&k| k == leaf || leaf.is_ancestor_of(k))));
+        // no overlap
+        assert!(balanced.windows(2).all(|w| !w[0].is_ancestor_of(w[1])));
+        assert!(balanced.len() > leaves.len());
+        assert_eq!(balance(&balanced), balanced);
+    }
+}
@@ -0,0 +1,769 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(all(
+    target_pointer_width = "64",
+    feature = "nightly",
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+use core::simd::u64x4;
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use neon::{add_lanes, decode_lanes, encode_lanes};
+
+use crate::Morton3D;
+
+/// number of codes encoded or decoded per iteration
+#[cfg(target_pointer_width = "64")]
+const LANES: usize = 4;
+
+/// coordinate bits kept by encoding
+#[cfg(target_pointer_width = "64")]
+const LOW: u64 = 0x1f_ffff;
+
+/// bits of the x axis of a code
+#[cfg(target_pointer_width = "64")]
+const DILATED: u64 = 0x1249_2492_4924_9249;
+
+/// flag of a code
+#[cfg(target_pointer_width = "64")]
+const FLAG: u64 = 1 << 63;
+
+/// bits of each axis of a code
+#[cfg(target_pointer_width = "64")]
+const AXES: [u64; 3] = [DILATED, DILATED << 1, DILATED << 2];
+
+/// shifts and masks spreading the bits of a coordinate, see `crate::bits::expand_bits_u64`
+#[cfg(target_pointer_width = "64")]
+const EXPAND: [(u64, u64); 5] = [
+    (32, 0x001f_0000_0000_ffff),
+    (16, 0x001f_0000_ff00_00ff),
+    (8, 0x100f_00f0_0f00_f00f),
+    (4, 0x10c3_0c30_c30c_30c3),
+    (2, DILATED),
+];
+
+/// shifts and masks gathering the bits of a coordinate, see `crate::bits::compact_bits_u64`
+#[cfg(target_pointer_width = "64")]
+const COMPACT: [(u64, u64); 5] = [
+    (2, 0x10c3_0c30_c30c_30c3),
+    (4, 0x100f_00f0_0f00_f00f),
+    (8, 0x001f_0000_ff00_00ff),
+    (16, 0x001f_0000_0000_ffff),
+    (32, LOW),
+];
+
+/// encode points `[x, y, z]` into `codes`, the same as [`Morton3D::from_coords`] on each point.
+///
+/// With the `std` feature on x86_64, the instructions are picked at runtime: the bits are
+/// deposited with `pdep` on CPUs supporting BMI2, except AMD CPUs before Zen 3 where it is slow,
+/// else 4 codes are dilated at once in AVX2 vectors. On 10M points on Intel Xeons,
+/// `benches/batch.rs` measures `pdep` as 1.7 to 2.9 times and AVX2 as 1.8 times faster than
+/// a loop of `from_coords`.
+///
+/// Otherwise codes are encoded 4 at a time with the bit dilation of the lanes done side by side,
+/// with NEON on aarch64, `core::simd` vectors with the `nightly` feature, or plain integers.
+/// Measured on x86_64, this is no faster than a loop of `from_coords`.
+///
+/// panic if `points` and `codes` have different lengths.
+pub fn encode_batch(points: &[[u32; 3]], codes: &mut [Morton3D]) {
+    assert_eq!(points.len(), codes.len(), "lengths differ");
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if bmi2::fast() {
+        // SAFETY: the CPU supports BMI2
+        return unsafe { bmi2::encode(points, codes) };
+    }
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2
+        return unsafe { avx2::encode(points, codes) };
+    }
+    #[cfg(target_pointer_width = "64")]
+    let (points, codes) = {
+        let mut points = points.chunks_exact(LANES);
+        let mut codes = codes.chunks_exact_mut(LANES);
+        for (p, c) in (&mut points).zip(&mut codes) {
+            let lanes = encode_lanes([p[0], p[1], p[2], p[3]]);
+            for (c, v) in c.iter_mut().zip(lanes.iter()) {
+                *c = Morton3D::from_raw(*v as usize);
+            }
+        }
+        (points.remainder(), codes.into_remainder())
+    };
+    for (&[x, y, z], code) in points.iter().zip(codes) {
+        *code = Morton3D::from_coords(x, y, z);
+    }
+}
+
+/// decode `codes` into points `[x, y, z]`, the same as [`Morton3D::to_coords`] on each code.
+/// The flag is ignored.
+///
+/// Codes are decoded with `pext` or AVX2 vectors when the CPU supports them,
+/// else 4 at a time, see [`encode_batch`].
+///
+/// panic if `codes` and `points` have different lengths.
+pub fn decode_batch(codes: &[Morton3D], points: &mut [[u32; 3]]) {
+    assert_eq!(codes.len(), points.len(), "lengths differ");
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if bmi2::fast() {
+        // SAFETY: the CPU supports BMI2
+        return unsafe { bmi2::decode(codes, points) };
+    }
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2
+        return unsafe { avx2::decode(codes, points) };
+    }
+    #[cfg(target_pointer_width = "64")]
+    let (codes, points) = {
+        let mut codes = codes.chunks_exact(LANES);
+        let mut points = points.chunks_exact_mut(LANES);
+        for (c, p) in (&mut codes).zip(&mut points) {
+            let lanes = decode_lanes([c[0], c[1], c[2], c[3]].map(|v| v.raw() as u64));
+            p.copy_from_slice(&lanes);
+        }
+        (codes.remainder(), points.into_remainder())
+    };
+    for (&code, point) in codes.iter().zip(points) {
+        let (x, y, z) = code.to_coords();
+        *point = [x, y, z];
+    }
+}
+
+/// add the coordinates of `offset` to each of `codes` into `out`, the same as
+/// [`Morton3D::wrapping_add`] on each code. Flags of `codes` are kept.
+///
+/// With the dilated offsets such as [`Morton3D::FACE_OFFSETS`], this moves many cells to
+/// their neighbors at once, wrapping around the edges of the grid.
+/// Codes are moved 4 at a time, but moving a code takes a few instructions so this is bound by
+/// memory and about as fast as a loop of `wrapping_add`.
+///
+/// panic if `codes` and `out` have different lengths.
+pub fn wrapping_add_batch(codes: &[Morton3D], offset: Morton3D, out: &mut [Morton3D]) {
+    assert_eq!(codes.len(), out.len(), "lengths differ");
+    #[cfg(target_pointer_width = "64")]
+    let (codes, out) = {
+        let mut codes = codes.chunks_exact(LANES);
+        let mut out = out.chunks_exact_mut(LANES);
+        for (c, o) in (&mut codes).zip(&mut out) {
+            let lanes = add_lanes(
+                [c[0], c[1], c[2], c[3]].map(|v| v.raw() as u64),
+                offset.raw() as u64,
+            );
+            for (o, v) in o.iter_mut().zip(lanes.iter()) {
+                *o = Morton3D::from_raw(*v as usize);
+            }
+        }
+        (codes.remainder(), out.into_remainder())
+    };
+    for (&code, out) in codes.iter().zip(out) {
+        *out = code.wrapping_add(offset);
+    }
+}
+
+/// append the 6 cells sharing a face with each of `cells` to `out`, with the dilated offsets of
+/// [`Morton3D::FACE_OFFSETS`] applied to all the cells at once (see [`wrapping_add_batch`]).
+///
+/// Neighbors are grouped by offset, so neighbor `k` of `cells[i]` is at
+/// `out[len + k * cells.len() + i]` where `len` is the length of `out` before the call.
+/// Unlike [`Morton3D::face_neighbors`], neighbors across the edges of the grid aren't skipped
+/// but wrap around. The flag of each cell is kept in its neighbors.
+#[cfg(feature = "alloc")]
+pub fn face_neighbors_batch(cells: &[Morton3D], out: &mut Vec<Morton3D>) {
+    if cells.is_empty() {
+        return;
+    }
+    let len = out.len();
+    out.resize(
+        len + Morton3D::FACE_OFFSETS.len() * cells.len(),
+        Morton3D::default(),
+    );
+    let groups = out[len..].chunks_exact_mut(cells.len());
+    for (&offset, group) in Morton3D::FACE_OFFSETS.iter().zip(groups) {
+        wrapping_add_batch(cells, offset, group);
+    }
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    not(feature = "nightly"),
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn encode_lanes(points: [[u32; 3]; LANES]) -> [u64; LANES] {
+    let mut codes = [0; LANES];
+    for axis in 0..3 {
+        let mut lanes = [0; LANES];
+        for (v, p) in lanes.iter_mut().zip(points.iter()) {
+            *v = u64::from(p[axis]) & LOW;
+        }
+        for &(shift, mask) in EXPAND.iter() {
+            for v in lanes.iter_mut() {
+                *v = (*v | *v << shift) & mask;
+            }
+        }
+        for (code, v) in codes.iter_mut().zip(lanes.iter()) {
+            *code |= v << axis;
+        }
+    }
+    codes
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    not(feature = "nightly"),
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn decode_lanes(codes: [u64; LANES]) -> [[u32; 3]; LANES] {
+    let mut points = [[0; 3]; LANES];
+    for axis in 0..3 {
+        let mut lanes = codes;
+        for v in lanes.iter_mut() {
+            *v = *v >> axis & DILATED;
+        }
+        for &(shift, mask) in COMPACT.iter() {
+            for v in lanes.iter_mut() {
+                *v = (*v | *v >> shift) & mask;
+            }
+        }
+        for (p, v) in points.iter_mut().zip(lanes.iter()) {
+            p[axis] = *v as u32;
+        }
+    }
+    points
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    not(feature = "nightly"),
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn add_lanes(codes: [u64; LANES], offset: u64) -> [u64; LANES] {
+    let mut sums = codes.map(|v| v & FLAG);
+    for &m in AXES.iter() {
+        // carries go through the bits of the other axes
+        for (sum, v) in sums.iter_mut().zip(codes.iter()) {
+            *sum |= (v | !m).wrapping_add(offset & m) & m;
+        }
+    }
+    sums
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    feature = "nightly",
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn encode_lanes(points: [[u32; 3]; LANES]) -> [u64; LANES] {
+    let [x, y, z] = [0, 1, 2].map(|axis| {
+        let mut v = u64x4::from_array(points.map(|p| u64::from(p[axis]))) & u64x4::splat(LOW);
+        for &(shift, mask) in EXPAND.iter() {
+            v = (v | v << shift) & u64x4::splat(mask);
+        }
+        v
+    });
+    (x | y << 1 | z << 2).to_array()
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    feature = "nightly",
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn decode_lanes(codes: [u64; LANES]) -> [[u32; 3]; LANES] {
+    let codes = u64x4::from_array(codes);
+    let [x, y, z] = [0, 1, 2].map(|axis| {
+        let mut v = codes >> axis & u64x4::splat(DILATED);
+        for &(shift, mask) in COMPACT.iter() {
+            v = (v | v >> shift) & u64x4::splat(mask);
+        }
+        v.to_array()
+    });
+    [0, 1, 2, 3].map(|i| [x[i] as u32, y[i] as u32, z[i] as u32])
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    feature = "nightly",
+    not(all(target_arch = "aarch64", target_feature = "neon"))
+))]
+fn add_lanes(codes: [u64; LANES], offset: u64) -> [u64; LANES] {
+    let codes = u64x4::from_array(codes);
+    let mut sums = codes & u64x4::splat(FLAG);
+    for &m in AXES.iter() {
+        // carries go through the bits of the other axes
+        sums |= ((codes | u64x4::splat(!m)) + u64x4::splat(offset & m)) & u64x4::splat(m);
+    }
+    sums.to_array()
+}
+
+/// lanes as pairs of NEON vectors, points are loaded and stored with
+/// `vld3q_u32` and `vst3q_u32` which split and merge the axes.
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon {
+    use core::arch::aarch64::*;
+
+    use super::{AXES, COMPACT, DILATED, EXPAND, FLAG, LANES, LOW};
+
+    /// spread the coordinates in the lanes of `v`
+    #[inline]
+    unsafe fn expand(v: uint64x2_t) -> uint64x2_t {
+        let mut x = vandq_u64(v, vdupq_n_u64(LOW));
+        x = vandq_u64(vorrq_u64(x, vshlq_n_u64::<32>(x)), vdupq_n_u64(EXPAND[0].1));
+        x = vandq_u64(vorrq_u64(x, vshlq_n_u64::<16>(x)), vdupq_n_u64(EXPAND[1].1));
+        x = vandq_u64(vorrq_u64(x, vshlq_n_u64::<8>(x)), vdupq_n_u64(EXPAND[2].1));
+        x = vandq_u64(vorrq_u64(x, vshlq_n_u64::<4>(x)), vdupq_n_u64(EXPAND[3].1));
+        vandq_u64(vorrq_u64(x, vshlq_n_u64::<2>(x)), vdupq_n_u64(EXPAND[4].1))
+    }
+
+    /// gather the x axis of the codes in the lanes of `v`
+    #[inline]
+    unsafe fn compact(v: uint64x2_t) -> uint32x2_t {
+        let mut x = vandq_u64(v, vdupq_n_u64(DILATED));
+        x = vandq_u64(vorrq_u64(x, vshrq_n_u64::<2>(x)), vdupq_n_u64(COMPACT[0].1));
+        x = vandq_u64(vorrq_u64(x, vshrq_n_u64::<4>(x)), vdupq_n_u64(COMPACT[1].1));
+        x = vandq_u64(vorrq_u64(x, vshrq_n_u64::<8>(x)), vdupq_n_u64(COMPACT[2].1));
+        x = vandq_u64(
+            vorrq_u64(x, vshrq_n_u64::<16>(x)),
+            vdupq_n_u64(COMPACT[3].1),
+        );
+        x = vandq_u64(
+            vorrq_u64(x, vshrq_n_u64::<32>(x)),
+            vdupq_n_u64(COMPACT[4].1),
+        );
+        vmovn_u64(x)
+    }
+
+    pub(super) fn encode_lanes(points: [[u32; 3]; LANES]) -> [u64; LANES] {
+        let mut codes = [0; LANES];
+        // SAFETY: NEON is enabled, loads and stores stay within the arrays
+        unsafe {
+            // one vector for each axis
+            let uint32x4x3_t(x, y, z) = vld3q_u32(points.as_ptr() as *const u32);
+            let low = vorrq_u64(
+                vorrq_u64(
+                    expand(vmovl_u32(vget_low_u32(x))),
+                    vshlq_n_u64::<1>(expand(vmovl_u32(vget_low_u32(y)))),
+                ),
+                vshlq_n_u64::<2>(expand(vmovl_u32(vget_low_u32(z)))),
+            );
+            let high = vorrq_u64(
+                vorrq_u64(
+                    expand(vmovl_high_u32(x)),
+                    vshlq_n_u64::<1>(expand(vmovl_high_u32(y))),
+                ),
+                vshlq_n_u64::<2>(expand(vmovl_high_u32(z))),
+            );
+            vst1q_u64(codes.as_mut_ptr(), low);
+            vst1q_u64(codes.as_mut_ptr().add(2), high);
+        }
+        codes
+    }
+
+    pub(super) fn decode_lanes(codes: [u64; LANES]) -> [[u32; 3]; LANES] {
+        let mut points = [[0; 3]; LANES];
+        // SAFETY: NEON is enabled, loads and stores stay within the arrays
+        unsafe {
+            let low = vld1q_u64(codes.as_ptr());
+            let high = vld1q_u64(codes.as_ptr().add(2));
+            let axes = uint32x4x3_t(
+                vcombine_u32(compact(low), compact(high)),
+                vcombine_u32(
+                    compact(vshrq_n_u64::<1>(low)),
+                    compact(vshrq_n_u64::<1>(high)),
+                ),
+                vcombine_u32(
+                    compact(vshrq_n_u64::<2>(low)),
+                    compact(vshrq_n_u64::<2>(high)),
+                ),
+            );
+            vst3q_u32(points.as_mut_ptr() as *mut u32, axes);
+        }
+        points
+    }
+
+    pub(super) fn add_lanes(codes: [u64; LANES], offset: u64) -> [u64; LANES] {
+        let mut sums = [0; LANES];
+        // SAFETY: NEON is enabled, loads and stores stay within the arrays
+        unsafe {
+            for half in 0..LANES / 2 {
+                let v = vld1q_u64(codes.as_ptr().add(2 * half));
+                let mut sum = vandq_u64(v, vdupq_n_u64(FLAG));
+                for &m in AXES.iter() {
+                    // carries go through the bits of the other axes
+                    let moved = vaddq_u64(vorrq_u64(v, vdupq_n_u64(!m)), vdupq_n_u64(offset & m));
+                    sum = vorrq_u64(sum, vandq_u64(moved, vdupq_n_u64(m)));
+                }
+                vst1q_u64(sums.as_mut_ptr().add(2 * half), sum);
+            }
+        }
+        sums
+    }
+}
+
+/// lanes as AVX2 vectors of 4 codes.
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+mod avx2 {
+    use core::arch::x86_64::*;
+
+    use super::{COMPACT, DILATED, EXPAND, LANES, LOW};
+    use crate::Morton3D;
+
+    /// 4 times the same 64-bit value
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn splat(v: u64) -> __m256i {
+        _mm256_set1_epi64x(v as i64)
+    }
+
+    /// spread the coordinates in the lanes of `v`
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn expand(v: __m256i) -> __m256i {
+        let mut x = _mm256_and_si256(v, splat(LOW));
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_slli_epi64::<32>(x)),
+            splat(EXPAND[0].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_slli_epi64::<16>(x)),
+            splat(EXPAND[1].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_slli_epi64::<8>(x)),
+            splat(EXPAND[2].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_slli_epi64::<4>(x)),
+            splat(EXPAND[3].1),
+        );
+        _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_slli_epi64::<2>(x)),
+            splat(EXPAND[4].1),
+        )
+    }
+
+    /// gather the x axis of the codes in the lanes of `v`
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn compact(v: __m256i) -> __m256i {
+        let mut x = _mm256_and_si256(v, splat(DILATED));
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_srli_epi64::<2>(x)),
+            splat(COMPACT[0].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_srli_epi64::<4>(x)),
+            splat(COMPACT[1].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_srli_epi64::<8>(x)),
+            splat(COMPACT[2].1),
+        );
+        x = _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_srli_epi64::<16>(x)),
+            splat(COMPACT[3].1),
+        );
+        _mm256_and_si256(
+            _mm256_or_si256(x, _mm256_srli_epi64::<32>(x)),
+            splat(COMPACT[4].1),
+        )
+    }
+
+    /// see [`super::encode_batch`], `points` and `codes` have the same length.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn encode(points: &[[u32; 3]], codes: &mut [Morton3D]) {
+        // the 12 coordinates of 4 points are loaded as coordinates 0..8 and 4..12,
+        // axis `a` of point `i` is coordinate `3 * i + a` taken from the first load if below 8
+        let from_low = [
+            _mm256_setr_epi32(0, 3, 6, 0, 0, 0, 0, 0),
+            _mm256_setr_epi32(1, 4, 7, 0, 0, 0, 0, 0),
+            _mm256_setr_epi32(2, 5, 0, 0, 0, 0, 0, 0),
+        ];
+        let from_high = [
+            _mm256_setr_epi32(0, 0, 0, 5, 0, 0, 0, 0),
+            _mm256_setr_epi32(0, 0, 0, 6, 0, 0, 0, 0),
+            _mm256_setr_epi32(0, 0, 4, 7, 0, 0, 0, 0),
+        ];
+        let mut chunks = points.chunks_exact(LANES);
+        let mut out = codes.chunks_exact_mut(LANES);
+        for (p, c) in (&mut chunks).zip(&mut out) {
+            let p = p.as_ptr() as *const u32;
+            let low = _mm256_loadu_si256(p as *const __m256i);
+            let high = _mm256_loadu_si256(p.add(4) as *const __m256i);
+            let x = _mm256_blend_epi32::<0b1000>(
+                _mm256_permutevar8x32_epi32(low, from_low[0]),
+                _mm256_permutevar8x32_epi32(high, from_high[0]),
+            );
+            let y = _mm256_blend_epi32::<0b1000>(
+                _mm256_permutevar8x32_epi32(low, from_low[1]),
+                _mm256_permutevar8x32_epi32(high, from_high[1]),
+            );
+            let z = _mm256_blend_epi32::<0b1100>(
+                _mm256_permutevar8x32_epi32(low, from_low[2]),
+                _mm256_permutevar8x32_epi32(high, from_high[2]),
+            );
+            let [x, y, z] =
+                [x, y, z].map(|v| expand(_mm256_cvtepu32_epi64(_mm256_castsi256_si128(v))));
+            let v = _mm256_or_si256(
+                _mm256_or_si256(x, _mm256_slli_epi64::<1>(y)),
+                _mm256_slli_epi64::<2>(z),
+            );
+            _mm256_storeu_si256(c.as_mut_ptr() as *mut __m256i, v);
+        }
+        for (&[x, y, z], code) in chunks.remainder().iter().zip(out.into_remainder()) {
+            *code = Morton3D::from_coords(x, y, z);
+        }
+    }
+
+    /// see [`super::decode_batch`], `codes` and `points` have the same length.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn decode(codes: &[Morton3D], points: &mut [[u32; 3]]) {
+        // the low halves of the 4 lanes
+        let pack = _mm256_setr_epi32(0, 2, 4, 6, 0, 0, 0, 0);
+        let mut chunks = codes.chunks_exact(LANES);
+        let mut out = points.chunks_exact_mut(LANES);
+        for (c, p) in (&mut chunks).zip(&mut out) {
+            let v = _mm256_loadu_si256(c.as_ptr() as *const __m256i);
+            let mut axes = [[0u32; LANES]; 3];
+            let shifted = [v, _mm256_srli_epi64::<1>(v), _mm256_srli_epi64::<2>(v)];
+            for (axis, v) in axes.iter_mut().zip(shifted.iter()) {
+                let packed = _mm256_permutevar8x32_epi32(compact(*v), pack);
+                _mm_storeu_si128(
+                    axis.as_mut_ptr() as *mut __m128i,
+                    _mm256_castsi256_si128(packed),
+                );
+            }
+            for (i, point) in p.iter_mut().enumerate() {
+                *point = [axes[0][i], axes[1][i], axes[2][i]];
+            }
+        }
+        for (&code, point) in chunks.remainder().iter().zip(out.into_remainder()) {
+            let (x, y, z) = code.to_coords();
+            *point = [x, y, z];
+        }
+    }
+}
+
+/// interleaving with the BMI2 bit deposit and extract instructions.
+///
+/// They take a few cycles whatever the mask on Intel CPUs and on AMD since Zen 3,
+/// but are microcoded and much slower than the shifts and masks on older AMD CPUs,
+/// which get the AVX2 path instead.
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+mod bmi2 {
+    use core::arch::x86_64::{__cpuid, _pdep_u64, _pext_u64};
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    use super::AXES;
+    use crate::Morton3D;
+
+    /// whether the CPU supports BMI2 and runs `pdep` and `pext` fast, checked once.
+    pub(super) fn fast() -> bool {
+        // 0 until checked, then 1 if slow or missing and 2 if fast
+        static FAST: AtomicU8 = AtomicU8::new(0);
+        match FAST.load(Ordering::Relaxed) {
+            0 => {
+                let fast = std::is_x86_feature_detected!("bmi2") && {
+                    // SAFETY: cpuid is available on x86_64, `__cpuid` is safe on newer compilers
+                    #[allow(unused_unsafe)]
+                    let (vendor, info) = unsafe { (__cpuid(0), __cpuid(1)) };
+                    let mut name = [0; 12];
+                    name[..4].copy_from_slice(&vendor.ebx.to_le_bytes());
+                    name[4..8].copy_from_slice(&vendor.edx.to_le_bytes());
+                    name[8..].copy_from_slice(&vendor.ecx.to_le_bytes());
+                    !microcoded(&name, info.eax)
+                };
+                FAST.store(if fast { 2 } else { 1 }, Ordering::Relaxed);
+                fast
+            }
+            v => v == 2,
+        }
+    }
+
+    /// whether `pdep` and `pext` are microcoded on the CPU of this vendor and signature
+    /// (`eax` of cpuid leaf 1), i.e. it is an AMD or Hygon CPU before Zen 3 (family 19h).
+    pub(super) fn microcoded(vendor: &[u8; 12], signature: u32) -> bool {
+        let family = match signature >> 8 & 0xf {
+            0xf => 0xf + (signature >> 20 & 0xff),
+            base => base,
+        };
+        (vendor == b"AuthenticAMD" || vendor == b"HygonGenuine") && family < 0x19
+    }
+
+    /// see [`super::encode_batch`], `points` and `codes` have the same length.
+    #[target_feature(enable = "bmi2")]
+    pub(super) unsafe fn encode(points: &[[u32; 3]], codes: &mut [Morton3D]) {
+        for (&[x, y, z], code) in points.iter().zip(codes) {
+            let v = _pdep_u64(u64::from(x), AXES[0])
+                | _pdep_u64(u64::from(y), AXES[1])
+                | _pdep_u64(u64::from(z), AXES[2]);
+            *code = Morton3D::from_raw(v as usize);
+        }
+    }
+
+    /// see [`super::decode_batch`], `codes` and `points` have the same length.
+    #[target_feature(enable = "bmi2")]
+    pub(super) unsafe fn decode(codes: &[Morton3D], points: &mut [[u32; 3]]) {
+        for (&code, point) in codes.iter().zip(points) {
+            let v = code.raw() as u64;
+            *point = AXES.map(|mask| _pext_u64(v, mask) as u32);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_batch, encode_batch, wrapping_add_batch};
+    use crate::Morton3D;
+
+    #[test]
+    fn test_batch() {
+        let points: Vec<_> = (0..103u32)
+            .map(|i| [i.wrapping_mul(2_654_435_761), (i * 7919) % 4093, i << 14])
+            .collect();
+        for start in 0..points.len() {
+            let points = &points[start..];
+            let mut codes = vec![Morton3D::default(); points.len()];
+            encode_batch(points, &mut codes);
+            assert!(codes
+                .iter()
+                .zip(points)
+                .all(|(&v, &[x, y, z])| v == Morton3D::from_coords(x, y, z)));
+
+            for v in codes.iter_mut().step_by(3) {
+                v.set_flag();
+            }
+            let mut decoded = vec![[0; 3]; codes.len()];
+            decode_batch(&codes, &mut decoded);
+            assert!(decoded
+                .iter()
+                .zip(&codes)
+                .all(|(&[x, y, z], v)| (x, y, z) == v.to_coords()));
+        }
+    }
+
+    #[test]
+    fn test_wrapping_add_batch() {
+        let mut codes: Vec<_> = (0..103u32)
+            .map(|i| Morton3D::from_coords(i.wrapping_mul(2_654_435_761), (i * 7919) % 4093, i))
+            .collect();
+        for v in codes.iter_mut().step_by(5) {
+            v.set_flag();
+        }
+        let mut out = vec![Morton3D::default(); codes.len()];
+        for &offset in Morton3D::MOORE_OFFSETS.iter() {
+            for start in 0..8 {
+                let (codes, out) = (&codes[start..], &mut out[start..]);
+                wrapping_add_batch(codes, offset, out);
+                assert!(out
+                    .iter()
+                    .zip(codes)
+                    .all(|(&moved, &v)| moved == v.wrapping_add(offset)));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_face_neighbors_batch() {
+        let cells: Vec<_> = (0..50u32)
+            .map(|i| Morton3D::from_coords(i % 7, (i * 13) % 11, (i * 5) % 9))
+            .collect();
+        let mut out = vec![Morton3D::default(); 3];
+        super::face_neighbors_batch(&cells, &mut out);
+        assert_eq!(out.len(), 3 + 6 * cells.len());
+        for (k, group) in out[3..].chunks(cells.len()).enumerate() {
+            let offset = Morton3D::FACE_OFFSETS[k];
+            assert!(group
+                .iter()
+                .zip(&cells)
+                .all(|(&v, &cell)| v == cell.wrapping_add(offset)));
+        }
+        // same as the neighbors in the grid
+        for (i, &cell) in cells.iter().enumerate() {
+            let mut batch: Vec<_> = (0..6).map(|k| out[3 + k * cells.len() + i]).collect();
+            let mut expected: Vec<_> = cell.face_neighbors().collect();
+            batch.retain(|v| expected.contains(v));
+            batch.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(batch, expected);
+        }
+
+        super::face_neighbors_batch(&[], &mut out);
+        assert_eq!(out.len(), 3 + 6 * cells.len());
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    fn test_bmi2() {
+        if !std::is_x86_feature_detected!("bmi2") {
+            return;
+        }
+        let points: Vec<_> = (0..100u32)
+            .map(|i| [i.wrapping_mul(2_654_435_761), (i * 7919) % 4093, !i])
+            .collect();
+        let mut codes = vec![Morton3D::default(); points.len()];
+        // SAFETY: the CPU supports BMI2
+        unsafe { super::bmi2::encode(&points, &mut codes) };
+        assert!(codes
+            .iter()
+            .zip(&points)
+            .all(|(&v, &[x, y, z])| v == Morton3D::from_coords(x, y, z)));
+
+        codes[7].set_flag();
+        let mut decoded = vec![[0; 3]; codes.len()];
+        // SAFETY: the CPU supports BMI2
+        unsafe { super::bmi2::decode(&codes, &mut decoded) };
+        assert!(decoded
+            .iter()
+            .zip(&codes)
+            .all(|(&[x, y, z], v)| (x, y, z) == v.to_coords()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    fn test_bmi2_microcoded() {
+        use super::bmi2::microcoded;
+        // Zen 2, Zen 3, Hygon Dhyana, Excavator and Skylake
+        assert!(microcoded(b"AuthenticAMD", 0x0083_0f10));
+        assert!(!microcoded(b"AuthenticAMD", 0x00a2_0f10));
+        assert!(microcoded(b"HygonGenuine", 0x0090_0f01));
+        assert!(microcoded(b"AuthenticAMD", 0x0066_0f01));
+        assert!(!microcoded(b"GenuineIntel", 0x0005_06e3));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    fn test_avx2() {
+        if !std::is_x86_feature_detected!("avx2") {
+            return;
+        }
+        let points: Vec<_> = (0..103u32)
+            .map(|i| [i.wrapping_mul(2_654_435_761), (i * 7919) % 4093, !i])
+            .collect();
+        for start in 0..4 {
+            let points = &points[start..];
+            let mut codes = vec![Morton3D::default(); points.len()];
+            // SAFETY: the CPU supports AVX2
+            unsafe { super::avx2::encode(points, &mut codes) };
+            assert!(codes
+                .iter()
+                .zip(points)
+                .all(|(&v, &[x, y, z])| v == Morton3D::from_coords(x, y, z)));
+
+            codes[start].set_flag();
+            let mut decoded = vec![[0; 3]; codes.len()];
+            // SAFETY: the CPU supports AVX2
+            unsafe { super::avx2::decode(&codes, &mut decoded) };
+            assert!(decoded
+                .iter()
+                .zip(&codes)
+                .all(|(&[x, y, z], v)| (x, y, z) == v.to_coords()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "lengths differ")]
+    fn test_batch_lengths() {
+        encode_batch(&[[0; 3]; 5], &mut [Morton3D::default(); 4]);
+    }
+}
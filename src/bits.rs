@@ -0,0 +1,97 @@
+//! 3-way bit dilation primitives for each storage width.
+
+/// Spread the lower 21 bits of `v`, bit `i` is moved to bit `3 * i`.
+pub(crate) const fn expand_bits_u64(v: u32) -> u64 {
+    let mut x = (v as u64) & 0x1f_ffff;
+    x = (x | x << 32) & 0x001f_0000_0000_ffff;
+    x = (x | x << 16) & 0x001f_0000_ff00_00ff;
+    x = (x | x << 8) & 0x100f_00f0_0f00_f00f;
+    x = (x | x << 4) & 0x10c3_0c30_c30c_30c3;
+    x = (x | x << 2) & 0x1249_2492_4924_9249;
+    x
+}
+
+/// Gather every third bit of `v` into the lower 21 bits, bit `3 * i` is moved to bit `i`.
+pub(crate) const fn compact_bits_u64(v: u64) -> u32 {
+    let mut x = v & 0x1249_2492_4924_9249;
+    x = (x | x >> 2) & 0x10c3_0c30_c30c_30c3;
+    x = (x | x >> 4) & 0x100f_00f0_0f00_f00f;
+    x = (x | x >> 8) & 0x001f_0000_ff00_00ff;
+    x = (x | x >> 16) & 0x001f_0000_0000_ffff;
+    x = (x | x >> 32) & 0x1f_ffff;
+    x as u32
+}
+
+/// Spread the lower 10 bits of `v`, bit `i` is moved to bit `3 * i`.
+#[cfg(target_pointer_width = "32")]
+pub(crate) const fn expand_bits_u32(v: u32) -> u32 {
+    let mut x = v & 0x3ff;
+    x = (x | x << 16) & 0x0300_00ff;
+    x = (x | x << 8) & 0x0300_f00f;
+    x = (x | x << 4) & 0x030c_30c3;
+    x = (x | x << 2) & 0x0924_9249;
+    x
+}
+
+/// Gather every third bit of `v` into the lower 10 bits, bit `3 * i` is moved to bit `i`.
+#[cfg(target_pointer_width = "32")]
+pub(crate) const fn compact_bits_u32(v: u32) -> u32 {
+    let mut x = v & 0x0924_9249;
+    x = (x | x >> 2) & 0x030c_30c3;
+    x = (x | x >> 4) & 0x0300_f00f;
+    x = (x | x >> 8) & 0x0300_00ff;
+    x = (x | x >> 16) & 0x3ff;
+    x
+}
+
+/// Spread the lower 16 bits of `v`, bit `i` is moved to bit `4 * i`.
+pub(crate) const fn expand_bits_4d_u64(v: u32) -> u64 {
+    let mut x = (v as u64) & 0xffff;
+    x = (x | x << 24) & 0xff_0000_00ff;
+    x = (x | x << 12) & 0xf_000f_000f_000f;
+    x = (x | x << 6) & 0x303_0303_0303_0303;
+    x = (x | x << 3) & 0x1111_1111_1111_1111;
+    x
+}
+
+/// Gather every fourth bit of `v` into the lower 16 bits, bit `4 * i` is moved to bit `i`.
+pub(crate) const fn compact_bits_4d_u64(v: u64) -> u32 {
+    let mut x = v & 0x1111_1111_1111_1111;
+    x = (x | x >> 3) & 0x303_0303_0303_0303;
+    x = (x | x >> 6) & 0xf_000f_000f_000f;
+    x = (x | x >> 12) & 0xff_0000_00ff;
+    x = (x | x >> 24) & 0xffff;
+    x as u32
+}
+
+/// Table spreading the bits of a byte `d`-way, bit `i` is moved to bit `d * i`.
+#[cfg(feature = "lut")]
+pub(crate) const fn expand_lut(d: usize) -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut b = 0;
+    while b < 256 {
+        let mut i = 0;
+        while i < 8 {
+            table[b] |= ((b as u64 >> i) & 1) << (d * i);
+            i += 1;
+        }
+        b += 1;
+    }
+    table
+}
+
+/// Table gathering every `d`-th bit of a byte, bit `d * i` is moved to bit `i`.
+#[cfg(feature = "lut")]
+pub(crate) const fn compact_lut(d: usize) -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut b = 0;
+    while b < 256 {
+        let mut i = 0;
+        while d * i < 8 {
+            table[b] |= (((b >> (d * i)) & 1) << i) as u8;
+            i += 1;
+        }
+        b += 1;
+    }
+    table
+}
@@ -0,0 +1,109 @@
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::MortonKey;
+
+/// candidate pairs of overlapping objects given by the smallest cells containing them
+/// (see [`MortonKey::enclosing`]), a broad phase for collision detection.
+///
+/// `sorted` must be sorted. Two cells overlap only if one contains the other, so a single sweep
+/// in preorder keeping the chain of open ancestors finds all pairs of overlapping cells,
+/// huge objects being paired with everything below them and small ones only with their neighbors
+/// in the same cell. Pairs `(i, j)` are indices into `sorted` with `i < j`, grouped by `j`.
+/// Objects in different cells never overlap, so every overlapping pair is a candidate.
+pub fn broad_phase(sorted: &[MortonKey]) -> BroadPhase<'_> {
+    BroadPhase {
+        sorted,
+        current: 0,
+        open: Vec::new(),
+        pos: 0,
+    }
+}
+
+/// Iterator over candidate pairs of overlapping objects, see [`broad_phase`].
+#[derive(Debug, Clone)]
+pub struct BroadPhase<'a> {
+    sorted: &'a [MortonKey],
+    /// index of the object paired with the open ones
+    current: usize,
+    /// objects containing the current one, coarsest first
+    open: Vec<usize>,
+    /// next open object to pair with the current one
+    pos: usize,
+}
+
+impl Iterator for BroadPhase<'_> {
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        loop {
+            let &key = self.sorted.get(self.current)?;
+            if let Some(&i) = self.open.get(self.pos) {
+                self.pos += 1;
+                return Some((i, self.current));
+            }
+            self.open.push(self.current);
+            self.current += 1;
+            let &next = self.sorted.get(self.current)?;
+            debug_assert!(key <= next, "keys aren't sorted");
+            while let Some(&top) = self.open.last() {
+                let open = self.sorted[top];
+                if open == next || open.is_ancestor_of(next) {
+                    break;
+                }
+                self.open.pop();
+            }
+            self.pos = 0;
+        }
+    }
+}
+
+impl FusedIterator for BroadPhase<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::broad_phase;
+    use crate::{Morton3D, MortonBox, MortonKey};
+
+    #[test]
+    fn test_broad_phase() {
+        let mut boxes: Vec<_> = (0..300u32)
+            .map(|i| {
+                let extent = if i % 50 == 0 { 300 } else { (i * 7) % 13 };
+                let (x, y, z) = ((i * 97) % 500, (i * 61) % 500, (i * 13) % 500);
+                MortonBox::new(
+                    Morton3D::from_coords(x, y, z),
+                    Morton3D::from_coords(x + extent, y + extent, z + extent),
+                )
+            })
+            .collect();
+        boxes.sort_by_key(|&b| MortonKey::enclosing(b));
+        let keys: Vec<_> = boxes.iter().map(|&b| MortonKey::enclosing(b)).collect();
+
+        let mut pairs: Vec<_> = broad_phase(&keys).collect();
+        assert!(pairs.iter().all(|&(i, j)| i < j));
+        pairs.sort_unstable();
+        let mut expected = Vec::new();
+        for j in 0..keys.len() {
+            for i in 0..j {
+                if keys[i] == keys[j] || keys[i].is_ancestor_of(keys[j]) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        expected.sort_unstable();
+        assert_eq!(pairs, expected);
+
+        // no overlapping pair is missed
+        for j in 0..boxes.len() {
+            for i in 0..j {
+                if boxes[i].intersection(boxes[j]).is_some() {
+                    assert!(pairs.binary_search(&(i, j)).is_ok());
+                }
+            }
+        }
+
+        assert_eq!(broad_phase(&[]).count(), 0);
+        let same = [MortonKey::ROOT.child(3); 4];
+        assert_eq!(broad_phase(&same).count(), 6);
+    }
+}
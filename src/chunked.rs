@@ -0,0 +1,155 @@
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::vec::Vec;
+
+use crate::Morton3D;
+
+/// Sparse voxel storage made of dense Morton-ordered bricks, allocated on first write.
+///
+/// A brick covers `2^brick_depth` cells along each axis. As the code of a cell is the code of its brick
+/// followed by `3 * brick_depth` bits, the brick of a cell and its index in the brick
+/// are the upper and lower bits of its code. Cells of bricks never written read as the default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkedVoxels<T> {
+    brick_depth: u32,
+    default: T,
+    bricks: BTreeMap<Morton3D, Vec<T>>,
+}
+
+impl<T: Clone> ChunkedVoxels<T> {
+    /// create empty storage of bricks `2^brick_depth` cells wide, reading as `default`.
+    ///
+    /// panic if `brick_depth` exceeds `Morton3D::MAX_DEPTH`.
+    pub fn new(brick_depth: u32, default: T) -> Self {
+        assert!(brick_depth <= Morton3D::MAX_DEPTH, "depth out of range");
+        Self {
+            brick_depth,
+            default,
+            bricks: BTreeMap::new(),
+        }
+    }
+
+    pub fn brick_depth(&self) -> u32 {
+        self.brick_depth
+    }
+    /// number of cells of a brick
+    pub fn brick_len(&self) -> usize {
+        1 << (3 * self.brick_depth)
+    }
+    /// number of allocated bricks
+    pub fn brick_count(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// code of the brick and index in it of the cell, flag is ignored
+    fn split(&self, code: Morton3D) -> (Morton3D, usize) {
+        let mut code = code;
+        code.unset_flag();
+        let bits = usize::from(code);
+        let shift = 3 * self.brick_depth;
+        (Morton3D::from(bits >> shift), bits & ((1 << shift) - 1))
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> &T {
+        self.get_morton(Morton3D::from_coords(x, y, z))
+    }
+    pub fn get_morton(&self, code: Morton3D) -> &T {
+        let (brick, i) = self.split(code);
+        self.bricks
+            .get(&brick)
+            .map_or(&self.default, |cells| &cells[i])
+    }
+
+    /// value of the cell for writing, its brick is allocated if needed.
+    pub fn get_mut(&mut self, x: u32, y: u32, z: u32) -> &mut T {
+        self.get_morton_mut(Morton3D::from_coords(x, y, z))
+    }
+    /// value of the cell for writing, its brick is allocated if needed.
+    pub fn get_morton_mut(&mut self, code: Morton3D) -> &mut T {
+        let (brick, i) = self.split(code);
+        let (len, default) = (self.brick_len(), &self.default);
+        let cells = self
+            .bricks
+            .entry(brick)
+            .or_insert_with(|| alloc::vec![default.clone(); len]);
+        &mut cells[i]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, z: u32, value: T) {
+        *self.get_mut(x, y, z) = value;
+    }
+    pub fn set_morton(&mut self, code: Morton3D, value: T) {
+        *self.get_morton_mut(code) = value;
+    }
+
+    /// cells of a brick in Morton order, `None` if it is not allocated.
+    pub fn brick(&self, brick: Morton3D) -> Option<&[T]> {
+        self.bricks.get(&brick).map(Vec::as_slice)
+    }
+    /// free a brick, its cells read as the default value again.
+    pub fn remove_brick(&mut self, brick: Morton3D) -> Option<Vec<T>> {
+        self.bricks.remove(&brick)
+    }
+
+    /// iterate over the allocated bricks with their codes, in Morton order.
+    pub fn bricks(&self) -> Bricks<'_, T> {
+        Bricks {
+            iter: self.bricks.iter(),
+        }
+    }
+}
+
+/// Iterator over the allocated bricks of a [`ChunkedVoxels`], in Morton order.
+#[derive(Debug, Clone)]
+pub struct Bricks<'a, T> {
+    iter: btree_map::Iter<'a, Morton3D, Vec<T>>,
+}
+
+impl<'a, T> Iterator for Bricks<'a, T> {
+    type Item = (Morton3D, &'a [T]);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(&code, cells)| (code, cells.as_slice()))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Bricks<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::ChunkedVoxels;
+    use crate::Morton3D;
+
+    #[test]
+    fn test_chunked() {
+        let mut voxels = ChunkedVoxels::new(4, 0u8);
+        assert_eq!(voxels.brick_len(), 4096);
+        assert_eq!(*voxels.get(100, 200, 300), 0);
+        assert_eq!(voxels.brick_count(), 0);
+
+        voxels.set(100, 200, 300, 5);
+        voxels.set(101, 200, 300, 6);
+        voxels.set(0, 0, 0, 1);
+        assert_eq!(voxels.brick_count(), 2);
+        assert_eq!(*voxels.get(100, 200, 300), 5);
+        assert_eq!(*voxels.get_morton(Morton3D::from_coords(101, 200, 300)), 6);
+        assert_eq!(*voxels.get(102, 200, 300), 0);
+        *voxels.get_mut(0, 0, 0) += 1;
+        assert_eq!(*voxels.get(0, 0, 0), 2);
+
+        // the brick is the cell at depth MAX_DEPTH - 4
+        let brick = Morton3D::from_coords(100 / 16, 200 / 16, 300 / 16);
+        let cells = voxels.brick(brick).unwrap();
+        let local = Morton3D::from_coords(100 % 16, 200 % 16, 300 % 16);
+        assert_eq!(cells[usize::from(local)], 5);
+        let bricks: Vec<_> = voxels.bricks().map(|(code, _)| code).collect();
+        assert_eq!(bricks, [Morton3D::default(), brick]);
+
+        assert!(voxels.remove_brick(brick).is_some());
+        assert_eq!(*voxels.get(100, 200, 300), 0);
+        assert_eq!(voxels.bricks().len(), 1);
+    }
+}
@@ -0,0 +1,461 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BinaryHeap, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::cmp::Reverse;
+use core::iter::FusedIterator;
+
+use crate::{Morton3D, MortonBox, MortonKey, MortonRange};
+
+/// Region of space which can be covered by octree cells, see `cover`.
+///
+/// Positions are in units of cells at [`MortonKey::MAX_DEPTH`],
+/// i.e. the cell `(x, y, z)` spans `[x, x + 1) * [y, y + 1) * [z, z + 1)`.
+pub trait Region {
+    /// whether the whole cell is inside of the region.
+    fn contains_key(&self, key: MortonKey) -> bool;
+    /// whether the cell and the region overlap.
+    fn intersects_key(&self, key: MortonKey) -> bool;
+}
+
+impl<R: Region + ?Sized> Region for &R {
+    fn contains_key(&self, key: MortonKey) -> bool {
+        (**self).contains_key(key)
+    }
+    fn intersects_key(&self, key: MortonKey) -> bool {
+        (**self).intersects_key(key)
+    }
+}
+
+impl Region for MortonBox {
+    fn contains_key(&self, key: MortonKey) -> bool {
+        let bounds = key.bounds();
+        self.contains(bounds.min()) && self.contains(bounds.max())
+    }
+    fn intersects_key(&self, key: MortonKey) -> bool {
+        self.intersection(key.bounds()).is_some()
+    }
+}
+
+/// Ball given by its center and radius, see [`Region`] for the units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: [f64; 3],
+    pub radius: f64,
+}
+
+impl Sphere {
+    pub fn new(center: [f64; 3], radius: f64) -> Self {
+        Self { center, radius }
+    }
+
+    /// lower and upper corners of the cell along each axis
+    fn span(key: MortonKey) -> [(f64, f64); 3] {
+        let (x, y, z) = key.anchor().to_coords();
+        let size = f64::from(key.size());
+        [x, y, z].map(|v| (f64::from(v), f64::from(v) + size))
+    }
+}
+
+impl Region for Sphere {
+    fn contains_key(&self, key: MortonKey) -> bool {
+        // farthest corner of the cell must be inside
+        let mut d = 0.0;
+        for (&c, (lo, hi)) in self.center.iter().zip(Self::span(key)) {
+            let v = f64::max(c - lo, hi - c);
+            d += v * v;
+        }
+        d <= self.radius * self.radius
+    }
+    fn intersects_key(&self, key: MortonKey) -> bool {
+        // nearest point of the cell must be inside
+        let mut d = 0.0;
+        for (&c, (lo, hi)) in self.center.iter().zip(Self::span(key)) {
+            let v = c - c.max(lo).min(hi);
+            d += v * v;
+        }
+        d <= self.radius * self.radius
+    }
+}
+
+/// Half-space of the points `p` such that `normal . p + offset >= 0`, see [`Region`] for the units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: [f64; 3],
+    pub offset: f64,
+}
+
+impl Plane {
+    pub fn new(normal: [f64; 3], offset: f64) -> Self {
+        Self { normal, offset }
+    }
+
+    /// `normal . p + offset` over the corners of the cell, as `(min, max)`
+    fn eval(&self, key: MortonKey, unit: f64) -> (f64, f64) {
+        let (mut min, mut max) = (self.offset * unit, self.offset * unit);
+        for (&n, (lo, hi)) in self.normal.iter().zip(Sphere::span(key)) {
+            let (a, b) = (n * lo, n * hi);
+            min += a.min(b);
+            max += a.max(b);
+        }
+        (min, max)
+    }
+}
+
+/// Convex region given by the intersection of half-spaces, e.g. a camera frustum.
+///
+/// Intersection is conservative: a cell is only rejected if it is fully outside of one of the planes,
+/// so cells near the edges of the region may be reported even though they are outside of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convex<'a> {
+    planes: &'a [Plane],
+    /// length of the unit of `planes` in cells at `MAX_DEPTH`
+    unit: f64,
+}
+
+impl<'a> Convex<'a> {
+    pub fn new(planes: &'a [Plane]) -> Self {
+        Self { planes, unit: 1.0 }
+    }
+
+    pub fn planes(&self) -> &'a [Plane] {
+        self.planes
+    }
+}
+
+impl Region for Convex<'_> {
+    fn contains_key(&self, key: MortonKey) -> bool {
+        self.planes.iter().all(|p| p.eval(key, self.unit).0 >= 0.0)
+    }
+    fn intersects_key(&self, key: MortonKey) -> bool {
+        self.planes.iter().all(|p| p.eval(key, self.unit).1 >= 0.0)
+    }
+}
+
+/// Iterator over the code ranges of the cells at a depth intersecting a [`Region`], in order.
+///
+/// The octree is walked from the root, skipping cells outside of the region
+/// and taking cells inside of it as a whole, adjacent ranges are merged.
+/// Codes are the ones at the depth, i.e. the coordinates are in `0..2^depth`.
+#[derive(Debug, Clone)]
+pub struct RegionRanges<R> {
+    region: R,
+    depth: u32,
+    next: Option<MortonKey>,
+    pending: Option<MortonRange>,
+}
+
+impl<R: Region> RegionRanges<R> {
+    /// ranges of the cells at `depth` intersecting `region`.
+    ///
+    /// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+    pub fn new(region: R, depth: u32) -> Self {
+        assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+        Self {
+            region,
+            depth,
+            next: Some(MortonKey::ROOT),
+            pending: None,
+        }
+    }
+
+    /// descendants at `depth` of the next cell to take, not merged
+    fn next_piece(&mut self) -> Option<MortonRange> {
+        loop {
+            let key = self.next?;
+            if !self.region.intersects_key(key) {
+                self.next = key.next_subtree(MortonKey::ROOT);
+                continue;
+            }
+            if key.depth() == self.depth || self.region.contains_key(key) {
+                self.next = key.next_subtree(MortonKey::ROOT);
+                return Some(key.descendants_range(self.depth).into());
+            }
+            self.next = Some(key.child(0));
+        }
+    }
+}
+
+impl<R: Region> Iterator for RegionRanges<R> {
+    type Item = MortonRange;
+    fn next(&mut self) -> Option<MortonRange> {
+        let mut range = self.pending.take().or_else(|| self.next_piece())?;
+        while let Some(piece) = self.next_piece() {
+            match range.merge(piece) {
+                Some(merged) => range = merged,
+                None => {
+                    self.pending = Some(piece);
+                    break;
+                }
+            }
+        }
+        Some(range)
+    }
+}
+
+impl<R: Region> FusedIterator for RegionRanges<R> {}
+
+/// Iterator over the codes of the cells at a depth intersecting a [`Region`], in order,
+/// see [`RegionRanges`].
+#[derive(Debug, Clone)]
+pub struct RegionCells<R> {
+    ranges: RegionRanges<R>,
+    current: Option<(Morton3D, Morton3D)>,
+}
+
+impl<R: Region> RegionCells<R> {
+    /// codes of the cells at `depth` intersecting `region`.
+    ///
+    /// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+    pub fn new(region: R, depth: u32) -> Self {
+        Self {
+            ranges: RegionRanges::new(region, depth),
+            current: None,
+        }
+    }
+}
+
+impl<R: Region> Iterator for RegionCells<R> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        let (v, end) = match self.current {
+            Some(current) => current,
+            None => {
+                let range = self.ranges.next()?;
+                (range.start(), range.end())
+            }
+        };
+        self.current = if v == end {
+            None
+        } else {
+            Some((Morton3D::from_raw(v.raw() + 1), end))
+        };
+        Some(v)
+    }
+}
+
+impl<R: Region> FusedIterator for RegionCells<R> {}
+
+/// sphere of cells at `depth` in units of cells at `MAX_DEPTH`
+fn sphere_at(center: [f64; 3], radius: f64, depth: u32) -> Sphere {
+    assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+    let scale = f64::from(1u32 << (MortonKey::MAX_DEPTH - depth));
+    Sphere::new(center.map(|c| c * scale), radius * scale)
+}
+
+/// codes of the cells at `depth` intersecting the sphere, in order.
+///
+/// `center` and `radius` are in units of cells at `depth`.
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn cover_sphere(center: [f64; 3], radius: f64, depth: u32) -> RegionCells<Sphere> {
+    RegionCells::new(sphere_at(center, radius, depth), depth)
+}
+
+/// contiguous code ranges of the cells at `depth` intersecting the sphere, in order,
+/// see [`cover_sphere`].
+pub fn cover_sphere_ranges(center: [f64; 3], radius: f64, depth: u32) -> RegionRanges<Sphere> {
+    RegionRanges::new(sphere_at(center, radius, depth), depth)
+}
+
+/// contiguous code ranges of the cells at `depth` intersecting the convex region bounded by `planes`,
+/// in order.
+///
+/// `planes` are in units of cells at `depth`, see [`Convex`] for the precision.
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn cover_convex(planes: &[Plane], depth: u32) -> RegionRanges<Convex<'_>> {
+    assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+    let unit = f64::from(1u32 << (MortonKey::MAX_DEPTH - depth));
+    RegionRanges::new(Convex { planes, unit }, depth)
+}
+
+/// cover `region` with at most `max_cells` cells of mixed depths down to `max_depth`,
+/// return the sorted keys, which don't overlap each other.
+///
+/// Like the `RegionCoverer` of S2, the largest cells partially covering the region are subdivided first,
+/// as long as the budget allows it. So the cover is exact when the budget is large enough,
+/// and otherwise contains cells sticking out of the region.
+/// At least one cell is returned if the region is not empty, even if `max_cells` is 0.
+#[cfg(feature = "alloc")]
+pub fn cover<R: Region + ?Sized>(region: &R, max_cells: usize, max_depth: u32) -> Vec<MortonKey> {
+    let max_depth = max_depth.min(MortonKey::MAX_DEPTH);
+    let mut result = Vec::new();
+    if !region.intersects_key(MortonKey::ROOT) {
+        return result;
+    }
+    // partially covered cells, coarsest first
+    let mut candidates = BinaryHeap::new();
+    let push = |key: MortonKey, result: &mut Vec<_>, candidates: &mut BinaryHeap<_>| {
+        if key.depth() >= max_depth || region.contains_key(key) {
+            result.push(key);
+        } else {
+            candidates.push((Reverse(key.depth()), key));
+        }
+    };
+    push(MortonKey::ROOT, &mut result, &mut candidates);
+    while let Some((_, key)) = candidates.pop() {
+        let mut children = key.children();
+        let mut len = 0;
+        for i in 0..8 {
+            if region.intersects_key(children[i]) {
+                children[len] = children[i];
+                len += 1;
+            }
+        }
+        let count = result.len() + candidates.len();
+        if count + len > max_cells.max(1) {
+            result.push(key);
+            continue;
+        }
+        for &child in &children[..len] {
+            push(child, &mut result, &mut candidates);
+        }
+    }
+    result.sort_unstable();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cover_convex, cover_sphere, cover_sphere_ranges, Plane};
+    use crate::Morton3D;
+    #[cfg(feature = "alloc")]
+    use {
+        super::{cover, Region, Sphere},
+        crate::{MortonBox, MortonKey},
+    };
+
+    #[cfg(feature = "alloc")]
+    fn check(region: &dyn Region, keys: &[MortonKey], max_cells: usize) {
+        assert!(!keys.is_empty() && keys.len() <= max_cells.max(1));
+        assert!(keys
+            .windows(2)
+            .all(|w| w[0] < w[1] && !w[0].is_ancestor_of(w[1])));
+        assert!(keys.iter().all(|&k| region.intersects_key(k)));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_cover_box() {
+        let region = MortonBox::new(
+            Morton3D::from_coords(3, 9, 2),
+            Morton3D::from_coords(7, 12, 10),
+        );
+        let exact = cover(&region, usize::MAX, MortonKey::MAX_DEPTH);
+        check(&region, &exact, usize::MAX);
+        assert!(exact.iter().all(|&k| region.contains_key(k)));
+        let count: u64 = exact.iter().map(|k| u64::from(k.size()).pow(3)).sum();
+        assert_eq!(count, 5 * 4 * 9);
+
+        for max_cells in [0, 1, 4, 8, 20] {
+            let keys = cover(&region, max_cells, MortonKey::MAX_DEPTH);
+            check(&region, &keys, max_cells);
+            // every cell of the region is covered
+            for v in exact.iter().flat_map(|k| k.bounds().ranges()) {
+                assert!(keys.iter().any(|k| k.bounds().contains(v.start())));
+                assert!(keys.iter().any(|k| k.bounds().contains(v.end())));
+            }
+        }
+        let single = cover(&region, 1, MortonKey::MAX_DEPTH);
+        assert_eq!(single[0].depth(), MortonKey::MAX_DEPTH - 4);
+
+        let coarse = cover(&region, usize::MAX, MortonKey::MAX_DEPTH - 2);
+        assert!(coarse.iter().all(|k| k.depth() <= MortonKey::MAX_DEPTH - 2));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_cover_sphere() {
+        let sphere = Sphere::new([100.0, 200.0, 50.5], 20.0);
+        assert!(cover(
+            &Sphere::new([-10.0, 0.0, 0.0], 5.0),
+            8,
+            MortonKey::MAX_DEPTH
+        )
+        .is_empty());
+        for max_cells in [1, 8, 50, 500] {
+            let keys = cover(&sphere, max_cells, MortonKey::MAX_DEPTH);
+            check(&sphere, &keys, max_cells);
+            for p in [
+                (100, 200, 50),
+                (119, 200, 50),
+                (100, 181, 50),
+                (90, 210, 60),
+            ] {
+                let leaf = MortonKey::new(Morton3D::from(p), MortonKey::MAX_DEPTH);
+                assert!(keys.iter().any(|&k| k == leaf || k.is_ancestor_of(leaf)));
+            }
+        }
+        let leaf = MortonKey::new(Morton3D::from_coords(100, 200, 50), MortonKey::MAX_DEPTH);
+        assert!(sphere.contains_key(leaf));
+        assert!(!sphere.contains_key(MortonKey::ROOT) && sphere.intersects_key(MortonKey::ROOT));
+    }
+
+    #[test]
+    fn test_sphere_cells() {
+        let inside = |(x, y, z): (u32, u32, u32)| {
+            // nearest point of the cell to the center
+            let d = |v: u32, c: f64| c - c.max(f64::from(v)).min(f64::from(v + 1));
+            let (dx, dy, dz) = (d(x, 10.5), d(y, 7.0), d(z, 12.25));
+            dx * dx + dy * dy + dz * dz <= 6.0 * 6.0
+        };
+        let depth = 5;
+        let mut expected: Vec<_> = (0..32)
+            .flat_map(|x| (0..32).flat_map(move |y| (0..32).map(move |z| (x, y, z))))
+            .filter(|&v| inside(v))
+            .map(Morton3D::from)
+            .collect();
+        expected.sort();
+        let cells: Vec<_> = cover_sphere([10.5, 7.0, 12.25], 6.0, depth).collect();
+        assert_eq!(cells, expected);
+
+        let ranges: Vec<_> = cover_sphere_ranges([10.5, 7.0, 12.25], 6.0, depth).collect();
+        assert!(ranges
+            .windows(2)
+            .all(|w| w[0].merge(w[1]).is_none() && w[0] < w[1]));
+        let count: usize = ranges.iter().map(|r| r.len()).sum();
+        assert_eq!(count, expected.len());
+        assert!(expected
+            .iter()
+            .all(|&v| ranges.iter().any(|r| r.contains(v))));
+
+        assert_eq!(cover_sphere([-5.0, 0.0, 0.0], 1.0, depth).count(), 0);
+        assert_eq!(cover_sphere([0.5, 0.5, 0.5], 0.1, 0).count(), 1);
+    }
+
+    #[test]
+    fn test_cover_convex() {
+        // x + y <= 20, z >= 3.5 and a slanted plane x - 2z + 10 >= 0
+        let planes = [
+            Plane::new([-1.0, -1.0, 0.0], 20.0),
+            Plane::new([0.0, 0.0, 1.0], -3.5),
+            Plane::new([1.0, 0.0, -2.0], 10.0),
+        ];
+        let outside = |(x, y, z): (u32, u32, u32)| {
+            planes.iter().any(|p| {
+                let corner = |v: u32, n: f64| f64::from(if n > 0.0 { v + 1 } else { v });
+                let [nx, ny, nz] = p.normal;
+                nx * corner(x, nx) + ny * corner(y, ny) + nz * corner(z, nz) + p.offset < 0.0
+            })
+        };
+        let depth = 5;
+        let mut expected: Vec<_> = (0..32)
+            .flat_map(|x| (0..32).flat_map(move |y| (0..32).map(move |z| (x, y, z))))
+            .filter(|&v| !outside(v))
+            .map(Morton3D::from)
+            .collect();
+        expected.sort();
+
+        let ranges: Vec<_> = cover_convex(&planes, depth).collect();
+        assert!(ranges
+            .windows(2)
+            .all(|w| w[0].merge(w[1]).is_none() && w[0] < w[1]));
+        let count: usize = ranges.iter().map(|r| r.len()).sum();
+        assert_eq!(count, expected.len());
+        assert!(expected
+            .iter()
+            .all(|&v| ranges.iter().any(|r| r.contains(v))));
+
+        assert_eq!(cover_convex(&[], 1).count(), 1);
+        let empty = [Plane::new([1.0, 0.0, 0.0], -100.0)];
+        assert_eq!(cover_convex(&empty, depth).count(), 0);
+    }
+}
@@ -0,0 +1,150 @@
+use core::iter::FusedIterator;
+
+use crate::{MortonND, MortonStorage};
+
+/// Iterator over the codes of a section of the curve, without flag, in order.
+///
+/// `ExactSizeIterator` is implemented for storages which never hold more codes than `usize`,
+/// i.e. `u16`, `u32` and `usize`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CurveIter<const D: usize, S: MortonStorage = usize> {
+    /// raw code of the next front item
+    front: S,
+    /// raw code after the next back item
+    back: S,
+}
+
+/// Implement curve iteration for storage `$t`.
+macro_rules! curve {
+    ($t:ty) => {
+        impl<const D: usize> MortonND<D, $t> {
+            /// iterate over the `2^(D * depth)` cells of the full level `depth`, in curve order.
+            ///
+            /// Codes are the ones at `depth`, i.e. the coordinates are in `0..2^depth`.
+            /// panic if `depth` exceeds `MAX_DEPTH`.
+            pub fn iter_depth(depth: u32) -> CurveIter<D, $t> {
+                assert!(depth <= Self::MAX_DEPTH, "depth out of range");
+                CurveIter {
+                    front: 0,
+                    back: 1 << (D as u32 * depth),
+                }
+            }
+
+            /// iterate over the codes from `start` (inclusive) to `end` (exclusive) along the curve.
+            ///
+            /// Flags are masked, the iterator is empty if `start` is not below `end`.
+            pub fn iter_between(start: Self, end: Self) -> CurveIter<D, $t> {
+                let (mut start, mut end) = (start, end);
+                start.unset_flag();
+                end.unset_flag();
+                let (front, back) = (start.raw(), end.raw());
+                CurveIter {
+                    front,
+                    back: back.max(front),
+                }
+            }
+        }
+
+        impl<const D: usize> Iterator for CurveIter<D, $t> {
+            type Item = MortonND<D, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.front == self.back {
+                    return None;
+                }
+                self.front += 1;
+                Some(MortonND::<D, $t>::from_raw(self.front - 1))
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.back - self.front;
+                if len as u128 <= usize::MAX as u128 {
+                    (len as usize, Some(len as usize))
+                } else {
+                    (usize::MAX, None)
+                }
+            }
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                if n as u128 >= (self.back - self.front) as u128 {
+                    self.front = self.back;
+                    return None;
+                }
+                self.front += n as $t;
+                self.next()
+            }
+        }
+
+        impl<const D: usize> DoubleEndedIterator for CurveIter<D, $t> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                if self.front == self.back {
+                    return None;
+                }
+                self.back -= 1;
+                Some(MortonND::<D, $t>::from_raw(self.back))
+            }
+        }
+
+        impl<const D: usize> FusedIterator for CurveIter<D, $t> {}
+    };
+}
+
+curve!(u16);
+curve!(u32);
+curve!(u64);
+curve!(u128);
+curve!(usize);
+
+impl<const D: usize> ExactSizeIterator for CurveIter<D, u16> {}
+impl<const D: usize> ExactSizeIterator for CurveIter<D, u32> {}
+impl<const D: usize> ExactSizeIterator for CurveIter<D, usize> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Morton2D, Morton3D, Morton3D128, Morton3D16};
+
+    #[test]
+    fn test_iter_depth() {
+        assert_eq!(
+            Morton3D::iter_depth(0).collect::<Vec<_>>(),
+            [Morton3D::default()]
+        );
+        let level = Morton3D::iter_depth(2);
+        assert_eq!(level.len(), 64);
+        let coords: Vec<_> = level.map(Morton3D::to_coords).collect();
+        assert!(coords.iter().all(|&(x, y, z)| x < 4 && y < 4 && z < 4));
+        assert_eq!(coords[..3], [(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert_eq!(coords[63], (3, 3, 3));
+
+        assert_eq!(Morton2D::iter_depth(3).len(), 64);
+        assert_eq!(Morton3D16::iter_depth(5).len(), 1 << 15);
+        assert_eq!(
+            Morton3D16::iter_depth(5).nth((1 << 15) - 1),
+            Some(Morton3D16::from_coords(31, 31, 31))
+        );
+        assert_eq!(
+            Morton3D128::iter_depth(Morton3D128::MAX_DEPTH).size_hint(),
+            (usize::MAX, None)
+        );
+    }
+
+    #[test]
+    fn test_iter_between() {
+        let mut a = Morton3D::from_coords(1, 0, 0);
+        a.set_flag();
+        let b = Morton3D::from_coords(1, 1, 1);
+        let mut iter = Morton3D::iter_between(a, b);
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.next().map(usize::from), Some(1));
+        assert_eq!(iter.next_back().map(usize::from), Some(6));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.nth(1).map(usize::from), Some(3));
+        assert_eq!(
+            iter.clone().rev().map(usize::from).collect::<Vec<_>>(),
+            [5, 4]
+        );
+        assert_eq!(iter.nth(5), None);
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(Morton3D::iter_between(b, a).count(), 0);
+        assert_eq!(Morton3D::iter_between(b, b).len(), 0);
+        assert!(Morton3D::iter_between(a, b).all(|v| !v.is_flag_set()));
+    }
+}
@@ -0,0 +1,150 @@
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::bits::{compact_bits_u64, expand_bits_u64};
+
+/// Mask of the bits used by [`Dilated21`].
+const MASK: u64 = 0x1249_2492_4924_9249;
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// One axis of 3-dimension morton code in dilated form (21-bit value, stored in every third bit).
+///
+/// Arithmetic and comparison work directly on the dilated representation,
+/// so a component of [`Morton3D`](crate::Morton3D) can be manipulated without compacting.
+/// Like primitive integers, `+` and `-` panic on overflow in debug build and wrap in release build.
+pub struct Dilated21(u64);
+
+impl Dilated21 {
+    pub const MIN: Self = Self(0);
+    pub const MAX: Self = Self(MASK);
+
+    /// dilate lower 21 bits of `v`
+    pub const fn new(v: u32) -> Self {
+        Self(expand_bits_u64(v))
+    }
+
+    /// build from dilated bits, bits not in the dilated positions are discarded
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw & MASK)
+    }
+
+    /// dilated bits
+    pub const fn raw(self) -> u64 {
+        self.0
+    }
+
+    /// undilated value
+    pub const fn get(self) -> u32 {
+        compact_bits_u64(self.0)
+    }
+
+    pub const fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let (v, overflow) = (self.0 | !MASK).overflowing_add(rhs.0);
+        (Self(v & MASK), overflow)
+    }
+    pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let (v, overflow) = self.0.overflowing_sub(rhs.0);
+        (Self(v & MASK), overflow)
+    }
+
+    pub const fn wrapping_add(self, rhs: Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+    pub const fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_add(rhs) {
+            (v, false) => Some(v),
+            (_, true) => None,
+        }
+    }
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (v, false) => Some(v),
+            (_, true) => None,
+        }
+    }
+}
+
+impl Add for Dilated21 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let (v, overflow) = self.overflowing_add(rhs);
+        debug_assert!(!overflow, "attempt to add with overflow");
+        v
+    }
+}
+
+impl Sub for Dilated21 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        let (v, overflow) = self.overflowing_sub(rhs);
+        debug_assert!(!overflow, "attempt to subtract with overflow");
+        v
+    }
+}
+
+impl AddAssign for Dilated21 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl SubAssign for Dilated21 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs
+    }
+}
+
+impl From<u32> for Dilated21 {
+    fn from(v: u32) -> Self {
+        Self::new(v)
+    }
+}
+
+impl From<Dilated21> for u32 {
+    fn from(v: Dilated21) -> Self {
+        v.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dilated21;
+
+    #[test]
+    fn test_new_get() {
+        assert_eq!(Dilated21::new(0b101).raw(), 0b001_000_001);
+        assert_eq!(Dilated21::new(u32::MAX), Dilated21::MAX);
+        assert_eq!(Dilated21::MAX.get(), (1 << 21) - 1);
+        assert_eq!(Dilated21::from_raw(0b111_111).get(), 0b11);
+    }
+
+    #[test]
+    fn test_arith() {
+        let a = Dilated21::new(1000);
+        let b = Dilated21::new(24);
+        assert_eq!((a + b).get(), 1024);
+        assert_eq!((a - b).get(), 976);
+        assert!(b < a);
+
+        let mut c = a;
+        c += b;
+        c -= Dilated21::new(1);
+        assert_eq!(c.get(), 1023);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let one = Dilated21::new(1);
+        assert_eq!(Dilated21::MAX.checked_add(one), None);
+        assert_eq!(Dilated21::MIN.checked_sub(one), None);
+        assert_eq!(Dilated21::MAX.wrapping_add(one), Dilated21::MIN);
+        assert_eq!(Dilated21::MIN.wrapping_sub(one), Dilated21::MAX);
+        assert_eq!(
+            Dilated21::MAX.overflowing_add(Dilated21::new(2)),
+            (one, true)
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use core::ops::Neg;
+
+use crate::Offset3D;
+
+/// One of the six axis-aligned directions of a 3-dimension grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    NegX,
+    PosX,
+    NegY,
+    PosY,
+    NegZ,
+    PosZ,
+}
+
+impl Direction {
+    /// All the directions, negative one first on each axis.
+    pub const ALL: [Direction; 6] = [
+        Direction::NegX,
+        Direction::PosX,
+        Direction::NegY,
+        Direction::PosY,
+        Direction::NegZ,
+        Direction::PosZ,
+    ];
+
+    /// axis of the direction (0: x, 1: y, 2: z)
+    pub const fn axis(self) -> usize {
+        self as usize / 2
+    }
+
+    pub const fn is_positive(self) -> bool {
+        self as usize % 2 == 1
+    }
+
+    /// direction on the same axis pointing the other way
+    pub const fn opposite(self) -> Self {
+        Self::ALL[self as usize ^ 1]
+    }
+
+    /// unit offset of the direction
+    pub const fn offset(self) -> Offset3D {
+        let d = if self.is_positive() { 1 } else { -1 };
+        match self.axis() {
+            0 => Offset3D::new(d, 0, 0),
+            1 => Offset3D::new(0, d, 0),
+            _ => Offset3D::new(0, 0, d),
+        }
+    }
+}
+
+impl Neg for Direction {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.opposite()
+    }
+}
+
+impl From<Direction> for Offset3D {
+    fn from(v: Direction) -> Self {
+        v.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Direction;
+    use crate::{Morton3D, Offset3D};
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::PosY.axis(), 1);
+        assert!(Direction::PosZ.is_positive());
+        assert!(!Direction::NegX.is_positive());
+        assert_eq!(-Direction::NegZ, Direction::PosZ);
+        assert_eq!(Direction::PosX.opposite(), Direction::NegX);
+        assert_eq!(Offset3D::from(Direction::NegY), Offset3D::new(0, -1, 0));
+        for dir in Direction::ALL {
+            assert_eq!(dir.opposite().opposite(), dir);
+            assert_eq!(-dir.offset(), dir.opposite().offset());
+        }
+    }
+
+    #[test]
+    fn test_shift() {
+        let morton = Morton3D::from_coords(5, 6, 7);
+        assert_eq!(morton.shift(Direction::PosX, 1), morton.increase_x());
+        assert_eq!(morton.shift(Direction::NegY, 6), morton.with_y(0));
+        assert_eq!(morton.shift(Direction::PosZ, 3).z(), 10);
+        assert_eq!(morton.checked_shift(Direction::NegY, 7), None);
+        assert_eq!(
+            morton.checked_shift(Direction::NegZ, 2),
+            Some(Morton3D::from_coords(5, 6, 5))
+        );
+        for dir in Direction::ALL {
+            assert_eq!(morton.shift(dir, 4).shift(-dir, 4), morton);
+            assert_eq!(morton.checked_shift(dir, 1), morton.translate(dir.offset()));
+        }
+    }
+}
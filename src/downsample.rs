@@ -0,0 +1,92 @@
+use alloc::vec::Vec;
+
+use crate::{Morton3D, MortonKey};
+
+/// codes at `depth` of the cells containing the points, with the indices of the points,
+/// sorted by code and then by index.
+fn bucket(points: &[[f64; 3]], depth: u32) -> Vec<(Morton3D, usize)> {
+    assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+    let n = f64::from(1u32 << depth);
+    let mut codes: Vec<_> = points
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.iter().all(|v| (0.0..n).contains(v)))
+        .map(|(i, p)| {
+            (
+                Morton3D::from_coords(p[0] as u32, p[1] as u32, p[2] as u32),
+                i,
+            )
+        })
+        .collect();
+    codes.sort_unstable();
+    codes
+}
+
+/// voxel-grid filter: the centroid of the points in each occupied cell at `depth`,
+/// with the code of the cell, sorted by code.
+///
+/// Points are in units of cells at `depth`, points outside of the grid are dropped.
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn downsample(points: &[[f64; 3]], depth: u32) -> Vec<(Morton3D, [f64; 3])> {
+    bucket(points, depth)
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|cell| {
+            let mut sum = [0.0; 3];
+            for &(_, i) in cell {
+                for (s, v) in sum.iter_mut().zip(points[i].iter()) {
+                    *s += v;
+                }
+            }
+            let count = cell.len() as f64;
+            (cell[0].0, sum.map(|s| s / count))
+        })
+        .collect()
+}
+
+/// voxel-grid filter keeping the first point of each occupied cell at `depth`,
+/// return the indices of the kept points, sorted by the code of their cell.
+///
+/// See [`downsample`] for the units.
+pub fn downsample_first(points: &[[f64; 3]], depth: u32) -> Vec<usize> {
+    bucket(points, depth)
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|cell| cell[0].1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{downsample, downsample_first};
+    use crate::Morton3D;
+
+    #[test]
+    fn test_downsample() {
+        let points = [
+            [1.25, 0.5, 0.5],
+            [0.25, 0.5, 0.5],
+            [0.75, 0.25, 0.0],
+            [1.75, 0.75, 0.25],
+            [3.5, 3.5, 3.5],
+            [4.0, 0.0, 0.0],
+            [-0.1, 0.0, 0.0],
+        ];
+        let cells = downsample(&points, 2);
+        assert_eq!(
+            cells,
+            vec![
+                (Morton3D::from_coords(0, 0, 0), [0.5, 0.375, 0.25]),
+                (Morton3D::from_coords(1, 0, 0), [1.5, 0.625, 0.375]),
+                (Morton3D::from_coords(3, 3, 3), [3.5, 3.5, 3.5]),
+            ]
+        );
+        assert_eq!(downsample_first(&points, 2), vec![1, 0, 4]);
+
+        // the root is a single cell of size 1
+        assert_eq!(downsample_first(&points, 0), vec![1]);
+        assert_eq!(
+            downsample(&[[0.25; 3], [0.5; 3]], 0),
+            vec![(Morton3D::from_coords(0, 0, 0), [0.375; 3])]
+        );
+        assert!(downsample(&[], 5).is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use core::fmt;
+
+/// Error returned by fallible morton code operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MortonError {
+    /// Coordinate of `axis` (0: x, 1: y, 2: z) exceeds the max coordinate `max`.
+    CoordinateOutOfRange { axis: usize, value: u64, max: u64 },
+}
+
+impl fmt::Display for MortonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MortonError::CoordinateOutOfRange { axis, value, max } => write!(
+                f,
+                "coordinate {} of axis {} is out of range (max: {})",
+                value, axis, max
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MortonError {}
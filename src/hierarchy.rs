@@ -0,0 +1,181 @@
+use crate::MortonND;
+
+/// Implement octree navigation for 3-dimension morton codes of storage `$t`.
+///
+/// A code at depth `d` has `3 * d` valid bits, its coordinates are in `0..2^d`.
+/// Parent drops the last 3-bit octant group, child appends one.
+macro_rules! hierarchy {
+    ($t:ty) => {
+        impl MortonND<3, $t> {
+            /// raw bits without flag
+            const fn bits(self) -> $t {
+                self.raw() & !(1 << (<$t>::BITS - 1))
+            }
+
+            /// code from raw bits, with flag of `self`
+            const fn with_bits(self, bits: $t) -> Self {
+                let mut v = Self::from_raw(bits);
+                if self.is_flag_set() {
+                    v.set_flag();
+                }
+                v
+            }
+
+            /// parent cell (one level coarser), flag is kept.
+            pub const fn parent(self) -> Self {
+                self.with_bits(self.bits() >> 3)
+            }
+
+            /// child cell in `octant` (one level finer), flag is kept.
+            ///
+            /// Bit 0, 1, 2 of `octant` is the lower bit of x, y, z coordinate of the child.
+            /// panic if `octant` is not in `0..8` or the cell is already at `MAX_DEPTH`.
+            pub const fn child(self, octant: usize) -> Self {
+                assert!(octant < 8, "octant out of range");
+                assert!(
+                    self.bits() >> (3 * (Self::MAX_DEPTH - 1)) == 0,
+                    "cell is at max depth"
+                );
+                self.with_bits(self.bits() << 3 | octant as $t)
+            }
+
+            /// all the 8 child cells in Z-order, flag is kept.
+            pub const fn children(self) -> [Self; 8] {
+                let mut children = [self; 8];
+                let mut octant = 0;
+                while octant < 8 {
+                    children[octant] = self.child(octant);
+                    octant += 1;
+                }
+                children
+            }
+
+            /// ancestor of this cell at `depth`, flag is kept.
+            ///
+            /// This cell is at `MAX_DEPTH`, panic if `depth` exceeds it.
+            pub const fn ancestor_at(self, depth: u32) -> Self {
+                assert!(depth <= Self::MAX_DEPTH, "depth out of range");
+                self.with_bits(self.bits() >> (3 * (Self::MAX_DEPTH - depth)))
+            }
+
+            /// octant of the ancestor of this cell at `depth` within its parent,
+            /// i.e. the 3-bit digit of level `depth`.
+            ///
+            /// This cell is at `MAX_DEPTH`, panic if `depth` is not in `1..=MAX_DEPTH`.
+            pub const fn octant_at(self, depth: u32) -> usize {
+                assert!(depth > 0, "root has no octant");
+                self.ancestor_at(depth).octant()
+            }
+
+            /// index of this cell within its parent, see [`child`](Self::child).
+            pub const fn octant(self) -> usize {
+                (self.bits() & 0b111) as usize
+            }
+
+            /// the other 7 cells sharing the parent in Z-order, flag is kept.
+            pub const fn siblings(self) -> [Self; 7] {
+                let mut siblings = [self; 7];
+                let mut i = 0;
+                let mut octant = 0;
+                while octant < 8 {
+                    if octant != self.octant() {
+                        siblings[i] = self.with_bits(self.bits() & !0b111 | octant as $t);
+                        i += 1;
+                    }
+                    octant += 1;
+                }
+                siblings
+            }
+        }
+    };
+}
+
+hierarchy!(u16);
+hierarchy!(u32);
+hierarchy!(u64);
+hierarchy!(u128);
+hierarchy!(usize);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Morton3D, Morton3D16};
+
+    #[test]
+    fn test_parent_child() {
+        let cell = Morton3D::from_coords(5, 2, 7);
+        assert_eq!(cell.parent().to_coords(), (2, 1, 3));
+        assert_eq!(cell.parent().child(0b101), cell);
+        assert_eq!(
+            Morton3D::from_coords(0, 0, 0).parent(),
+            Morton3D::from_coords(0, 0, 0)
+        );
+
+        let children = cell.children();
+        assert!(children.windows(2).all(|w| w[0] < w[1]));
+        for (octant, child) in children.iter().enumerate() {
+            assert_eq!(child.parent(), cell);
+            assert_eq!(usize::from(*child) & 0b111, octant);
+        }
+        assert_eq!(children[0b011].to_coords(), (11, 5, 14));
+
+        let mut flagged = cell;
+        flagged.set_flag();
+        assert!(flagged.parent().is_flag_set());
+        assert!(flagged.child(3).is_flag_set());
+        assert_eq!(flagged.child(3).parent(), flagged);
+
+        let small = Morton3D16::from_coords(15, 15, 15);
+        assert_eq!(small.child(7).to_coords(), (31, 31, 31));
+    }
+
+    #[test]
+    fn test_siblings() {
+        let cell = Morton3D::from_coords(5, 2, 7);
+        assert_eq!(cell.octant(), 0b101);
+        let siblings = cell.siblings();
+        assert!(!siblings.contains(&cell));
+        assert!(siblings.iter().all(|s| s.parent() == cell.parent()));
+        assert!(siblings.windows(2).all(|w| w[0] < w[1]));
+        let mut family = siblings.to_vec();
+        family.push(cell);
+        family.sort();
+        assert_eq!(family, cell.parent().children());
+        for (octant, child) in cell.children().iter().enumerate() {
+            assert_eq!(child.octant(), octant);
+        }
+
+        let mut flagged = cell;
+        flagged.set_flag();
+        assert!(flagged.siblings().iter().all(|s| s.is_flag_set()));
+        assert_eq!(flagged.octant(), 0b101);
+    }
+
+    #[test]
+    fn test_ancestor() {
+        let shift = Morton3D::MAX_DEPTH - 3;
+        let cell = Morton3D::from_coords(5 << shift, 2 << shift, (7 << shift) + 1);
+        assert_eq!(cell.ancestor_at(3).to_coords(), (5, 2, 7));
+        assert_eq!(cell.ancestor_at(1).to_coords(), (1, 0, 1));
+        assert_eq!(cell.ancestor_at(0), Morton3D::default());
+        assert_eq!(cell.ancestor_at(Morton3D::MAX_DEPTH), cell);
+        assert_eq!(cell.ancestor_at(Morton3D::MAX_DEPTH - 1), cell.parent());
+
+        assert_eq!(cell.octant_at(1), 0b101);
+        assert_eq!(cell.octant_at(2), 0b110);
+        assert_eq!(cell.octant_at(3), 0b101);
+        assert_eq!(cell.octant_at(Morton3D::MAX_DEPTH), 0b100);
+        let rebuilt =
+            (1..=Morton3D::MAX_DEPTH).fold(Morton3D::default(), |v, d| v.child(cell.octant_at(d)));
+        assert_eq!(rebuilt, cell);
+
+        let mut flagged = cell;
+        flagged.set_flag();
+        assert!(flagged.ancestor_at(2).is_flag_set());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_child_of_max_depth() {
+        Morton3D::from_coords(Morton3D::MAX_COORD, 0, 0).child(0);
+    }
+}
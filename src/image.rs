@@ -0,0 +1,81 @@
+use core::iter::FusedIterator;
+
+use crate::Morton2D;
+
+/// Iterator over the pixels of a `width * height` image in Z-order, as `(x, y)`.
+///
+/// The image needs not to be a power of two square,
+/// sections of the curve outside of it are skipped by [`bigmin`](crate::MortonND::bigmin).
+#[derive(Debug, Clone)]
+pub struct ImagePixels {
+    next: Option<Morton2D>,
+    max: Morton2D,
+}
+
+impl Iterator for ImagePixels {
+    type Item = (u32, u32);
+    fn next(&mut self) -> Option<(u32, u32)> {
+        let v = self.next?;
+        self.next = if v == self.max {
+            None
+        } else {
+            let min = Morton2D::default();
+            v.next_on_curve().map(|next| {
+                if next.is_within(min, self.max) {
+                    next
+                } else {
+                    next.bigmin(min, self.max)
+                }
+            })
+        };
+        Some(v.to_coords())
+    }
+}
+
+impl FusedIterator for ImagePixels {}
+
+impl Morton2D {
+    /// iterate over the pixels of a `width * height` image in Z-order, see [`ImagePixels`].
+    ///
+    /// panic if `width` or `height` exceeds `MAX_COORD + 1`.
+    pub fn iter_image(width: u32, height: u32) -> ImagePixels {
+        let max = Self::MAX_COORD as u64 + 1;
+        assert!(
+            width as u64 <= max && height as u64 <= max,
+            "size must be in 0..={}",
+            max
+        );
+        if width == 0 || height == 0 {
+            return ImagePixels {
+                next: None,
+                max: Self::default(),
+            };
+        }
+        ImagePixels {
+            next: Some(Self::default()),
+            max: Self::from_coords(width - 1, height - 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Morton2D;
+
+    #[test]
+    fn test_iter_image() {
+        let pixels: Vec<_> = Morton2D::iter_image(3, 2).collect();
+        assert_eq!(pixels, [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (2, 1)]);
+
+        for (width, height) in [(1, 1), (7, 5), (16, 16), (1, 33), (20, 3)] {
+            let pixels: Vec<_> = Morton2D::iter_image(width, height).collect();
+            let mut expected: Vec<_> = (0..width)
+                .flat_map(|x| (0..height).map(move |y| (x, y)))
+                .collect();
+            expected.sort_by_key(|&(x, y)| Morton2D::from_coords(x, y));
+            assert_eq!(pixels, expected);
+        }
+        assert_eq!(Morton2D::iter_image(0, 10).count(), 0);
+        assert_eq!(Morton2D::iter_image(10, 0).count(), 0);
+    }
+}
@@ -0,0 +1,449 @@
+use core::cmp::Ordering;
+use core::iter::FusedIterator;
+use core::ops::RangeInclusive;
+
+use crate::{Direction, Morton3D, MortonBox, Neighbors};
+
+/// Octree node identifier, a [`Morton3D`] cell together with its depth packed in one `usize`.
+///
+/// The code of a cell at depth `d` has `3 * d` bits, a sentinel bit is placed just above them,
+/// so depth is recovered from the position of the highest set bit.
+/// There is no flag, the sentinel of a cell at `MAX_DEPTH` uses the flag bit.
+///
+/// Keys are ordered by the cell they cover first and by depth next,
+/// which is the pre-order (depth-first) traversal order of the octree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MortonKey(usize);
+
+impl MortonKey {
+    /// Max depth of key.
+    pub const MAX_DEPTH: u32 = Morton3D::MAX_DEPTH;
+
+    /// The cell covering the whole grid.
+    pub const ROOT: Self = Self(1);
+
+    /// create key of the cell `code` at `depth`, flag of `code` is ignored.
+    ///
+    /// The coordinates of `code` are the ones at `depth`, i.e. in `0..2^depth`.
+    /// panic if `depth` exceeds `MAX_DEPTH` or `code` doesn't fit in `depth`.
+    pub const fn new(code: Morton3D, depth: u32) -> Self {
+        assert!(depth <= Self::MAX_DEPTH, "depth out of range");
+        let bits = Self::bits_of(code);
+        assert!(bits >> (3 * depth) == 0, "code doesn't fit in depth");
+        Self((1 << (3 * depth)) | bits)
+    }
+
+    /// create key of the cell at `depth` containing the cell `code` at `MAX_DEPTH`.
+    pub const fn from_leaf(code: Morton3D, depth: u32) -> Self {
+        Self::new(code.ancestor_at(depth), depth)
+    }
+
+    /// create key from the packed representation,
+    /// return `None` if it isn't a valid key.
+    pub const fn from_raw(raw: usize) -> Option<Self> {
+        if raw == 0 || !(usize::BITS - 1 - raw.leading_zeros()).is_multiple_of(3) {
+            None
+        } else {
+            Some(Self(raw))
+        }
+    }
+
+    /// packed representation
+    pub const fn raw(self) -> usize {
+        self.0
+    }
+
+    /// code bits without flag
+    const fn bits_of(code: Morton3D) -> usize {
+        let mut code = code;
+        code.unset_flag();
+        code.raw()
+    }
+
+    pub const fn depth(self) -> u32 {
+        (usize::BITS - 1 - self.0.leading_zeros()) / 3
+    }
+
+    /// code of the cell at its depth
+    pub const fn code(self) -> Morton3D {
+        Morton3D::from_raw(self.0 ^ (1 << (3 * self.depth())))
+    }
+
+    /// code of the first cell at `MAX_DEPTH` inside of this cell
+    pub const fn anchor(self) -> Morton3D {
+        Morton3D::from_raw(Self::bits_of(self.code()) << (3 * (Self::MAX_DEPTH - self.depth())))
+    }
+
+    /// parent cell, `None` for the root.
+    pub const fn parent(self) -> Option<Self> {
+        if self.0 == Self::ROOT.0 {
+            None
+        } else {
+            Some(Self(self.0 >> 3))
+        }
+    }
+
+    /// child cell in `octant`, see [`Morton3D::child`].
+    ///
+    /// panic if `octant` is not in `0..8` or the cell is already at `MAX_DEPTH`.
+    pub const fn child(self, octant: usize) -> Self {
+        assert!(octant < 8, "octant out of range");
+        assert!(self.depth() < Self::MAX_DEPTH, "cell is at max depth");
+        Self(self.0 << 3 | octant)
+    }
+
+    /// all the 8 child cells, in order.
+    pub const fn children(self) -> [Self; 8] {
+        let mut children = [self; 8];
+        let mut octant = 0;
+        while octant < 8 {
+            children[octant] = self.child(octant);
+            octant += 1;
+        }
+        children
+    }
+
+    /// index of this cell within its parent, 0 for the root.
+    pub const fn octant(self) -> usize {
+        if self.0 == Self::ROOT.0 {
+            0
+        } else {
+            self.0 & 0b111
+        }
+    }
+
+    /// whether this cell strictly contains `other`, i.e. `other` is in the subtree below this cell.
+    pub const fn is_ancestor_of(self, other: Self) -> bool {
+        let (depth, other_depth) = (self.depth(), other.depth());
+        depth < other_depth && other.0 >> (3 * (other_depth - depth)) == self.0
+    }
+
+    /// whether `other` strictly contains this cell.
+    pub const fn is_descendant_of(self, other: Self) -> bool {
+        other.is_ancestor_of(self)
+    }
+
+    /// deepest cell containing both cells (a cell contains itself).
+    pub const fn common_ancestor(self, other: Self) -> Self {
+        let (depth, other_depth) = (self.depth(), other.depth());
+        let (mut a, mut b) = (self.0, other.0);
+        // bring both to the same depth, sentinels are then at the same bit
+        if depth > other_depth {
+            a >>= 3 * (depth - other_depth);
+        } else {
+            b >>= 3 * (other_depth - depth);
+        }
+        let levels = (usize::BITS - (a ^ b).leading_zeros()).div_ceil(3);
+        Self(a >> (3 * levels))
+    }
+
+    /// codes at `depth` of the cells inside of this cell, they are contiguous in Z-order.
+    ///
+    /// panic if `depth` is not in `self.depth()..=MAX_DEPTH`.
+    pub const fn descendants_range(self, depth: u32) -> RangeInclusive<Morton3D> {
+        assert!(
+            self.depth() <= depth && depth <= Self::MAX_DEPTH,
+            "depth out of range"
+        );
+        let shift = 3 * (depth - self.depth());
+        let first = Self::bits_of(self.code()) << shift;
+        Morton3D::from_raw(first)..=Morton3D::from_raw(first | ((1 << shift) - 1))
+    }
+
+    /// number of cells at `MAX_DEPTH` along each axis of this cell
+    pub const fn size(self) -> u32 {
+        1 << (Self::MAX_DEPTH - self.depth())
+    }
+
+    /// cells at `MAX_DEPTH` covered by this cell
+    pub fn bounds(self) -> MortonBox {
+        let range = self.descendants_range(Self::MAX_DEPTH);
+        MortonBox::new(*range.start(), *range.end())
+    }
+
+    /// smallest cell containing all cells of `bounds` at `MAX_DEPTH`.
+    pub fn enclosing(bounds: MortonBox) -> Self {
+        let min = Self::from_leaf(bounds.min(), Self::MAX_DEPTH);
+        let max = Self::from_leaf(bounds.max(), Self::MAX_DEPTH);
+        min.common_ancestor(max)
+    }
+
+    /// center of this cell, in units of cells at `MAX_DEPTH`
+    pub fn center(self) -> [f64; 3] {
+        let (x, y, z) = self.anchor().to_coords();
+        let half = f64::from(self.size()) / 2.0;
+        [
+            f64::from(x) + half,
+            f64::from(y) + half,
+            f64::from(z) + half,
+        ]
+    }
+
+    /// box of the cells at the depth of this key
+    fn level_bounds(self) -> MortonBox {
+        let max = Morton3D::MAX_COORD >> (Self::MAX_DEPTH - self.depth());
+        MortonBox::new(
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(max, max, max),
+        )
+    }
+
+    /// adjacent cell at the same depth toward `dir`, `None` if it is out of the grid.
+    pub fn neighbor(self, dir: Direction) -> Option<Self> {
+        let depth = self.depth();
+        self.code()
+            .checked_shift(dir, 1)
+            .filter(|&code| self.level_bounds().contains(code))
+            .map(|code| Self::new(code, depth))
+    }
+
+    /// iterate over the up-to-6 cells at the same depth sharing a face with this cell.
+    pub fn face_neighbors(self) -> KeyNeighbors {
+        KeyNeighbors {
+            depth: self.depth(),
+            inner: self.code().face_neighbors_in(self.level_bounds()),
+        }
+    }
+
+    /// iterate over the up-to-18 cells at the same depth sharing a face or an edge with this cell.
+    pub fn edge_neighbors(self) -> KeyNeighbors {
+        KeyNeighbors {
+            depth: self.depth(),
+            inner: self.code().edge_neighbors_in(self.level_bounds()),
+        }
+    }
+
+    /// iterate over the up-to-26 cells at the same depth surrounding this cell.
+    pub fn moore_neighbors(self) -> KeyNeighbors {
+        KeyNeighbors {
+            depth: self.depth(),
+            inner: self.code().moore_neighbors_in(self.level_bounds()),
+        }
+    }
+}
+
+impl Default for MortonKey {
+    fn default() -> Self {
+        Self::ROOT
+    }
+}
+
+impl Ord for MortonKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.anchor(), self.depth()).cmp(&(other.anchor(), other.depth()))
+    }
+}
+
+impl PartialOrd for MortonKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<MortonKey> for usize {
+    fn from(v: MortonKey) -> Self {
+        v.0
+    }
+}
+
+/// Iterator over neighbor cells of a [`MortonKey`] at the same depth.
+#[derive(Debug, Clone)]
+pub struct KeyNeighbors {
+    depth: u32,
+    inner: Neighbors,
+}
+
+impl Iterator for KeyNeighbors {
+    type Item = MortonKey;
+    fn next(&mut self) -> Option<MortonKey> {
+        let depth = self.depth;
+        self.inner.next().map(|code| MortonKey::new(code, depth))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl FusedIterator for KeyNeighbors {}
+
+#[cfg(test)]
+mod tests {
+    use super::MortonKey;
+    use crate::{Direction, Morton3D, MortonBox};
+
+    #[test]
+    fn test_key() {
+        let key = MortonKey::new(Morton3D::from_coords(5, 2, 7), 3);
+        assert_eq!(key.depth(), 3);
+        assert_eq!(key.code().to_coords(), (5, 2, 7));
+        assert_eq!(MortonKey::from_raw(key.raw()), Some(key));
+        assert_eq!(MortonKey::from_raw(0b10), None);
+        assert_eq!(MortonKey::from_raw(0), None);
+        assert_eq!(MortonKey::ROOT.depth(), 0);
+        assert_eq!(MortonKey::ROOT.parent(), None);
+
+        let shift = MortonKey::MAX_DEPTH - 3;
+        assert_eq!(
+            key.anchor().to_coords(),
+            (5 << shift, 2 << shift, 7 << shift)
+        );
+        assert_eq!(MortonKey::from_leaf(key.anchor(), 3), key);
+        assert_eq!(
+            MortonKey::from_leaf(key.anchor(), 1).code().to_coords(),
+            (1, 0, 1)
+        );
+
+        let max = Morton3D::MAX_COORD;
+        let leaf = MortonKey::new(Morton3D::from_coords(max, max, max), MortonKey::MAX_DEPTH);
+        assert_eq!(leaf.depth(), MortonKey::MAX_DEPTH);
+        assert_eq!(leaf.code().to_coords(), (max, max, max));
+        assert_eq!(
+            leaf.parent().map(MortonKey::depth),
+            Some(MortonKey::MAX_DEPTH - 1)
+        );
+    }
+
+    #[test]
+    fn test_key_hierarchy() {
+        let key = MortonKey::new(Morton3D::from_coords(5, 2, 7), 3);
+        assert_eq!(
+            key.parent().map(MortonKey::code),
+            Some(Morton3D::from_coords(2, 1, 3))
+        );
+        assert_eq!(key.octant(), 0b101);
+        for (octant, child) in key.children().iter().enumerate() {
+            assert_eq!(child.depth(), 4);
+            assert_eq!(child.parent(), Some(key));
+            assert_eq!(child.octant(), octant);
+            assert_eq!(child.code(), key.code().child(octant));
+        }
+
+        // pre-order
+        let mut keys = vec![
+            key.child(7),
+            key.child(0).child(3),
+            key,
+            key.parent().unwrap(),
+            key.child(0),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                key.parent().unwrap(),
+                key,
+                key.child(0),
+                key.child(0).child(3),
+                key.child(7)
+            ]
+        );
+        assert!(MortonKey::ROOT < key);
+    }
+
+    #[test]
+    fn test_key_ancestry() {
+        let key = MortonKey::new(Morton3D::from_coords(5, 2, 7), 3);
+        let grandchild = key.child(4).child(1);
+        assert!(key.is_ancestor_of(grandchild));
+        assert!(grandchild.is_descendant_of(key));
+        assert!(MortonKey::ROOT.is_ancestor_of(key));
+        assert!(!key.is_ancestor_of(key));
+        assert!(!grandchild.is_ancestor_of(key));
+        assert!(!key.is_ancestor_of(key.parent().unwrap().child(0).child(4)));
+
+        assert_eq!(key.common_ancestor(key), key);
+        assert_eq!(key.common_ancestor(grandchild), key);
+        assert_eq!(grandchild.common_ancestor(key), key);
+        assert_eq!(key.child(2).common_ancestor(key.child(5).child(1)), key);
+        let far = MortonKey::new(Morton3D::from_coords(2, 2, 7), 3);
+        assert_eq!(key.common_ancestor(far), MortonKey::ROOT);
+        let near = MortonKey::new(Morton3D::from_coords(4, 2, 7), 3);
+        assert_eq!(key.common_ancestor(near), key.parent().unwrap());
+        assert_eq!(key.common_ancestor(MortonKey::ROOT), MortonKey::ROOT);
+    }
+
+    #[test]
+    fn test_descendants_range() {
+        let key = MortonKey::new(Morton3D::from_coords(1, 0, 1), 1);
+        let range = key.descendants_range(1);
+        assert_eq!((*range.start(), *range.end()), (key.code(), key.code()));
+
+        let range = key.descendants_range(3);
+        assert_eq!(range.start().to_coords(), (4, 0, 4));
+        assert_eq!(range.end().to_coords(), (7, 3, 7));
+        assert_eq!(
+            range.start().raw()..=range.end().raw(),
+            0b101_000_000..=0b101_111_111
+        );
+
+        let leaves = key.descendants_range(MortonKey::MAX_DEPTH);
+        assert_eq!(*leaves.start(), key.anchor());
+        assert!(leaves.contains(&key.child(3).child(6).anchor()));
+        assert!(!leaves.contains(&key.parent().unwrap().child(0).anchor()));
+
+        // sorted-array subtree scan
+        let cells: Vec<_> = (0..64).map(|i| Morton3D::from_raw(i * 8)).collect();
+        let range = MortonKey::new(Morton3D::from_coords(0, 0, 1), 1).descendants_range(3);
+        let first = cells.partition_point(|c| c < range.start());
+        let last = cells.partition_point(|c| c <= range.end());
+        assert_eq!(last - first, 8);
+        let range = MortonKey::ROOT.descendants_range(2);
+        assert_eq!(range.end().raw() - range.start().raw() + 1, 64);
+    }
+
+    #[test]
+    fn test_key_geometry() {
+        let key = MortonKey::new(Morton3D::from_coords(1, 0, 3), 2);
+        let size = 1 << (MortonKey::MAX_DEPTH - 2);
+        assert_eq!(key.size(), size);
+        let bounds = key.bounds();
+        assert_eq!(bounds.min().to_coords(), (size, 0, 3 * size));
+        assert_eq!(
+            bounds.max().to_coords(),
+            (2 * size - 1, size - 1, 4 * size - 1)
+        );
+        let half = f64::from(size) / 2.0;
+        assert_eq!(key.center(), [3.0 * half, half, 7.0 * half]);
+
+        let max = Morton3D::MAX_COORD;
+        let leaf = MortonKey::new(Morton3D::from_coords(max, 0, 0), MortonKey::MAX_DEPTH);
+        assert_eq!(leaf.size(), 1);
+        assert_eq!(leaf.bounds().min(), leaf.bounds().max());
+        assert_eq!(leaf.center(), [f64::from(max) + 0.5, 0.5, 0.5]);
+        assert_eq!(MortonKey::ROOT.bounds(), MortonBox::<usize>::FULL);
+
+        assert_eq!(MortonKey::enclosing(key.bounds()), key);
+        assert_eq!(MortonKey::enclosing(leaf.bounds()), leaf);
+        let inner = MortonBox::new(
+            Morton3D::from_coords(size + 3, 5, 3 * size),
+            Morton3D::from_coords(size + 9, 6, 3 * size + 1),
+        );
+        assert_eq!(
+            MortonKey::enclosing(inner).depth(),
+            MortonKey::MAX_DEPTH - 4
+        );
+        assert!(key.is_ancestor_of(MortonKey::enclosing(inner)));
+    }
+
+    #[test]
+    fn test_key_neighbors() {
+        let key = MortonKey::new(Morton3D::from_coords(3, 0, 2), 2);
+        assert_eq!(key.neighbor(Direction::PosX), None);
+        assert_eq!(key.neighbor(Direction::NegY), None);
+        assert_eq!(
+            key.neighbor(Direction::PosZ),
+            Some(MortonKey::new(Morton3D::from_coords(3, 0, 3), 2))
+        );
+        assert_eq!(key.face_neighbors().count(), 4);
+        assert_eq!(key.edge_neighbors().count(), 9);
+        assert_eq!(key.moore_neighbors().count(), 2 * 2 * 3 - 1);
+        assert!(key.moore_neighbors().all(|n| n.depth() == 2));
+        assert_eq!(MortonKey::ROOT.moore_neighbors().count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_key_out_of_depth() {
+        MortonKey::new(Morton3D::from_coords(4, 0, 0), 2);
+    }
+}
@@ -0,0 +1,134 @@
+use core::ops::RangeInclusive;
+
+use crate::{Morton3D, MortonKey};
+
+/// number of meaningful bits of codes, i.e. without the flag and unused bits
+const CODE_BITS: u32 = 3 * MortonKey::MAX_DEPTH;
+
+/// length of the common prefix of `sorted[i]` and `sorted[j]` (the δ of Karras' linear BVH builder),
+/// `None` if `j` is out of range.
+///
+/// Equal codes are told apart by their indices, so the prefix then goes on with
+/// the common prefix of `i` and `j`, and every pair of distinct indices gets a distinct split.
+/// An index before the start can be given as `i.wrapping_sub(1)`.
+///
+/// The flag of codes is ignored.
+pub fn delta(sorted: &[Morton3D], i: usize, j: usize) -> Option<u32> {
+    let (mut a, mut b) = (sorted[i], *sorted.get(j)?);
+    a.unset_flag();
+    b.unset_flag();
+    let diff = a.raw() ^ b.raw();
+    Some(if diff == 0 {
+        CODE_BITS + (i ^ j).leading_zeros()
+    } else {
+        diff.leading_zeros() - (usize::BITS - CODE_BITS)
+    })
+}
+
+/// index of the last code of the first child of the node spanning `sorted[first..=last]`,
+/// i.e. the node splits into `first..=split` and `split + 1..=last`.
+///
+/// It is the highest position where the common prefix of the codes changes, found by binary search.
+///
+/// panic if `first >= last` or `last` is out of range.
+pub fn find_split(sorted: &[Morton3D], first: usize, last: usize) -> usize {
+    assert!(first < last && last < sorted.len(), "invalid node");
+    let common = delta(sorted, first, last);
+    let (mut split, mut step) = (first, last - first);
+    while step > 1 {
+        step = step.div_ceil(2);
+        let next = split + step;
+        if next < last && delta(sorted, first, next) > common {
+            split = next;
+        }
+    }
+    split
+}
+
+/// codes spanned by the internal node `i` of the linear BVH over `sorted`, in Karras' layout.
+///
+/// There are `sorted.len() - 1` internal nodes and node 0 is the root. A node spanning
+/// `first..=last` with the split `s` (see [`find_split`]) has the children `s` and `s + 1`,
+/// which are leaves (indices of codes) if they span one code and internal nodes otherwise.
+/// Ranges of all nodes can be computed independently of each other, e.g. in parallel.
+///
+/// panic if `i + 1` is out of range.
+pub fn node_range(sorted: &[Morton3D], i: usize) -> RangeInclusive<usize> {
+    assert!(i + 1 < sorted.len(), "invalid node");
+    let at = |offset: usize, forward: bool| {
+        let j = if forward {
+            i.wrapping_add(offset)
+        } else {
+            i.wrapping_sub(offset)
+        };
+        (j, delta(sorted, i, j))
+    };
+    // the node extends toward the neighbor sharing the longer prefix
+    let forward = at(1, true).1 > at(1, false).1;
+    let min = at(1, !forward).1;
+    let mut max_len = 2;
+    while at(max_len, forward).1 > min {
+        max_len *= 2;
+    }
+    let (mut len, mut step) = (0, max_len / 2);
+    while step > 0 {
+        if at(len + step, forward).1 > min {
+            len += step;
+        }
+        step /= 2;
+    }
+    let (j, _) = at(len, forward);
+    i.min(j)..=i.max(j)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{delta, find_split, node_range, CODE_BITS};
+    use crate::Morton3D;
+
+    /// check the node and its subtree, deltas strictly grow from a node to its children
+    fn check(sorted: &[Morton3D], node: usize, first: usize, last: usize) {
+        assert_eq!(node_range(sorted, node), first..=last);
+        let split = find_split(sorted, first, last);
+        assert!(first <= split && split < last);
+        let common = delta(sorted, first, last);
+        assert!(delta(sorted, first, split) > common || first == split);
+        assert!(delta(sorted, split + 1, last) > common || split + 1 == last);
+        assert_eq!(delta(sorted, split, split + 1), common);
+        if split > first {
+            check(sorted, split, first, split);
+        }
+        if split + 1 < last {
+            check(sorted, split + 1, split + 1, last);
+        }
+    }
+
+    #[test]
+    fn test_delta() {
+        let sorted: Vec<_> = vec![0b000_001, 0b000_011, 0b100_000, 0b100_000]
+            .into_iter()
+            .map(Morton3D::from_raw)
+            .collect();
+        assert_eq!(delta(&sorted, 0, 1), Some(CODE_BITS - 2));
+        assert_eq!(delta(&sorted, 1, 2), Some(CODE_BITS - 6));
+        assert_eq!(delta(&sorted, 2, 3), Some(CODE_BITS + usize::BITS - 1));
+        assert_eq!(delta(&sorted, 0, 4), None);
+        assert_eq!(delta(&sorted, 0, 0usize.wrapping_sub(1)), None);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut codes: Vec<_> = (0..300usize)
+            .map(|i| Morton3D::from_raw((i * 2_654_435_761) % (1 << 20)))
+            .collect();
+        // duplicates
+        codes.extend_from_slice(&codes.clone()[..40]);
+        codes.push(codes[0]);
+        codes.sort_unstable();
+        check(&codes, 0, 0, codes.len() - 1);
+
+        let same = vec![Morton3D::from_raw(7); 9];
+        check(&same, 0, 0, same.len() - 1);
+        assert_eq!(node_range(&same[..2], 0), 0..=1);
+    }
+}
@@ -1,111 +1,218 @@
-#[cfg(target_pointer_width = "64")]
-const MASK: usize =
-    0b0_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001_001;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "nightly", feature(step_trait))]
+// batch kernels use NEON instead on aarch64
+#![cfg_attr(
+    all(
+        feature = "nightly",
+        not(all(target_arch = "aarch64", target_feature = "neon"))
+    ),
+    feature(portable_simd)
+)]
 
-#[cfg(target_pointer_width = "32")]
-const MASK: usize = 0b00_001_001_001_001_001_001_001_001_001_001;
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-/// Number of bits for usize.
-const NUM_BITS_USIZE: usize = std::mem::size_of::<usize>() * 8;
+mod aniso;
+#[cfg(feature = "alloc")]
+mod array;
+mod balance;
+mod batch;
+mod bits;
+#[cfg(feature = "alloc")]
+mod broad;
+#[cfg(feature = "alloc")]
+mod chunked;
+mod cover;
+mod curve;
+mod dilated;
+mod direction;
+#[cfg(feature = "alloc")]
+mod downsample;
+mod error;
+mod hierarchy;
+mod image;
+mod key;
+mod lbvh;
+#[cfg(feature = "alloc")]
+mod linear;
+#[cfg(feature = "alloc")]
+mod loose;
+#[cfg(feature = "alloc")]
+mod map;
+mod morton3d30;
+mod morton4d;
+mod morton_box;
+mod nd;
+mod neighbors;
+#[cfg(feature = "alloc")]
+mod octree;
+mod offset;
+#[cfg(feature = "rayon")]
+mod par;
+mod periodic;
+mod quantize;
+mod query;
+mod range;
+mod raycast;
+#[cfg(feature = "alloc")]
+mod rle;
+#[cfg(feature = "alloc")]
+mod roaring;
+mod search;
+#[cfg(feature = "alloc")]
+mod set;
+#[cfg(feature = "alloc")]
+mod shifted;
+mod sort;
+mod storage;
+mod stream;
+mod tiled;
+mod voxelize;
+mod walk;
+mod xyz;
 
-/// Max number of depth
-#[allow(dead_code)]
-const MAX_DEPTH: usize = NUM_BITS_USIZE / 3;
+pub use aniso::MortonAniso;
+#[cfg(feature = "alloc")]
+pub use array::{ArrayIter, ArrayIterMut, MortonArray};
+#[cfg(feature = "alloc")]
+pub use balance::balance;
+pub use balance::is_balanced;
+#[cfg(feature = "alloc")]
+pub use batch::face_neighbors_batch;
+pub use batch::{decode_batch, encode_batch, wrapping_add_batch};
+#[cfg(feature = "alloc")]
+pub use broad::{broad_phase, BroadPhase};
+#[cfg(feature = "alloc")]
+pub use chunked::{Bricks, ChunkedVoxels};
+#[cfg(feature = "alloc")]
+pub use cover::cover;
+pub use cover::{
+    cover_convex, cover_sphere, cover_sphere_ranges, Convex, Plane, Region, RegionCells,
+    RegionRanges, Sphere,
+};
+pub use curve::CurveIter;
+pub use dilated::Dilated21;
+pub use direction::Direction;
+#[cfg(feature = "alloc")]
+pub use downsample::{downsample, downsample_first};
+pub use error::MortonError;
+pub use image::ImagePixels;
+pub use key::{KeyNeighbors, MortonKey};
+pub use lbvh::{delta, find_split, node_range};
+#[cfg(feature = "alloc")]
+pub use linear::{LinearOctree, Partitions};
+#[cfg(feature = "alloc")]
+pub use loose::{loose_bounds, LooseIter, LooseOctree, LooseQuery};
+#[cfg(feature = "alloc")]
+pub use map::{MapIter, MapQuery, MortonMap};
+pub use morton3d30::Morton3D30;
+pub use morton4d::Morton4D;
+pub use morton_box::MortonBox;
+pub use nd::{Morton2D, Morton3D, Morton3D128, Morton3D16, Morton3D32, Morton3D64, MortonND};
+pub use neighbors::{Neighbors, NeighborsWithin};
+#[cfg(feature = "alloc")]
+pub use octree::{Octree, OctreeQuery};
+pub use offset::Offset3D;
+#[cfg(feature = "rayon")]
+pub use par::{par_decode, par_encode, par_sort_by_morton};
+pub use periodic::{PeriodicGrid, PeriodicNeighbors};
+pub use quantize::Quantizer;
+pub use query::{iter_box, query_box, BoxIter, BoxRanges, BoxShell, QueryBox};
+pub use range::{Blocks, MortonRange, RangeSplit, Strided};
+pub use raycast::{raycast, Raycast};
+#[cfg(feature = "alloc")]
+pub use rle::{RleIter, RleSet};
+#[cfg(feature = "alloc")]
+pub use roaring::{CompressedIter, CompressedSet};
+#[cfg(feature = "alloc")]
+pub use search::knn;
+pub use search::{nearest, within_radius, WithinRadius};
+#[cfg(feature = "alloc")]
+pub use set::{MortonSet, SetIter};
+#[cfg(feature = "alloc")]
+pub use shifted::ShiftedCurves;
+pub use sort::{Point3D, SortByMorton};
+pub use storage::MortonStorage;
+pub use stream::{morton_codes, morton_points, MortonCodes, MortonPoints};
+pub use tiled::TiledLayout;
+#[cfg(feature = "alloc")]
+pub use voxelize::voxelize_mesh;
+pub use voxelize::{voxelize_segment, voxelize_triangle, Triangle};
+#[cfg(feature = "alloc")]
+pub use walk::BreadthFirstWalk;
+pub use walk::{visit, DepthFirstWalk, Visit, Visitor};
+pub use xyz::{Morton3DXyz, MortonXyz};
 
 #[cfg(target_pointer_width = "64")]
-#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
-/// 3-dimension morton code(zyx), 21-level, first bit is used for 1-bit flag.
-pub struct Morton3D(usize);
+use bits::{compact_bits_u64, expand_bits_u64};
 
 #[cfg(target_pointer_width = "32")]
-#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
-/// 3-dimension morton code(zyx), 10-level, first bit is used for 1-bit flag.
-///
-/// To be compatible with 64-bit Morton3D, second bit is not used.
-pub struct Morton3D(usize);
-
-impl Morton3D {
-    pub const fn is_flag_set(self) -> bool {
-        (self.0 >> (NUM_BITS_USIZE - 1)) == 1
-    }
-    pub fn set_flag(&mut self) {
-        self.0 |= 1 << (NUM_BITS_USIZE - 1)
-    }
-    pub fn unset_flag(&mut self) {
-        self.0 &= !(1 << (NUM_BITS_USIZE - 1))
-    }
-
-    /// generate mask bits
-    const fn mask_n(n: usize) -> usize {
-        MASK << (n % 3)
-    }
+use bits::{compact_bits_u32, expand_bits_u32};
 
-    /// decrease n-th dim (0: x, 1: y, 2: z) morton code,
-    /// panic if it can't
-    const fn decrease_nth_dim(self, n: usize) -> Self {
-        Self((((self.0 & Self::mask_n(n)) - 1) & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)))
-    }
-    /// increase n-th dim (0: x, 1: y, 2: z) morton code,
-    /// panic if it can't
-    const fn increase_nth_dim(self, n: usize) -> Self {
-        Self((((self.0 | !Self::mask_n(n)) + 1) & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)))
-    }
+/// Max number of depth
+const MAX_DEPTH: usize = Morton3D::MAX_DEPTH as usize;
 
-    pub const fn decrease_x(self) -> Self {
-        self.decrease_nth_dim(0)
-    }
-    pub const fn decrease_y(self) -> Self {
-        self.decrease_nth_dim(1)
-    }
-    pub const fn decrease_z(self) -> Self {
-        self.decrease_nth_dim(2)
-    }
+/// Spread the lower 21 bits of `v` so that there are two zero bits between each of them,
+/// i.e. bit `i` of `v` is moved to bit `3 * i`. Higher bits of `v` are discarded.
+///
+/// This is the 3-way bit dilation used to encode each axis of [`Morton3D`].
+#[cfg(target_pointer_width = "64")]
+pub const fn expand_bits(v: u32) -> usize {
+    expand_bits_u64(v) as usize
+}
 
-    pub const fn increase_x(self) -> Self {
-        self.increase_nth_dim(0)
-    }
-    pub const fn increase_y(self) -> Self {
-        self.increase_nth_dim(1)
-    }
-    pub const fn increase_z(self) -> Self {
-        self.increase_nth_dim(2)
-    }
+/// Spread the lower 10 bits of `v` so that there are two zero bits between each of them,
+/// i.e. bit `i` of `v` is moved to bit `3 * i`. Higher bits of `v` are discarded.
+///
+/// This is the 3-way bit dilation used to encode each axis of [`Morton3D`].
+#[cfg(target_pointer_width = "32")]
+pub const fn expand_bits(v: u32) -> usize {
+    expand_bits_u32(v) as usize
 }
 
-impl From<usize> for Morton3D {
-    fn from(v: usize) -> Self {
-        Self(v)
-    }
+/// Gather every third bit of `v` (bit `3 * i` is moved to bit `i`) into the lower 21 bits,
+/// other bits of `v` are ignored.
+///
+/// This is the inverse of [`expand_bits`].
+#[cfg(target_pointer_width = "64")]
+pub const fn compact_bits(v: usize) -> u32 {
+    compact_bits_u64(v as u64)
 }
 
-impl Into<usize> for Morton3D {
-    fn into(self) -> usize {
-        self.0
-    }
+/// Gather every third bit of `v` (bit `3 * i` is moved to bit `i`) into the lower 10 bits,
+/// other bits of `v` are ignored.
+///
+/// This is the inverse of [`expand_bits`].
+#[cfg(target_pointer_width = "32")]
+pub const fn compact_bits(v: usize) -> u32 {
+    compact_bits_u32(v as u32)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::convert::TryInto;
+    use std::convert::{TryFrom, TryInto};
 
-    use crate::MAX_DEPTH;
+    use crate::{compact_bits, expand_bits, MAX_DEPTH};
 
-    use super::Morton3D;
+    use super::{
+        Dilated21, Morton2D, Morton3D, Morton3D128, Morton3D16, Morton3D32, Morton3D64,
+        MortonError, MortonND, Offset3D,
+    };
 
     #[test]
     fn test_flag() {
-        let mut morton = Morton3D(0);
+        let mut morton = Morton3D::from(0);
         morton.set_flag();
         assert!(morton.is_flag_set());
         morton.unset_flag();
-        assert_eq!(morton, Morton3D(0));
+        assert_eq!(morton, Morton3D::from(0));
         assert!(!morton.is_flag_set());
     }
 
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn test_max() {
-        let mut morton = Morton3D(0);
+        let mut morton = Morton3D::from(0);
         for _ in 0..(2_u64.pow(MAX_DEPTH.try_into().unwrap()) - 1) {
             morton = morton.increase_x();
             morton = morton.increase_y();
@@ -113,13 +220,13 @@ mod tests {
         }
         // println!("actual: 0b{:064b}", morton.0);
         // println!("should: 0b0111111111111111111111111111111111111111111111111111111111111111");
-        assert_eq!(morton, Morton3D(0b0_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111));
+        assert_eq!(morton, Morton3D::from(0b0_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111_111));
     }
 
     #[test]
     #[cfg(target_pointer_width = "32")]
     fn test_max() {
-        let mut morton = Morton3D(0);
+        let mut morton = Morton3D::from(0);
         for _ in 0..(2_u64.pow(MAX_DEPTH.try_into().unwrap()) - 1) {
             morton = morton.increase_x();
             morton = morton.increase_y();
@@ -129,41 +236,873 @@ mod tests {
         // println!("should: 0b0111111111111111111111111111111111111111111111111111111111111111");
         assert_eq!(
             morton,
-            Morton3D(0b00_111_111_111_111_111_111_111_111_111_111)
+            Morton3D::from(0b00_111_111_111_111_111_111_111_111_111_111)
+        );
+    }
+
+    #[test]
+    fn test_dilate() {
+        assert_eq!(expand_bits(0), 0);
+        assert_eq!(expand_bits(0b1011), 0b001_000_001_001);
+        assert_eq!(
+            expand_bits(u32::MAX),
+            usize::from(Morton3D::from_coords(u32::MAX, 0, 0))
+        );
+        assert_eq!(compact_bits(0b001_000_001_001), 0b1011);
+        assert_eq!(compact_bits(!0), (1 << MAX_DEPTH) - 1);
+        for v in [
+            0,
+            1,
+            2,
+            12345 & ((1 << MAX_DEPTH) - 1),
+            (1 << MAX_DEPTH) - 1,
+        ] {
+            assert_eq!(compact_bits(expand_bits(v)), v);
+            assert_eq!(compact_bits(expand_bits(v) << 1), 0);
+        }
+    }
+
+    #[test]
+    fn test_from_coords() {
+        assert_eq!(Morton3D::from_coords(0, 0, 0), Morton3D::from(0));
+        assert_eq!(Morton3D::from_coords(1, 0, 0), Morton3D::from(0b001));
+        assert_eq!(Morton3D::from_coords(0, 1, 0), Morton3D::from(0b010));
+        assert_eq!(Morton3D::from_coords(0, 0, 1), Morton3D::from(0b100));
+        assert_eq!(Morton3D::from_coords(3, 2, 1), Morton3D::from(0b011_101));
+
+        let morton = Morton3D::from_coords(5, 6, 7)
+            .increase_x()
+            .increase_y()
+            .increase_z();
+        assert_eq!(morton, Morton3D::from_coords(6, 7, 8));
+    }
+
+    #[test]
+    fn test_from_coords_limit() {
+        let max = (1 << MAX_DEPTH) - 1;
+        let morton = Morton3D::from_coords(u32::MAX, u32::MAX, u32::MAX);
+        assert!(!morton.is_flag_set());
+        assert_eq!(morton, Morton3D::from_coords(max, max, max));
+    }
+
+    #[test]
+    fn test_try_from_coords() {
+        let max = Morton3D::MAX_COORD;
+        assert_eq!(
+            Morton3D::try_from_coords(max, 0, max),
+            Ok(Morton3D::from_coords(max, 0, max))
+        );
+        assert_eq!(
+            Morton3D::try_from_coords(0, max + 1, 0),
+            Err(MortonError::CoordinateOutOfRange {
+                axis: 1,
+                value: u64::from(max) + 1,
+                max: u64::from(max)
+            })
+        );
+        assert!(Morton3D::try_from_coords(0, 0, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_to_coords() {
+        assert_eq!(Morton3D::from(0b011_101).to_coords(), (3, 2, 1));
+
+        let max = (1 << MAX_DEPTH) - 1;
+        for &(x, y, z) in &[
+            (0, 0, 0),
+            (1, 2, 3),
+            (max, 0, max),
+            (max, max, max),
+            (max / 3, max / 5, 9),
+        ] {
+            let mut morton = Morton3D::from_coords(x, y, z);
+            assert_eq!(morton.to_coords(), (x, y, z));
+            morton.set_flag();
+            assert_eq!(morton.to_coords(), (x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_axis() {
+        let morton = Morton3D::from_coords(7, 100, 3);
+        assert_eq!(morton.x(), 7);
+        assert_eq!(morton.y(), 100);
+        assert_eq!(morton.z(), 3);
+        assert_eq!(morton.increase_y().y(), 101);
+    }
+
+    #[test]
+    fn test_set_axis() {
+        let morton = Morton3D::from_coords(7, 100, 3);
+        assert_eq!(morton.with_x(1), Morton3D::from_coords(1, 100, 3));
+        assert_eq!(morton.with_y(0), Morton3D::from_coords(7, 0, 3));
+        assert_eq!(morton.with_z(9), Morton3D::from_coords(7, 100, 9));
+
+        let mut morton = morton;
+        morton.set_flag();
+        morton.set_x(2);
+        morton.set_y(4);
+        morton.set_z(6);
+        assert!(morton.is_flag_set());
+        assert_eq!(morton.to_coords(), (2, 4, 6));
+    }
+
+    #[test]
+    fn test_coords_conversion() {
+        let morton = Morton3D::from_coords(1, 2, 3);
+        assert_eq!(Morton3D::from((1, 2, 3)), morton);
+        assert_eq!(Morton3D::from([1, 2, 3]), morton);
+
+        let coords: (u32, u32, u32) = morton.into();
+        assert_eq!(coords, (1, 2, 3));
+        let coords: [u32; 3] = morton.into();
+        assert_eq!(coords, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_const() {
+        const TABLE: [Morton3D; 4] = {
+            let mut table = [Morton3D::from_coords(0, 0, 0); 4];
+            let mut i = 0;
+            while i < table.len() {
+                table[i] = Morton3D::from_coords(i as u32, 0, 0).increase_y();
+                i += 1;
+            }
+            table
+        };
+        const COORDS: (u32, u32, u32) = TABLE[3].decrease_y().increase_z().to_coords();
+        const FLAGGED: Morton3D = {
+            let mut morton = TABLE[1];
+            morton.set_flag();
+            morton.set_z(5);
+            morton
+        };
+
+        assert_eq!(TABLE[2], Morton3D::from_coords(2, 1, 0));
+        assert_eq!(COORDS, (3, 0, 1));
+        assert!(FLAGGED.is_flag_set());
+        assert_eq!(FLAGGED.to_coords(), (1, 1, 5));
+    }
+
+    #[test]
+    fn test_dilated() {
+        let morton = Morton3D::from_coords(10, 20, 30);
+        assert_eq!(morton.dilated_x(), Dilated21::new(10));
+        assert_eq!(morton.dilated_y(), Dilated21::new(20));
+        assert_eq!(morton.dilated_z(), Dilated21::new(30));
+
+        let x = morton.dilated_x() + Dilated21::new(5);
+        let morton = Morton3D::from_dilated(x, morton.dilated_y(), morton.dilated_z());
+        assert_eq!(morton, Morton3D::from_coords(15, 20, 30));
+    }
+
+    #[test]
+    fn test_fixed_width() {
+        assert_eq!(Morton3D64::MAX_DEPTH, 21);
+        assert_eq!(Morton3D32::MAX_DEPTH, 10);
+
+        let morton = Morton3D64::from_coords(1, 2, 3).increase_x().decrease_z();
+        assert_eq!(u64::from(morton), 0b111_000);
+        assert_eq!(morton.to_coords(), (2, 2, 2));
+
+        let morton = Morton3D32::from_coords(1, 2, 3).increase_x().decrease_z();
+        assert_eq!(u32::from(morton), 0b111_000);
+        assert_eq!(
+            Morton3D32::from_coords(u32::MAX, u32::MAX, u32::MAX),
+            Morton3D32::from(0b00_111_111_111_111_111_111_111_111_111_111)
+        );
+    }
+
+    #[test]
+    fn test_fixed_width_conversion() {
+        let mut morton = Morton3D32::from_coords(1023, 5, 700);
+        morton.set_flag();
+        let wide = Morton3D64::from(morton);
+        assert!(wide.is_flag_set());
+        assert_eq!(wide.to_coords(), (1023, 5, 700));
+
+        let native = Morton3D::from_coords(7, 8, 9);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(Morton3D::from(Morton3D64::from(native)), native);
+        #[cfg(target_pointer_width = "32")]
+        assert_eq!(Morton3D::from(Morton3D32::from(native)), native);
+    }
+
+    #[test]
+    fn test_128() {
+        assert_eq!(Morton3D128::MAX_DEPTH, 42);
+        let max = Morton3D128::MAX_COORD;
+        assert_eq!(max, (1 << 42) - 1);
+
+        let mut morton = Morton3D128::from_coords(max, 1 << 40, 3);
+        assert_eq!(morton.to_coords(), (max, 1 << 40, 3));
+        assert!(!morton.is_flag_set());
+        morton.set_flag();
+        assert_eq!(
+            morton.increase_z().decrease_x().to_coords(),
+            (max - 1, 1 << 40, 4)
+        );
+        assert!(Morton3D128::try_from_coords(0, max + 1, 0).is_err());
+
+        let max = Morton3D128::from_coords(max, max, max);
+        assert_eq!(u128::from(max), (1 << 126) - 1);
+    }
+
+    #[test]
+    fn test_128_conversion() {
+        let mut morton = Morton3D64::from_coords(1, 2, (1 << 21) - 1);
+        morton.set_flag();
+        let wide = Morton3D128::from(morton);
+        assert!(wide.is_flag_set());
+        assert_eq!(wide.to_coords(), (1, 2, (1 << 21) - 1));
+        assert_eq!(Morton3D64::try_from(wide), Ok(morton));
+        assert_eq!(wide.truncate_64(), morton);
+
+        let wide = Morton3D128::from_coords(1 << 21 | 5, 6, 7);
+        assert_eq!(
+            Morton3D64::try_from(wide),
+            Err(MortonError::CoordinateOutOfRange {
+                axis: 0,
+                value: 1 << 21 | 5,
+                max: (1 << 21) - 1
+            })
+        );
+        assert_eq!(wide.truncate_64(), Morton3D64::from_coords(5, 6, 7));
+        assert_eq!(
+            Morton3D32::try_from(Morton3D64::from_coords(1, 2, 1024)),
+            Err(MortonError::CoordinateOutOfRange {
+                axis: 2,
+                value: 1024,
+                max: 1023
+            })
         );
     }
 
     #[test]
     fn test_inc() {
-        let morton = Morton3D(0b000_001);
+        let morton = Morton3D::from(0b000_001);
 
         let morton = morton.increase_y();
-        assert_eq!(morton, Morton3D(0b000_011));
+        assert_eq!(morton, Morton3D::from(0b000_011));
 
         let morton = morton.increase_y();
-        assert_eq!(morton, Morton3D(0b010_001));
+        assert_eq!(morton, Morton3D::from(0b010_001));
 
         let morton = morton.increase_y();
-        assert_eq!(morton, Morton3D(0b010_011));
+        assert_eq!(morton, Morton3D::from(0b010_011));
 
         let morton = morton.increase_y().increase_z();
-        assert_eq!(morton, Morton3D(0b010_000_101));
+        assert_eq!(morton, Morton3D::from(0b010_000_101));
     }
 
     #[test]
     fn test_dec() {
-        let morton = Morton3D(0b010_000_101);
+        let morton = Morton3D::from(0b010_000_101);
 
         let morton = morton.decrease_y();
-        assert_eq!(morton, Morton3D(0b000_010_111));
+        assert_eq!(morton, Morton3D::from(0b000_010_111));
 
         let morton = morton.decrease_y();
-        assert_eq!(morton, Morton3D(0b000_010_101));
+        assert_eq!(morton, Morton3D::from(0b000_010_101));
 
         let morton = morton.decrease_z().decrease_y();
-        assert_eq!(morton, Morton3D(0b000_000_011));
+        assert_eq!(morton, Morton3D::from(0b000_000_011));
 
         let morton = morton.decrease_y();
-        assert_eq!(morton, Morton3D(0b000_000_001));
+        assert_eq!(morton, Morton3D::from(0b000_000_001));
+    }
+
+    #[test]
+    fn test_checked() {
+        let morton = Morton3D::from_coords(0, 5, Morton3D::MAX_COORD);
+        assert_eq!(morton.checked_decrease_x(), None);
+        assert_eq!(morton.checked_increase_z(), None);
+        assert_eq!(
+            morton.checked_increase_x(),
+            Some(Morton3D::from_coords(1, 5, Morton3D::MAX_COORD))
+        );
+        assert_eq!(morton.checked_decrease_y(), Some(morton.decrease_y()));
+        assert_eq!(morton.checked_increase_y(), Some(morton.increase_y()));
+        assert_eq!(
+            morton.checked_decrease_z(),
+            Some(Morton3D::from_coords(0, 5, Morton3D::MAX_COORD - 1))
+        );
+
+        let mut morton = Morton3D::from_coords(0, 0, 0);
+        morton.set_flag();
+        let morton = morton.checked_increase_x().unwrap();
+        assert!(morton.is_flag_set());
+        assert_eq!(morton.to_coords(), (1, 0, 0));
+
+        let morton = Morton2D::from_coords(Morton2D::MAX_COORD, 0);
+        assert_eq!(morton.checked_increase_x(), None);
+        assert_eq!(morton.checked_decrease_y(), None);
+    }
+
+    #[test]
+    fn test_saturating() {
+        let morton = Morton3D::from_coords(0, 5, Morton3D::MAX_COORD);
+        assert_eq!(morton.saturating_decrease_x(), morton);
+        assert_eq!(morton.saturating_increase_z(), morton);
+        assert_eq!(
+            morton.saturating_increase_x().to_coords(),
+            (1, 5, Morton3D::MAX_COORD)
+        );
+        assert_eq!(
+            morton.saturating_decrease_y().to_coords(),
+            (0, 4, Morton3D::MAX_COORD)
+        );
+        assert_eq!(
+            morton.saturating_decrease_z().saturating_increase_z(),
+            morton
+        );
+
+        let morton = Morton2D::from_coords(0, Morton2D::MAX_COORD);
+        assert_eq!(morton.saturating_decrease_x(), morton);
+        assert_eq!(morton.saturating_increase_y(), morton);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let morton = Morton3D::from_coords(0, 5, Morton3D::MAX_COORD);
+        assert_eq!(
+            morton.wrapping_decrease_x().to_coords(),
+            (Morton3D::MAX_COORD, 5, Morton3D::MAX_COORD)
+        );
+        assert_eq!(morton.wrapping_increase_z().to_coords(), (0, 5, 0));
+        assert_eq!(morton.wrapping_increase_y(), morton.increase_y());
+        assert_eq!(morton.wrapping_decrease_z(), morton.decrease_z());
+
+        let mut morton = Morton3D::from_coords(0, 5, 9);
+        morton.set_flag();
+        assert_eq!(morton.wrapping_decrease_x_mod(10).to_coords(), (9, 5, 9));
+        assert_eq!(morton.wrapping_increase_z_mod(10).to_coords(), (0, 5, 0));
+        assert_eq!(morton.wrapping_increase_y_mod(10).to_coords(), (0, 6, 9));
+        assert_eq!(morton.wrapping_decrease_y_mod(10).to_coords(), (0, 4, 9));
+        assert!(morton.wrapping_decrease_x_mod(10).is_flag_set());
+        assert!(morton.wrapping_increase_z_mod(10).is_flag_set());
+
+        let morton = Morton2D::from_coords(0, 7);
+        assert_eq!(morton.wrapping_decrease_x_mod(8).to_coords(), (7, 7));
+        assert_eq!(morton.wrapping_increase_y_mod(8).to_coords(), (0, 0));
+        assert_eq!(
+            morton.wrapping_decrease_x().to_coords(),
+            (Morton2D::MAX_COORD, 7)
+        );
+    }
+
+    #[test]
+    fn test_overflowing() {
+        let morton = Morton3D::from_coords(0, 5, Morton3D::MAX_COORD);
+        assert_eq!(
+            morton.overflowing_decrease_x(),
+            (morton.wrapping_decrease_x(), true)
+        );
+        assert_eq!(
+            morton.overflowing_increase_x(),
+            (morton.increase_x(), false)
+        );
+        assert_eq!(
+            morton.overflowing_decrease_y(),
+            (morton.decrease_y(), false)
+        );
+        assert_eq!(
+            morton.overflowing_increase_y(),
+            (morton.increase_y(), false)
+        );
+        assert_eq!(
+            morton.overflowing_increase_z(),
+            (Morton3D::from_coords(0, 5, 0), true)
+        );
+        assert_eq!(
+            morton.overflowing_decrease_z(),
+            (morton.decrease_z(), false)
+        );
+
+        let morton = Morton2D::from_coords(Morton2D::MAX_COORD, 0);
+        assert!(morton.overflowing_increase_x().1);
+        assert!(morton.overflowing_decrease_y().1);
+        assert!(!morton.overflowing_increase_y().1);
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let morton = Morton3D::from_coords(100, 5, 500);
+        assert_eq!(morton.add_x(28).to_coords(), (128, 5, 500));
+        assert_eq!(morton.sub_y(5).to_coords(), (100, 0, 500));
+        assert_eq!(morton.add_z(12).sub_z(512).to_coords(), (100, 5, 0));
+        let mut step = morton;
+        for _ in 0..37 {
+            step = step.increase_y();
+        }
+        assert_eq!(morton.add_y(37), step);
+
+        let mut flagged = morton;
+        flagged.set_flag();
+        assert!(flagged.add_x(1 << 9).is_flag_set());
+        assert!(flagged.sub_z(7).is_flag_set());
+
+        let max = Morton3D::MAX_COORD;
+        assert_eq!(morton.checked_add_x(max - 100), Some(morton.with_x(max)));
+        assert_eq!(morton.checked_add_x(max - 99), None);
+        assert_eq!(morton.checked_add_x(u32::MAX), None);
+        assert_eq!(morton.checked_sub_y(5), Some(morton.with_y(0)));
+        assert_eq!(morton.checked_sub_y(6), None);
+
+        assert_eq!(morton.saturating_add_z(max), morton.with_z(max));
+        assert_eq!(morton.saturating_sub_x(500), morton.with_x(0));
+        assert_eq!(morton.saturating_sub_x(1), morton.with_x(99));
+
+        assert_eq!(morton.wrapping_add_x(max), morton.with_x(99));
+        assert_eq!(morton.wrapping_sub_y(6), morton.with_y(max));
+        assert_eq!(morton.wrapping_add_z(3), morton.with_z(503));
+
+        let morton = Morton2D::from_coords(3, 4);
+        assert_eq!(morton.add_x(5).sub_y(4).to_coords(), (8, 0));
+        assert_eq!(morton.checked_sub_x(4), None);
+        assert_eq!(
+            morton.saturating_add_y(Morton2D::MAX_COORD).to_coords(),
+            (3, Morton2D::MAX_COORD)
+        );
+        assert_eq!(
+            morton.wrapping_sub_x(4).to_coords(),
+            (Morton2D::MAX_COORD, 4)
+        );
+    }
+
+    #[test]
+    fn test_boundary() {
+        let morton = Morton3D::from_coords(0, 7, 3);
+        assert!(morton.is_min_x());
+        assert!(!morton.is_min_y());
+        assert!(morton.is_max_y(3));
+        assert!(!morton.is_max_y(4));
+        assert!(!morton.is_max_z(3));
+        assert!(morton.is_max_z(2));
+        assert!(morton.is_max_x(0));
+        assert!(Morton3D::from_coords(0, 0, Morton3D::MAX_COORD).is_max_z(Morton3D::MAX_DEPTH));
+
+        let mut flagged = Morton3D::from_coords(1, 0, 0);
+        flagged.set_flag();
+        assert!(flagged.is_max_x(1));
+        assert!(flagged.is_min_z());
+
+        let morton = Morton2D::from_coords(15, 0);
+        assert!(morton.is_max_x(4));
+        assert!(morton.is_min_y());
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        let a = Morton3D::from_coords(0, 0, 0);
+        let mut b = Morton3D::from_coords(0, 0, 0);
+        b.set_flag();
+        assert_eq!(a.common_prefix_bits(b), 3 * Morton3D::MAX_DEPTH);
+        assert_eq!(a.first_diff_level(b), None);
+
+        let b = Morton3D::from_coords(1, 0, 0);
+        assert_eq!(a.common_prefix_bits(b), 3 * Morton3D::MAX_DEPTH - 1);
+        assert_eq!(a.first_diff_level(b), Some(Morton3D::MAX_DEPTH));
+
+        let top = 1 << (Morton3D::MAX_DEPTH - 1);
+        let b = Morton3D::from_coords(0, 0, top);
+        assert_eq!(a.common_prefix_bits(b), 0);
+        assert_eq!(a.first_diff_level(b), Some(1));
+        let b = Morton3D::from_coords(top, 0, 0);
+        assert_eq!(a.common_prefix_bits(b), 2);
+        assert_eq!(a.first_diff_level(b), Some(1));
+
+        let b = Morton3D::from_coords(0, top >> 2, 0);
+        assert_eq!(a.first_diff_level(b), Some(3));
+        assert_eq!(a.ancestor_at(2), b.ancestor_at(2));
+        assert_ne!(a.ancestor_at(3), b.ancestor_at(3));
+
+        let a = Morton2D::from_coords(0, 0);
+        let b = Morton2D::from_coords(0, 1);
+        assert_eq!(a.common_prefix_bits(b), 2 * Morton2D::MAX_DEPTH - 2);
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn test_step() {
+        let a = Morton3D::from_coords(1, 0, 0);
+        let b = Morton3D::from_coords(1, 1, 1);
+        let codes: Vec<_> = (a..=b).map(usize::from).collect();
+        assert_eq!(codes, (usize::from(a)..=usize::from(b)).collect::<Vec<_>>());
+        assert_eq!((a..b).count(), 6);
+        assert_eq!((b..a).count(), 0);
+
+        let (mut a, mut b) = (a, b);
+        a.set_flag();
+        b.set_flag();
+        assert!((a..=b).all(|v| v.is_flag_set()));
+        assert_eq!((a..=b).next_back(), Some(b));
+
+        let max = Morton3D16::from_coords(31, 31, 31);
+        assert_eq!(core::iter::Step::forward_checked(max, 1), None);
+        // unused bits between the code and the flag
+        let max = Morton2D::from_coords(Morton2D::MAX_COORD, Morton2D::MAX_COORD);
+        assert_eq!(core::iter::Step::forward_checked(max, 1), None);
+        let before = Morton2D::from_coords(Morton2D::MAX_COORD - 1, Morton2D::MAX_COORD);
+        assert_eq!((before..=max).collect::<Vec<_>>(), vec![before, max]);
+        let max = Morton3D32::from_coords(1023, 1023, 1023);
+        assert_eq!(core::iter::Step::forward_checked(max, 1), None);
+        let steps = (1 << 30) - 1;
+        assert_eq!(
+            core::iter::Step::steps_between(&Morton3D32::default(), &max),
+            (steps, Some(steps))
+        );
+        assert_eq!(
+            core::iter::Step::backward_checked(Morton3D16::default(), 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_prev_on_curve() {
+        let mut v = Morton3D::from_coords(1, 1, 1);
+        v.set_flag();
+        let next = v.next_on_curve().unwrap();
+        assert_eq!(next.to_coords(), (2, 0, 0));
+        assert!(next.is_flag_set());
+        assert_eq!(next.prev_on_curve(), Some(v));
+        assert_eq!(Morton3D::default().prev_on_curve(), None);
+
+        let max = Morton3D::MAX_COORD;
+        let mut last = Morton3D::from_coords(max, max, max);
+        assert_eq!(last.next_on_curve(), None);
+        last.set_flag();
+        assert_eq!(last.next_on_curve(), None);
+
+        // unused bit between the code and the flag
+        let max = Morton2D::MAX_COORD;
+        let last = Morton2D::from_coords(max, max);
+        assert_eq!(last.next_on_curve(), None);
+        assert_eq!(
+            last.prev_on_curve().and_then(Morton2D::next_on_curve),
+            Some(last)
+        );
+    }
+
+    #[test]
+    fn test_offset_on_curve() {
+        let origin = Morton3D::from_coords(1, 2, 3);
+        let mut end = Morton3D::from_coords(9, 8, 7);
+        end.set_flag();
+        let len = end.offset_from(origin).unwrap();
+        assert_eq!(
+            len,
+            usize::from(Morton3D::from_coords(9, 8, 7)) - usize::from(origin)
+        );
+        assert_eq!(origin.offset_from(end), None);
+        assert_eq!(origin.offset_from(origin), Some(0));
+
+        // split into equal pieces
+        let mid = origin.offset_by(len / 2).unwrap();
+        assert_eq!(mid.offset_from(origin), Some(len / 2));
+        assert_eq!(end.offset_from(mid), Some(len - len / 2));
+        let mut plain_end = end;
+        plain_end.unset_flag();
+        assert_eq!(origin.offset_by(len), Some(plain_end));
+        assert_eq!(origin.offset_by(0), Some(origin));
+        assert_eq!(origin.offset_by(1), origin.next_on_curve());
+
+        let max = Morton3D16::MAX_COORD;
+        let last = Morton3D16::from_coords(max, max, max);
+        assert_eq!(Morton3D16::default().offset_by(last.raw()), Some(last));
+        assert_eq!(Morton3D16::default().offset_by(last.raw() + 1), None);
+        assert_eq!(last.offset_by(u16::MAX), None);
+    }
+
+    #[test]
+    fn test_is_within() {
+        let min = Morton3D::from_coords(2, 3, 1);
+        let max = Morton3D::from_coords(6, 4, 7);
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    let mut v = Morton3D::from_coords(x, y, z);
+                    v.set_flag();
+                    let expected =
+                        (2..=6).contains(&x) && (3..=4).contains(&y) && (1..=7).contains(&z);
+                    assert_eq!(v.is_within(min, max), expected);
+                }
+            }
+        }
+        const INSIDE: [bool; 2] = [
+            Morton2D::from_coords(1, 2)
+                .is_within(Morton2D::from_coords(0, 2), Morton2D::from_coords(1, 3)),
+            Morton2D::from_coords(2, 2)
+                .is_within(Morton2D::from_coords(0, 2), Morton2D::from_coords(1, 3)),
+        ];
+        assert_eq!(INSIDE, [true, false]);
+    }
+
+    #[test]
+    fn test_bigmin_litmax() {
+        // check against a linear scan of the curve
+        let min = Morton2D::from_coords(3, 5);
+        let max = Morton2D::from_coords(5, 10);
+        let inside = |v: usize| {
+            let (x, y) = Morton2D::from(v).to_coords();
+            (3..=5).contains(&x) && (5..=10).contains(&y)
+        };
+        let (lo, hi) = (usize::from(min), usize::from(max));
+        for v in lo + 1..hi {
+            if inside(v) {
+                continue;
+            }
+            let expected = (v + 1..=hi).find(|&c| inside(c)).unwrap();
+            assert_eq!(usize::from(Morton2D::from(v).bigmin(min, max)), expected);
+            let expected = (lo..v).rev().find(|&c| inside(c)).unwrap();
+            assert_eq!(usize::from(Morton2D::from(v).litmax(min, max)), expected);
+        }
+
+        let min = Morton3D::from_coords(2, 3, 1);
+        let max = Morton3D::from_coords(6, 4, 7);
+        let mut outside = Morton3D::from_coords(7, 3, 1);
+        outside.set_flag();
+        let next = outside.bigmin(min, max);
+        assert!(!next.is_flag_set());
+        assert!(next > Morton3D::from_coords(7, 3, 1));
+        let prev = outside.litmax(min, max);
+        assert!(prev < Morton3D::from_coords(7, 3, 1));
+        for v in [next, prev] {
+            let (x, y, z) = v.to_coords();
+            assert!((2..=6).contains(&x) && (3..=4).contains(&y) && (1..=7).contains(&z));
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut morton = Morton3D::from_coords(10, 20, 30);
+        morton.set_flag();
+        let moved = morton.translate(Offset3D::new(-10, 5, 0)).unwrap();
+        assert_eq!(moved.to_coords(), (0, 25, 30));
+        assert!(moved.is_flag_set());
+        assert_eq!(moved.translate(-Offset3D::new(-10, 5, 0)), Some(morton));
+        assert_eq!(morton.translate(Offset3D::ZERO), Some(morton));
+
+        assert_eq!(morton.translate(Offset3D::new(-11, 0, 0)), None);
+        assert_eq!(morton.translate(Offset3D::new(0, 0, i32::MIN)), None);
+        assert_eq!(morton.translate(Offset3D::new(0, i32::MAX, 0)), None);
+        assert_eq!(Offset3D::new(0, 0, i32::MIN).checked_neg(), None);
+        assert_eq!(
+            Offset3D::new(1, i32::MIN, i32::MAX).wrapping_neg(),
+            Offset3D::new(-1, i32::MIN, -i32::MAX)
+        );
+        assert_eq!(
+            Offset3D::new(3, -4, 0).checked_neg(),
+            Some(-Offset3D::new(3, -4, 0))
+        );
+        let max = Morton3D::MAX_COORD as i32;
+        assert_eq!(
+            morton
+                .translate((0, 0, max - 30).into())
+                .map(Morton3D::to_coords),
+            Some((10, 20, Morton3D::MAX_COORD))
+        );
+        assert_eq!(morton.translate((0, 0, max - 29).into()), None);
+
+        let small = Morton3D16::from_coords(1, 2, 3);
+        assert_eq!(small.translate(Offset3D::new(1 << 16, 0, 0)), None);
+        assert_eq!(
+            small
+                .translate(Offset3D::new(30, -2, 0))
+                .map(Morton3D16::to_coords),
+            Some((31, 0, 3))
+        );
+    }
+
+    #[test]
+    fn test_add_sub_codes() {
+        let mut a = Morton3D::from_coords(10, 20, 30);
+        a.set_flag();
+        let b = Morton3D::from_coords(5, 0, 100);
+        let sum = a + b;
+        assert_eq!(sum.to_coords(), (15, 20, 130));
+        assert!(sum.is_flag_set());
+        assert_eq!(sum - b, a);
+        assert!(!(b + a).is_flag_set());
+
+        let mut c = b;
+        c += Morton3D::from_coords(1, 2, 3);
+        c -= Morton3D::from_coords(6, 0, 0);
+        assert_eq!(c.to_coords(), (0, 2, 103));
+
+        let max = Morton3D::from_coords(Morton3D::MAX_COORD, 0, 0);
+        let one = Morton3D::from_coords(1, 1, 1);
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(max.wrapping_add(one).to_coords(), (0, 1, 1));
+        assert_eq!(one.checked_sub(max), None);
+        assert_eq!(
+            one.wrapping_sub(b).to_coords(),
+            (Morton3D::MAX_COORD - 3, 1, Morton3D::MAX_COORD - 98)
+        );
+        assert_eq!(b.checked_sub(b), Some(Morton3D::default()));
+
+        let a = MortonND::<5>::from_array([1, 2, 3, 4, 5]);
+        let b = MortonND::<5>::from_array([5, 4, 3, 2, 1]);
+        assert_eq!((a + b).to_array(), [6; 5]);
+        assert_eq!((a + b - a), b);
+    }
+
+    #[test]
+    fn test_component_min_max() {
+        let a = Morton3D::from_coords(10, 200, 30);
+        let mut b = Morton3D::from_coords(50, 7, 30);
+        assert_eq!(a.component_min(b).to_coords(), (10, 7, 30));
+        assert_eq!(a.component_max(b).to_coords(), (50, 200, 30));
+        assert_eq!(a.component_min(a), a);
+
+        b.set_flag();
+        assert!(b.component_min(a).is_flag_set());
+        assert!(!a.component_max(b).is_flag_set());
+
+        let codes = [(3, 9, 1), (0, 4, 4), (8, 2, 6)].map(Morton3D::from);
+        let min = codes.iter().copied().reduce(Morton3D::component_min);
+        let max = codes.iter().copied().reduce(Morton3D::component_max);
+        assert_eq!(min.map(Morton3D::to_coords), Some((0, 2, 1)));
+        assert_eq!(max.map(Morton3D::to_coords), Some((8, 9, 6)));
+
+        let a = Morton2D::from_coords(1, 5);
+        let b = Morton2D::from_coords(4, 2);
+        assert_eq!(a.component_min(b).to_coords(), (1, 2));
+        assert_eq!(a.component_max(b).to_coords(), (4, 5));
+    }
+
+    #[test]
+    fn test_delta() {
+        let a = Morton3D::from_coords(10, 200, 30);
+        let mut b = Morton3D::from_coords(50, 7, 30);
+        b.set_flag();
+        assert_eq!(a.delta(b), (40, -193, 0));
+        assert_eq!(b.delta(a), (-40, 193, 0));
+        assert_eq!(
+            a.translate(a.delta(b).into()).map(Morton3D::to_coords),
+            Some(b.to_coords())
+        );
+
+        let max = Morton3D::MAX_COORD;
+        let far = Morton3D::from_coords(max, 0, max);
+        assert_eq!(Morton3D::default().delta(far), (max as i32, 0, max as i32));
+        assert_eq!(
+            Morton2D::from_coords(0, Morton2D::MAX_COORD).delta(Morton2D::from_coords(9, 0)),
+            (9, -(Morton2D::MAX_COORD as i32))
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Morton3D::from_coords(10, 200, 30);
+        let mut b = Morton3D::from_coords(50, 7, 30);
+        b.set_flag();
+        assert_eq!(a.chebyshev_distance(b), 193);
+        assert_eq!(a.manhattan_distance(b), 233);
+        assert_eq!(a.euclidean_distance_squared(b), 40 * 40 + 193 * 193);
+        assert_eq!(b.manhattan_distance(a), a.manhattan_distance(b));
+        assert_eq!(a.chebyshev_distance(a), 0);
+
+        let max = Morton3D::MAX_COORD;
+        let far = Morton3D::from_coords(max, max, max);
+        assert_eq!(Morton3D::default().manhattan_distance(far), 3 * max as u64);
+        assert_eq!(
+            Morton3D::default().euclidean_distance_squared(far),
+            3 * max as u64 * max as u64
+        );
+
+        let max = Morton2D::MAX_COORD;
+        let far = Morton2D::from_coords(max, max);
+        assert_eq!(Morton2D::default().chebyshev_distance(far), max);
+        assert_eq!(
+            Morton2D::default().euclidean_distance_squared(far),
+            2 * max as u64 * max as u64
+        );
+    }
+
+    #[test]
+    fn test_2d_flag() {
+        let mut morton = Morton2D::from(0);
+        morton.set_flag();
+        assert!(morton.is_flag_set());
+        morton.unset_flag();
+        assert_eq!(morton, Morton2D::from(0));
+        assert!(!morton.is_flag_set());
+    }
+
+    #[test]
+    fn test_2d_coords() {
+        assert_eq!(Morton2D::from_coords(0b11, 0b01), Morton2D::from(0b01_11));
+        assert_eq!(Morton2D::from_coords(0b10, 0b11), Morton2D::from(0b11_10));
+
+        let max = Morton2D::MAX_COORD;
+        for &(x, y) in &[(0, 0), (1, 2), (max, 0), (max, max), (max / 3, 12345)] {
+            let mut morton = Morton2D::from_coords(x, y);
+            assert!(!morton.is_flag_set());
+            assert_eq!(morton.to_coords(), (x, y));
+            morton.set_flag();
+            assert_eq!(morton.to_coords(), (x, y));
+        }
+        assert_eq!(
+            Morton2D::from_coords(u32::MAX, u32::MAX),
+            Morton2D::from_coords(max, max)
+        );
+        assert!(Morton2D::try_from_coords(max, max).is_ok());
+        assert!(Morton2D::try_from_coords(0, max + 1).is_err());
+    }
+
+    #[test]
+    fn test_2d_inc_dec() {
+        let morton = Morton2D::from_coords(1, 1);
+        assert_eq!(morton.increase_x(), Morton2D::from_coords(2, 1));
+        assert_eq!(
+            morton.increase_y().increase_y(),
+            Morton2D::from_coords(1, 3)
+        );
+        assert_eq!(morton.decrease_x(), Morton2D::from_coords(0, 1));
+        assert_eq!(morton.decrease_y().decrease_x(), Morton2D::from(0));
+        assert_eq!(morton.with_y(7), Morton2D::from_coords(1, 7));
+    }
+
+    #[test]
+    fn test_nd() {
+        assert_eq!(MortonND::<2>::MAX_DEPTH, (usize::BITS - 1) / 2);
+        assert_eq!(MortonND::<5>::MAX_DEPTH, (usize::BITS - 1) / 5);
+        assert_eq!(MortonND::<8>::MAX_DEPTH, (usize::BITS - 1) / 8);
+
+        assert_eq!(
+            usize::from(MortonND::<5>::from_array([1, 0, 1, 1, 0])),
+            0b01101
+        );
+        let coords = [1, 6, 3, 2, 5, 7, 4, 0];
+        let morton = MortonND::<8>::from_array(coords);
+        assert_eq!(morton.to_array(), coords);
+        assert_eq!(morton.increase_nth_dim(7).nth_dim(7), 1);
+        assert_eq!(morton.decrease_nth_dim(1).nth_dim(1), 5);
+        assert_eq!(
+            morton.with_nth_dim(4, 0).to_array(),
+            [1, 6, 3, 2, 0, 7, 4, 0]
+        );
+
+        let max = MortonND::<5>::MAX_COORD;
+        let mut morton = MortonND::<5>::from_array([max; 5]);
+        assert!(!morton.is_flag_set());
+        morton.set_flag();
+        assert_eq!(morton.to_array(), [max; 5]);
+        assert!(MortonND::<5>::try_from_array([0, 0, max + 1, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_nd_matches_3d() {
+        for &(x, y, z) in &[
+            (0, 0, 0),
+            (1, 2, 3),
+            (1000, 0, 77),
+            (Morton3D::MAX_COORD, 5, 1),
+        ] {
+            let morton = Morton3D::from_coords(x, y, z);
+            assert_eq!(Morton3D::from_array([x, y, z]), morton);
+            assert_eq!(morton.to_array(), [x, y, z]);
+        }
     }
 }
@@ -0,0 +1,262 @@
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+
+use crate::MortonKey;
+
+/// Linear octree, the sorted leaves of an octree without its inner cells.
+///
+/// Leaves never overlap each other. They usually cover the whole space (see
+/// [`LinearOctree::is_complete`]), which [`LinearOctree::refine`] and [`LinearOctree::coarsen`] keep.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LinearOctree {
+    leaves: Vec<MortonKey>,
+}
+
+impl Default for LinearOctree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinearOctree {
+    /// octree made of the root cell only
+    pub fn new() -> Self {
+        Self {
+            leaves: alloc::vec![MortonKey::ROOT],
+        }
+    }
+
+    /// octree with `leaves` in any order, `None` if some of them overlap.
+    pub fn from_leaves(mut leaves: Vec<MortonKey>) -> Option<Self> {
+        leaves.sort_unstable();
+        // in preorder, a cell overlapping an earlier one is below the previous one
+        if leaves
+            .windows(2)
+            .any(|w| w[0] == w[1] || w[0].is_ancestor_of(w[1]))
+        {
+            return None;
+        }
+        Some(Self { leaves })
+    }
+
+    /// sorted leaves
+    pub fn leaves(&self) -> &[MortonKey] {
+        &self.leaves
+    }
+    pub fn into_leaves(self) -> Vec<MortonKey> {
+        self.leaves
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// leaf containing `key` (or `key` itself).
+    pub fn leaf_containing(&self, key: MortonKey) -> Option<MortonKey> {
+        let i = self.leaves.partition_point(|&k| k <= key);
+        self.leaves[..i]
+            .last()
+            .copied()
+            .filter(|&k| k == key || k.is_ancestor_of(key))
+    }
+
+    /// whether the leaves cover the whole space without gaps.
+    pub fn is_complete(&self) -> bool {
+        // cell whose corner the next leaf has to start at
+        let mut next = Some(MortonKey::ROOT);
+        for &leaf in &self.leaves {
+            match next {
+                Some(cell)
+                    if leaf == cell
+                        || (cell.is_ancestor_of(leaf) && leaf.anchor() == cell.anchor()) =>
+                {
+                    next = leaf.next_subtree(MortonKey::ROOT);
+                }
+                _ => return false,
+            }
+        }
+        next.is_none()
+    }
+
+    /// split the leaves for which `predicate` returns `true` into their children,
+    /// return the number of split leaves.
+    ///
+    /// Leaves at [`MortonKey::MAX_DEPTH`] are never split. It refines by one depth,
+    /// call it again to refine the new leaves further.
+    pub fn refine<F: FnMut(MortonKey) -> bool>(&mut self, mut predicate: F) -> usize {
+        let mut count = 0;
+        let mut leaves = Vec::with_capacity(self.leaves.len());
+        for &leaf in &self.leaves {
+            if leaf.depth() < MortonKey::MAX_DEPTH && predicate(leaf) {
+                leaves.extend(leaf.children().iter());
+                count += 1;
+            } else {
+                leaves.push(leaf);
+            }
+        }
+        self.leaves = leaves;
+        count
+    }
+
+    /// merge families of 8 sibling leaves into their parent when `predicate` returns `true` for
+    /// the parent, return the number of merged families.
+    ///
+    /// It coarsens by one depth, call it again to coarsen the new leaves further.
+    pub fn coarsen<F: FnMut(MortonKey) -> bool>(&mut self, mut predicate: F) -> usize {
+        let mut count = 0;
+        let mut leaves = Vec::with_capacity(self.leaves.len());
+        let mut i = 0;
+        while let Some(&leaf) = self.leaves.get(i) {
+            let family = leaf
+                .parent()
+                .filter(|parent| self.leaves[i..].starts_with(&parent.children()));
+            match family {
+                Some(parent) if predicate(parent) => {
+                    leaves.push(parent);
+                    count += 1;
+                    i += 8;
+                }
+                _ => {
+                    leaves.push(leaf);
+                    i += 1;
+                }
+            }
+        }
+        self.leaves = leaves;
+        count
+    }
+
+    /// split the leaves into `parts` contiguous pieces of the curve with (almost) the same number
+    /// of leaves, e.g. one per process.
+    ///
+    /// panic if `parts` is 0.
+    pub fn partition(&self, parts: usize) -> Partitions<'_> {
+        assert!(parts > 0, "no parts");
+        Partitions {
+            leaves: &self.leaves,
+            parts,
+            next: 0,
+        }
+    }
+}
+
+/// Iterator over the pieces of a [`LinearOctree`], see [`LinearOctree::partition`].
+///
+/// Piece `i` of `n` holds the leaves `i * len / n..(i + 1) * len / n`.
+#[derive(Debug, Clone)]
+pub struct Partitions<'a> {
+    leaves: &'a [MortonKey],
+    parts: usize,
+    next: usize,
+}
+
+impl Partitions<'_> {
+    fn bound(&self, part: usize) -> usize {
+        // avoids overflow of `part * len`
+        let len = self.leaves.len();
+        len / self.parts * part + len % self.parts * part / self.parts
+    }
+}
+
+impl<'a> Iterator for Partitions<'a> {
+    type Item = &'a [MortonKey];
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.parts {
+            return None;
+        }
+        let piece = &self.leaves[self.bound(self.next)..self.bound(self.next + 1)];
+        self.next += 1;
+        Some(piece)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.parts - self.next;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Partitions<'_> {}
+impl FusedIterator for Partitions<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::LinearOctree;
+    use crate::MortonKey;
+
+    #[test]
+    fn test_refine_coarsen() {
+        let root = MortonKey::ROOT;
+        let mut octree = LinearOctree::new();
+        assert!(octree.is_complete());
+
+        assert_eq!(octree.refine(|_| true), 1);
+        assert_eq!(octree.leaves(), &root.children()[..]);
+        assert_eq!(octree.refine(|k| k.octant() % 2 == 0), 4);
+        assert_eq!(octree.len(), 4 + 4 * 8);
+        assert!(octree.is_complete());
+        assert!(octree.leaves().windows(2).all(|w| w[0] < w[1]));
+
+        // only complete families are merged
+        let removed = octree.leaves()[3];
+        let mut partial = LinearOctree::from_leaves(
+            octree
+                .leaves()
+                .iter()
+                .copied()
+                .filter(|&k| k != removed)
+                .collect(),
+        )
+        .unwrap();
+        assert!(!partial.is_complete());
+        assert_eq!(partial.coarsen(|_| true), 3);
+        assert_eq!(partial.len(), 4 + 7 + 3);
+
+        assert_eq!(octree.coarsen(|k| k != root.child(2)), 3);
+        assert_eq!(octree.len(), 7 + 8);
+        assert!(octree.is_complete());
+        assert_eq!(octree.coarsen(|k| k != root.child(2)), 0);
+        assert_eq!(
+            octree.leaf_containing(root.child(2).child(5).child(1)),
+            Some(root.child(2).child(5))
+        );
+        octree.refine(|k| k.depth() == 1);
+        octree.coarsen(|_| true);
+        octree.coarsen(|_| true);
+        assert_eq!(octree, LinearOctree::new());
+    }
+
+    #[test]
+    fn test_from_leaves() {
+        let root = MortonKey::ROOT;
+        assert_eq!(LinearOctree::from_leaves(vec![root, root.child(1)]), None);
+        assert_eq!(
+            LinearOctree::from_leaves(vec![root.child(1), root.child(1)]),
+            None
+        );
+        let mut leaves = root.child(6).children().to_vec();
+        leaves.reverse();
+        let octree = LinearOctree::from_leaves(leaves).unwrap();
+        assert_eq!(octree.leaves(), &root.child(6).children()[..]);
+        assert!(!octree.is_complete());
+        assert!(!LinearOctree::from_leaves(Vec::new()).unwrap().is_complete());
+        let gap = vec![root.child(0), root.child(2)];
+        assert!(!LinearOctree::from_leaves(gap).unwrap().is_complete());
+    }
+
+    #[test]
+    fn test_partition() {
+        let mut octree = LinearOctree::new();
+        octree.refine(|_| true);
+        octree.refine(|k| k.octant() < 3);
+        assert_eq!(octree.len(), 5 + 24);
+
+        let parts: Vec<_> = octree.partition(4).collect();
+        assert_eq!(parts.len(), 4);
+        assert!(parts.iter().all(|p| p.len() == 7 || p.len() == 8));
+        assert_eq!(parts.concat(), octree.leaves());
+        assert_eq!(octree.partition(100).filter(|p| p.is_empty()).count(), 71);
+        assert_eq!(octree.partition(1).next(), Some(octree.leaves()));
+    }
+}
@@ -0,0 +1,290 @@
+use alloc::collections::{btree_map, BTreeMap};
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ops::Bound;
+use core::slice;
+
+use crate::{Morton3D, MortonBox, MortonKey};
+
+/// Loose octree of objects given by their bounding boxes, a broad-phase structure.
+///
+/// Each cell is loosened by half of its size on every side, and an object is stored
+/// at the cell containing the center of its box at the deepest depth where the loosened cell
+/// still contains the whole box. Objects are never split or stored twice, so moving
+/// an object only touches one cell, at the cost of looking into the loosened neighbors on queries.
+///
+/// Boxes are in cells at [`MortonKey::MAX_DEPTH`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LooseOctree<T> {
+    cells: BTreeMap<MortonKey, Vec<(MortonBox, T)>>,
+    len: usize,
+}
+
+impl<T> Default for LooseOctree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the cell loosened by half of its size on every side, clamped to the space.
+pub fn loose_bounds(key: MortonKey) -> MortonBox {
+    let last = MortonKey::ROOT.size() - 1;
+    let (size, half) = (key.size(), key.size() / 2);
+    let (x, y, z) = key.anchor().to_coords();
+    let [lx, ly, lz] = [x, y, z].map(|v| v.saturating_sub(half));
+    let [hx, hy, hz] = [x, y, z].map(|v| (v + (size - 1)).saturating_add(half).min(last));
+    MortonBox::new(
+        Morton3D::from_coords(lx, ly, lz),
+        Morton3D::from_coords(hx, hy, hz),
+    )
+}
+
+impl<T> LooseOctree<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// number of objects
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// cell storing an object with the bounding box `bounds`.
+    pub fn cell_of(bounds: MortonBox) -> MortonKey {
+        let (min, max) = (bounds.min().to_coords(), bounds.max().to_coords());
+        let extent = (max.0 - min.0).max(max.1 - min.1).max(max.2 - min.2) + 1;
+        // deepest cell at least as large as the box, its loosened cell contains the box
+        let levels = u32::BITS - (extent - 1).leading_zeros();
+        let center = Morton3D::from_coords(
+            min.0 + (max.0 - min.0) / 2,
+            min.1 + (max.1 - min.1) / 2,
+            min.2 + (max.2 - min.2) / 2,
+        );
+        MortonKey::from_leaf(center, MortonKey::MAX_DEPTH - levels)
+    }
+
+    /// add an object, return the cell storing it.
+    pub fn insert(&mut self, bounds: MortonBox, value: T) -> MortonKey {
+        let key = Self::cell_of(bounds);
+        self.cells.entry(key).or_default().push((bounds, value));
+        self.len += 1;
+        key
+    }
+
+    /// remove an object equal to `value` with the bounding box `bounds`, return it.
+    pub fn remove(&mut self, bounds: MortonBox, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let key = Self::cell_of(bounds);
+        let objects = self.cells.get_mut(&key)?;
+        let i = objects
+            .iter()
+            .position(|(b, v)| *b == bounds && v == value)?;
+        let (_, removed) = objects.swap_remove(i);
+        if objects.is_empty() {
+            self.cells.remove(&key);
+        }
+        self.len -= 1;
+        Some(removed)
+    }
+
+    /// keep only the objects for which `f` returns `true`.
+    pub fn retain<F: FnMut(MortonBox, &mut T) -> bool>(&mut self, mut f: F) {
+        let mut len = 0;
+        self.cells.retain(|_, objects| {
+            objects.retain_mut(|(b, v)| f(*b, v));
+            len += objects.len();
+            !objects.is_empty()
+        });
+        self.len = len;
+    }
+
+    /// objects in preorder of their cells
+    pub fn iter(&self) -> LooseIter<'_, T> {
+        LooseIter {
+            cells: self.cells.values(),
+            objects: [].iter(),
+        }
+    }
+
+    /// objects whose bounding boxes overlap `bounds`, in preorder of their cells.
+    ///
+    /// Subtrees whose loosened cells don't overlap `bounds` are skipped as a whole.
+    pub fn query(&self, bounds: MortonBox) -> LooseQuery<'_, T> {
+        LooseQuery {
+            cells: &self.cells,
+            bounds,
+            next: Some(Bound::Included(MortonKey::ROOT)),
+            checked: None,
+            objects: [].iter(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LooseOctree<T> {
+    type Item = (MortonBox, &'a T);
+    type IntoIter = LooseIter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the objects of a [`LooseOctree`], see [`LooseOctree::iter`].
+#[derive(Debug, Clone)]
+pub struct LooseIter<'a, T> {
+    cells: btree_map::Values<'a, MortonKey, Vec<(MortonBox, T)>>,
+    objects: slice::Iter<'a, (MortonBox, T)>,
+}
+
+impl<'a, T> Iterator for LooseIter<'a, T> {
+    type Item = (MortonBox, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((bounds, value)) = self.objects.next() {
+                return Some((*bounds, value));
+            }
+            self.objects = self.cells.next()?.iter();
+        }
+    }
+}
+
+impl<T> FusedIterator for LooseIter<'_, T> {}
+
+/// Iterator over the objects of a [`LooseOctree`] overlapping a box, see [`LooseOctree::query`].
+#[derive(Debug, Clone)]
+pub struct LooseQuery<'a, T> {
+    cells: &'a BTreeMap<MortonKey, Vec<(MortonBox, T)>>,
+    bounds: MortonBox,
+    /// cells before this bound are done
+    next: Option<Bound<MortonKey>>,
+    /// cell whose loosened cell is known to overlap the box, so do its ancestors
+    checked: Option<MortonKey>,
+    objects: slice::Iter<'a, (MortonBox, T)>,
+}
+
+impl<'a, T> LooseQuery<'a, T> {
+    /// move to the next cell whose loosened ancestors all overlap the box
+    fn next_cell(&mut self) -> Option<slice::Iter<'a, (MortonBox, T)>> {
+        loop {
+            let (&key, objects) = self.cells.range((self.next?, Bound::Unbounded)).next()?;
+            let unknown = self
+                .checked
+                .map_or(0, |checked| checked.common_ancestor(key).depth() + 1);
+            let outside = (unknown..=key.depth())
+                .map(|depth| MortonKey::from_leaf(key.anchor(), depth))
+                .find(|&cell| self.bounds.intersection(loose_bounds(cell)).is_none());
+            match outside {
+                Some(cell) => {
+                    self.checked = cell.parent();
+                    self.next = cell.next_subtree(MortonKey::ROOT).map(Bound::Included);
+                }
+                None => {
+                    self.checked = Some(key);
+                    self.next = Some(Bound::Excluded(key));
+                    return Some(objects.iter());
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Iterator for LooseQuery<'a, T> {
+    type Item = (MortonBox, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for (bounds, value) in self.objects.by_ref() {
+                if self.bounds.intersection(*bounds).is_some() {
+                    return Some((*bounds, value));
+                }
+            }
+            self.objects = self.next_cell()?;
+        }
+    }
+}
+
+impl<T> FusedIterator for LooseQuery<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{loose_bounds, LooseOctree};
+    use crate::{Morton3D, MortonBox, MortonKey};
+
+    fn bounds(min: (u32, u32, u32), max: (u32, u32, u32)) -> MortonBox {
+        MortonBox::new(
+            Morton3D::from_coords(min.0, min.1, min.2),
+            Morton3D::from_coords(max.0, max.1, max.2),
+        )
+    }
+
+    #[test]
+    fn test_cell_of() {
+        let point = bounds((5, 6, 7), (5, 6, 7));
+        let key = LooseOctree::<()>::cell_of(point);
+        assert_eq!(key.depth(), MortonKey::MAX_DEPTH);
+        assert_eq!(key.anchor(), Morton3D::from_coords(5, 6, 7));
+
+        for &(min, max) in [
+            ((0, 0, 0), (7, 7, 7)),
+            ((3, 4, 5), (10, 4, 5)),
+            ((100, 200, 300), (131, 250, 300)),
+            ((1000, 0, 70), (1062, 9, 71)),
+        ]
+        .iter()
+        {
+            let b = bounds(min, max);
+            let key = LooseOctree::<()>::cell_of(b);
+            let loose = loose_bounds(key);
+            assert!(loose.contains(b.min()) && loose.contains(b.max()));
+            // one depth deeper is too small for the box
+            assert!(2 * u32::max(max.0 - min.0, max.1 - min.1) + 2 > key.size());
+        }
+    }
+
+    #[test]
+    fn test_query() {
+        let mut octree = LooseOctree::new();
+        let mut objects = Vec::new();
+        for i in 0..200u32 {
+            let min = ((i * 37) % 500, (i * 91) % 300, (i * 13) % 200);
+            let extent = (i * 7) % 60;
+            let b = bounds(min, (min.0 + extent, min.1 + extent / 2, min.2 + 1));
+            octree.insert(b, i);
+            objects.push((b, i));
+        }
+        assert_eq!(octree.len(), 200);
+        assert_eq!(octree.iter().count(), 200);
+
+        for &(min, max) in [
+            ((0, 0, 0), (50, 50, 50)),
+            ((200, 100, 20), (260, 180, 90)),
+            ((499, 0, 0), (600, 400, 400)),
+            ((1000, 1000, 1000), (1100, 1100, 1100)),
+        ]
+        .iter()
+        {
+            let q = bounds(min, max);
+            let mut got: Vec<_> = octree.query(q).map(|(_, &i)| i).collect();
+            got.sort_unstable();
+            let expected: Vec<_> = objects
+                .iter()
+                .filter(|(b, _)| b.intersection(q).is_some())
+                .map(|&(_, i)| i)
+                .collect();
+            assert_eq!(got, expected);
+        }
+
+        let (b, i) = objects[10];
+        assert_eq!(octree.remove(b, &i), Some(i));
+        assert_eq!(octree.remove(b, &i), None);
+        octree.retain(|_, i| *i % 2 == 0);
+        assert_eq!(octree.len(), 99);
+        assert_eq!(octree.iter().count(), 99);
+    }
+}
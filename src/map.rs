@@ -0,0 +1,261 @@
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::slice;
+
+use crate::{Morton3D, MortonBox, QueryBox};
+
+/// Spatial map from cells to values, kept sorted by [`Morton3D`] code.
+///
+/// Keys and values are stored in two sorted `Vec`s, so lookups are binary searches
+/// and box queries skip the parts of the curve outside of the box (see [`QueryBox`]).
+/// The flag of keys is ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MortonMap<T> {
+    keys: Vec<Morton3D>,
+    values: Vec<T>,
+}
+
+impl<T> Default for MortonMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// code without flag
+fn plain(mut code: Morton3D) -> Morton3D {
+    code.unset_flag();
+    code
+}
+
+impl<T> MortonMap<T> {
+    pub fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// set the value of the cell, return the previous one.
+    pub fn insert(&mut self, code: Morton3D, value: T) -> Option<T> {
+        let code = plain(code);
+        match self.keys.binary_search(&code) {
+            Ok(i) => Some(core::mem::replace(&mut self.values[i], value)),
+            Err(i) => {
+                self.keys.insert(i, code);
+                self.values.insert(i, value);
+                None
+            }
+        }
+    }
+
+    /// remove the cell, return its value.
+    pub fn remove(&mut self, code: Morton3D) -> Option<T> {
+        let i = self.keys.binary_search(&plain(code)).ok()?;
+        self.keys.remove(i);
+        Some(self.values.remove(i))
+    }
+
+    pub fn get(&self, code: Morton3D) -> Option<&T> {
+        let i = self.keys.binary_search(&plain(code)).ok()?;
+        Some(&self.values[i])
+    }
+    pub fn get_mut(&mut self, code: Morton3D) -> Option<&mut T> {
+        let i = self.keys.binary_search(&plain(code)).ok()?;
+        Some(&mut self.values[i])
+    }
+    pub fn contains_key(&self, code: Morton3D) -> bool {
+        self.keys.binary_search(&plain(code)).is_ok()
+    }
+
+    /// iterate over the entries in curve order.
+    pub fn iter(&self) -> MapIter<'_, T> {
+        MapIter {
+            keys: self.keys.iter(),
+            values: self.values.iter(),
+        }
+    }
+
+    /// iterate over the entries inside of the box spanned by the cells `min` and `max`, in curve order.
+    pub fn query_box(&self, min: Morton3D, max: Morton3D) -> MapQuery<'_, T> {
+        MapQuery {
+            query: MortonBox::new(min, max).query(&self.keys),
+            values: &self.values,
+            sphere: None,
+        }
+    }
+
+    /// iterate over the entries whose euclidean distance to `center` is at most `radius`,
+    /// in curve order.
+    pub fn query_radius(&self, center: Morton3D, radius: u32) -> MapQuery<'_, T> {
+        let (x, y, z) = center.to_coords();
+        let low = |v: u32| v.saturating_sub(radius);
+        let high = |v: u32| v.saturating_add(radius).min(Morton3D::MAX_COORD);
+        let bounds = MortonBox::new(
+            Morton3D::from_coords(low(x), low(y), low(z)),
+            Morton3D::from_coords(high(x), high(y), high(z)),
+        );
+        MapQuery {
+            query: bounds.query(&self.keys),
+            values: &self.values,
+            sphere: Some((center, u64::from(radius) * u64::from(radius))),
+        }
+    }
+}
+
+impl<T> FromIterator<(Morton3D, T)> for MortonMap<T> {
+    /// collect entries, the last value is kept for duplicated keys.
+    fn from_iter<I: IntoIterator<Item = (Morton3D, T)>>(iter: I) -> Self {
+        let mut entries: Vec<_> = iter.into_iter().map(|(k, v)| (plain(k), v)).collect();
+        // stable, so duplicates keep their order
+        entries.sort_by_key(|&(k, _)| k);
+        let mut map = Self::new();
+        for (k, v) in entries {
+            if map.keys.last() == Some(&k) {
+                *map.values.last_mut().unwrap() = v;
+            } else {
+                map.keys.push(k);
+                map.values.push(v);
+            }
+        }
+        map
+    }
+}
+
+/// Iterator over the entries of a [`MortonMap`], in curve order.
+#[derive(Debug, Clone)]
+pub struct MapIter<'a, T> {
+    keys: slice::Iter<'a, Morton3D>,
+    values: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for MapIter<'a, T> {
+    type Item = (Morton3D, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((*self.keys.next()?, self.values.next()?))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for MapIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some((*self.keys.next_back()?, self.values.next_back()?))
+    }
+}
+
+impl<T> ExactSizeIterator for MapIter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a MortonMap<T> {
+    type Item = (Morton3D, &'a T);
+    type IntoIter = MapIter<'a, T>;
+    fn into_iter(self) -> MapIter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`MortonMap`] inside of a box or a sphere, in curve order.
+#[derive(Debug, Clone)]
+pub struct MapQuery<'a, T> {
+    query: QueryBox<'a>,
+    values: &'a [T],
+    /// center and squared radius
+    sphere: Option<(Morton3D, u64)>,
+}
+
+impl<'a, T> Iterator for MapQuery<'a, T> {
+    type Item = (Morton3D, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let code = self.query.next()?;
+            let value = &self.values[self.values.len() - self.query.remaining() - 1];
+            match self.sphere {
+                Some((center, r2)) if center.euclidean_distance_squared(code) > r2 => continue,
+                _ => return Some((code, value)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MortonMap;
+    use crate::Morton3D;
+
+    #[test]
+    fn test_map() {
+        let mut map = MortonMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(Morton3D::from_coords(3, 2, 1), "a"), None);
+        assert_eq!(map.insert(Morton3D::from_coords(0, 0, 0), "b"), None);
+        let mut flagged = Morton3D::from_coords(3, 2, 1);
+        flagged.set_flag();
+        assert_eq!(map.insert(flagged, "c"), Some("a"));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(Morton3D::from_coords(3, 2, 1)), Some(&"c"));
+        *map.get_mut(Morton3D::from_coords(0, 0, 0)).unwrap() = "d";
+        assert!(map.contains_key(Morton3D::from_coords(0, 0, 0)));
+        assert!(!map.contains_key(Morton3D::from_coords(0, 0, 1)));
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(
+            entries,
+            [
+                (Morton3D::from_coords(0, 0, 0), &"d"),
+                (Morton3D::from_coords(3, 2, 1), &"c")
+            ]
+        );
+        assert_eq!(map.remove(flagged), Some("c"));
+        assert_eq!(map.remove(flagged), None);
+        assert_eq!(map.len(), 1);
+
+        let map: MortonMap<_> = vec![
+            (Morton3D::from_coords(1, 1, 1), 1),
+            (Morton3D::from_coords(1, 1, 1), 2),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(Morton3D::from_coords(1, 1, 1)), Some(&2));
+    }
+
+    #[test]
+    fn test_map_query() {
+        let map: MortonMap<_> = (0..16)
+            .flat_map(|x| (0..16).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+            .filter(|&(x, y, z)| (x + y + z) % 3 == 0)
+            .map(|c| (Morton3D::from(c), c))
+            .collect();
+
+        let found: Vec<_> = map
+            .query_box(
+                Morton3D::from_coords(2, 3, 4),
+                Morton3D::from_coords(6, 8, 9),
+            )
+            .collect();
+        let expected: Vec<_> = map
+            .iter()
+            .filter(|&(_, &(x, y, z))| {
+                (2..=6).contains(&x) && (3..=8).contains(&y) && (4..=9).contains(&z)
+            })
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+        assert!(found.iter().all(|&(code, &c)| code.to_coords() == c));
+
+        let center = Morton3D::from_coords(2, 7, 5);
+        let found: Vec<_> = map.query_radius(center, 3).collect();
+        let expected: Vec<_> = map
+            .iter()
+            .filter(|&(code, _)| center.euclidean_distance_squared(code) <= 9)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+}
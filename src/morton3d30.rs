@@ -0,0 +1,168 @@
+use core::convert::TryFrom;
+
+use crate::{Morton3D32, Morton3D64, MortonError};
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// 3-dimension morton code(xyz) backed by `u32`, 10-level, 30 bits.
+///
+/// This is the layout used by GPU LBVH builders (`expandBits(x) * 4 + expandBits(y) * 2 + expandBits(z)`):
+/// x is stored in the highest bit of each 3-bit group and z in the lowest.
+/// Two highest bits are always zero, there is no flag bit.
+pub struct Morton3D30(Morton3D32);
+
+impl Morton3D30 {
+    /// Max number of depth (bits per axis).
+    pub const MAX_DEPTH: u32 = Morton3D32::MAX_DEPTH;
+
+    /// Max coordinate of each axis.
+    pub const MAX_COORD: u32 = Morton3D32::MAX_COORD;
+
+    /// encode coordinates into morton code,
+    /// only the lower 10 bits of each coordinate are used.
+    pub const fn from_coords(x: u32, y: u32, z: u32) -> Self {
+        // same bits as `Morton3D32` with x and z swapped
+        Self(Morton3D32::from_coords(z, y, x))
+    }
+
+    /// encode coordinates into morton code,
+    /// return error if any coordinate exceeds [`Morton3D30::MAX_COORD`].
+    pub const fn try_from_coords(x: u32, y: u32, z: u32) -> Result<Self, MortonError> {
+        match Morton3D32::try_from_coords(z, y, x) {
+            Ok(v) => Ok(Self(v)),
+            Err(MortonError::CoordinateOutOfRange { axis, value, max }) => {
+                Err(MortonError::CoordinateOutOfRange {
+                    axis: 2 - axis,
+                    value,
+                    max,
+                })
+            }
+        }
+    }
+
+    /// decode morton code into coordinates `(x, y, z)`.
+    pub const fn to_coords(self) -> (u32, u32, u32) {
+        (self.x(), self.y(), self.z())
+    }
+
+    pub const fn x(self) -> u32 {
+        self.0.z()
+    }
+    pub const fn y(self) -> u32 {
+        self.0.y()
+    }
+    pub const fn z(self) -> u32 {
+        self.0.x()
+    }
+
+    pub const fn decrease_x(self) -> Self {
+        Self(self.0.decrease_z())
+    }
+    pub const fn decrease_y(self) -> Self {
+        Self(self.0.decrease_y())
+    }
+    pub const fn decrease_z(self) -> Self {
+        Self(self.0.decrease_x())
+    }
+
+    pub const fn increase_x(self) -> Self {
+        Self(self.0.increase_z())
+    }
+    pub const fn increase_y(self) -> Self {
+        Self(self.0.increase_y())
+    }
+    pub const fn increase_z(self) -> Self {
+        Self(self.0.increase_x())
+    }
+}
+
+impl From<u32> for Morton3D30 {
+    /// build from GPU key, two highest bits are discarded.
+    fn from(v: u32) -> Self {
+        Self(Morton3D32::from(v & 0x3fff_ffff))
+    }
+}
+
+impl From<Morton3D30> for u32 {
+    fn from(v: Morton3D30) -> Self {
+        u32::from(v.0)
+    }
+}
+
+impl From<Morton3D30> for Morton3D64 {
+    fn from(v: Morton3D30) -> Self {
+        let (x, y, z) = v.to_coords();
+        Morton3D64::from_coords(x, y, z)
+    }
+}
+
+impl TryFrom<Morton3D64> for Morton3D30 {
+    type Error = MortonError;
+    /// convert to GPU layout, fail if any coordinate exceeds [`Morton3D30::MAX_COORD`], flag is dropped.
+    fn try_from(v: Morton3D64) -> Result<Self, MortonError> {
+        let (x, y, z) = v.to_coords();
+        Self::try_from_coords(x, y, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::Morton3D30;
+    use crate::{Morton3D64, MortonError};
+
+    /// reference implementation from "Thinking Parallel, Part III" (Karras, 2012)
+    fn gpu_key(x: u32, y: u32, z: u32) -> u32 {
+        fn expand_bits(v: u32) -> u32 {
+            let v = v.wrapping_mul(0x0001_0001) & 0xff00_00ff;
+            let v = v.wrapping_mul(0x0000_0101) & 0x0f00_f00f;
+            let v = v.wrapping_mul(0x0000_0011) & 0xc30c_30c3;
+            v.wrapping_mul(0x0000_0005) & 0x4924_9249
+        }
+        expand_bits(x) * 4 + expand_bits(y) * 2 + expand_bits(z)
+    }
+
+    #[test]
+    fn test_gpu_layout() {
+        for &(x, y, z) in &[
+            (0, 0, 0),
+            (1, 0, 0),
+            (0, 0, 1),
+            (1023, 512, 7),
+            (1023, 1023, 1023),
+        ] {
+            let morton = Morton3D30::from_coords(x, y, z);
+            assert_eq!(u32::from(morton), gpu_key(x, y, z));
+            assert_eq!(Morton3D30::from(gpu_key(x, y, z)), morton);
+            assert_eq!(morton.to_coords(), (x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_inc_dec() {
+        let morton = Morton3D30::from_coords(3, 4, 5);
+        assert_eq!(morton.increase_x(), Morton3D30::from_coords(4, 4, 5));
+        assert_eq!(morton.decrease_z(), Morton3D30::from_coords(3, 4, 4));
+        assert_eq!(
+            morton.increase_y().decrease_x(),
+            Morton3D30::from_coords(2, 5, 5)
+        );
+    }
+
+    #[test]
+    fn test_conversion() {
+        let morton = Morton3D30::from_coords(1000, 2, 30);
+        let wide = Morton3D64::from(morton);
+        assert_eq!(wide.to_coords(), (1000, 2, 30));
+        assert_eq!(Morton3D30::try_from(wide), Ok(morton));
+        assert_eq!(
+            Morton3D30::try_from(Morton3D64::from_coords(1024, 0, 0)),
+            Err(MortonError::CoordinateOutOfRange {
+                axis: 0,
+                value: 1024,
+                max: 1023
+            })
+        );
+        assert!(Morton3D30::try_from_coords(0, 0, 1024).is_err());
+    }
+}
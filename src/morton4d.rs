@@ -0,0 +1,209 @@
+use crate::bits::{compact_bits_4d_u64, expand_bits_4d_u64};
+use crate::MortonError;
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// 4-dimension morton code(tzyx) backed by `u64`, 16-level.
+///
+/// All 64 bits are used by coordinates, so there is no flag bit.
+pub struct Morton4D(u64);
+
+impl Morton4D {
+    /// Max number of depth (bits per axis).
+    pub const MAX_DEPTH: u32 = u64::BITS / 4;
+
+    /// Max coordinate of each axis.
+    pub const MAX_COORD: u32 = (1 << Self::MAX_DEPTH) - 1;
+
+    /// Bits of x axis.
+    const MASK: u64 = 0x1111_1111_1111_1111;
+
+    /// encode coordinates into morton code,
+    /// only the lower `MAX_DEPTH` bits of each coordinate are used.
+    pub const fn from_coords(x: u32, y: u32, z: u32, t: u32) -> Self {
+        Self(
+            expand_bits_4d_u64(x)
+                | (expand_bits_4d_u64(y) << 1)
+                | (expand_bits_4d_u64(z) << 2)
+                | (expand_bits_4d_u64(t) << 3),
+        )
+    }
+
+    /// encode coordinates into morton code,
+    /// return error if any coordinate exceeds [`Morton4D::MAX_COORD`].
+    pub const fn try_from_coords(x: u32, y: u32, z: u32, t: u32) -> Result<Self, MortonError> {
+        let coords = [x, y, z, t];
+        let mut axis = 0;
+        while axis < 4 {
+            if coords[axis] > Self::MAX_COORD {
+                return Err(MortonError::CoordinateOutOfRange {
+                    axis,
+                    value: coords[axis] as u64,
+                    max: Self::MAX_COORD as u64,
+                });
+            }
+            axis += 1;
+        }
+        Ok(Self::from_coords(x, y, z, t))
+    }
+
+    /// decode morton code into coordinates `(x, y, z, t)`.
+    pub const fn to_coords(self) -> (u32, u32, u32, u32) {
+        (self.x(), self.y(), self.z(), self.t())
+    }
+
+    /// generate mask bits
+    const fn mask_n(n: usize) -> u64 {
+        Self::MASK << (n % 4)
+    }
+
+    /// decrease n-th dim (0: x, 1: y, 2: z, 3: t) morton code,
+    /// panic if it can't
+    const fn decrease_nth_dim(self, n: usize) -> Self {
+        Self((((self.0 & Self::mask_n(n)) - 1) & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)))
+    }
+    /// increase n-th dim (0: x, 1: y, 2: z, 3: t) morton code,
+    /// panic if it can't
+    const fn increase_nth_dim(self, n: usize) -> Self {
+        Self((((self.0 | !Self::mask_n(n)) + 1) & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)))
+    }
+
+    /// get n-th dim (0: x, 1: y, 2: z, 3: t) coordinate
+    const fn nth_dim(self, n: usize) -> u32 {
+        compact_bits_4d_u64(self.0 >> (n % 4))
+    }
+
+    pub const fn x(self) -> u32 {
+        self.nth_dim(0)
+    }
+    pub const fn y(self) -> u32 {
+        self.nth_dim(1)
+    }
+    pub const fn z(self) -> u32 {
+        self.nth_dim(2)
+    }
+    pub const fn t(self) -> u32 {
+        self.nth_dim(3)
+    }
+
+    /// replace n-th dim (0: x, 1: y, 2: z, 3: t) coordinate, other dims are kept
+    const fn with_nth_dim(self, n: usize, v: u32) -> Self {
+        Self((self.0 & !Self::mask_n(n)) | (expand_bits_4d_u64(v) << (n % 4)))
+    }
+
+    pub const fn with_x(self, x: u32) -> Self {
+        self.with_nth_dim(0, x)
+    }
+    pub const fn with_y(self, y: u32) -> Self {
+        self.with_nth_dim(1, y)
+    }
+    pub const fn with_z(self, z: u32) -> Self {
+        self.with_nth_dim(2, z)
+    }
+    pub const fn with_t(self, t: u32) -> Self {
+        self.with_nth_dim(3, t)
+    }
+
+    pub const fn decrease_x(self) -> Self {
+        self.decrease_nth_dim(0)
+    }
+    pub const fn decrease_y(self) -> Self {
+        self.decrease_nth_dim(1)
+    }
+    pub const fn decrease_z(self) -> Self {
+        self.decrease_nth_dim(2)
+    }
+    pub const fn decrease_t(self) -> Self {
+        self.decrease_nth_dim(3)
+    }
+
+    pub const fn increase_x(self) -> Self {
+        self.increase_nth_dim(0)
+    }
+    pub const fn increase_y(self) -> Self {
+        self.increase_nth_dim(1)
+    }
+    pub const fn increase_z(self) -> Self {
+        self.increase_nth_dim(2)
+    }
+    pub const fn increase_t(self) -> Self {
+        self.increase_nth_dim(3)
+    }
+}
+
+impl From<u64> for Morton4D {
+    fn from(v: u64) -> Self {
+        Self(v)
+    }
+}
+
+impl From<Morton4D> for u64 {
+    fn from(v: Morton4D) -> Self {
+        v.0
+    }
+}
+
+impl From<(u32, u32, u32, u32)> for Morton4D {
+    fn from((x, y, z, t): (u32, u32, u32, u32)) -> Self {
+        Self::from_coords(x, y, z, t)
+    }
+}
+
+impl From<[u32; 4]> for Morton4D {
+    fn from([x, y, z, t]: [u32; 4]) -> Self {
+        Self::from_coords(x, y, z, t)
+    }
+}
+
+impl From<Morton4D> for (u32, u32, u32, u32) {
+    fn from(v: Morton4D) -> Self {
+        v.to_coords()
+    }
+}
+
+impl From<Morton4D> for [u32; 4] {
+    fn from(v: Morton4D) -> Self {
+        [v.x(), v.y(), v.z(), v.t()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Morton4D;
+
+    #[test]
+    fn test_coords() {
+        assert_eq!(Morton4D::from_coords(1, 0, 1, 1), Morton4D(0b1101));
+        assert_eq!(Morton4D::from_coords(2, 0, 0, 3), Morton4D(0b1001_1000));
+
+        let max = Morton4D::MAX_COORD;
+        for &(x, y, z, t) in &[
+            (0, 0, 0, 0),
+            (1, 2, 3, 4),
+            (max, 0, max, 7),
+            (max, max, max, max),
+        ] {
+            let morton = Morton4D::from_coords(x, y, z, t);
+            assert_eq!(morton.to_coords(), (x, y, z, t));
+        }
+        assert_eq!(
+            Morton4D::from_coords(max, max, max, max),
+            Morton4D(u64::MAX)
+        );
+        assert!(Morton4D::try_from_coords(0, 0, 0, max + 1).is_err());
+    }
+
+    #[test]
+    fn test_inc_dec() {
+        let morton = Morton4D::from_coords(3, 3, 3, 3);
+        assert_eq!(morton.increase_x(), Morton4D::from_coords(4, 3, 3, 3));
+        assert_eq!(morton.increase_t(), Morton4D::from_coords(3, 3, 3, 4));
+        assert_eq!(
+            morton.decrease_y().decrease_z(),
+            Morton4D::from_coords(3, 2, 2, 3)
+        );
+        assert_eq!(
+            morton.decrease_t().with_x(9),
+            Morton4D::from_coords(9, 3, 3, 2)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{MortonND, MortonStorage};
+
+/// Axis-aligned box of 3-dimension cells, given by its inclusive min and max corner codes.
+///
+/// Corners never have the flag set, containment ignores the flag of the tested code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MortonBox<S: MortonStorage = usize> {
+    min: MortonND<3, S>,
+    max: MortonND<3, S>,
+}
+
+impl<S: MortonStorage> MortonBox<S> {
+    /// create box spanned by two corner cells, in any order.
+    pub fn new(a: MortonND<3, S>, b: MortonND<3, S>) -> Self
+    where
+        Self: From<(MortonND<3, S>, MortonND<3, S>)>,
+    {
+        Self::from((a, b))
+    }
+
+    pub fn min(self) -> MortonND<3, S> {
+        self.min
+    }
+    pub fn max(self) -> MortonND<3, S> {
+        self.max
+    }
+}
+
+/// Implement [`MortonBox`] operations for storage `$t`.
+macro_rules! morton_box {
+    ($t:ty) => {
+        impl MortonBox<$t> {
+            /// box covering the whole grid
+            pub const FULL: Self = Self {
+                min: MortonND::<3, $t>::from_coords(0, 0, 0),
+                max: MortonND::<3, $t>::from_coords(
+                    MortonND::<3, $t>::MAX_COORD,
+                    MortonND::<3, $t>::MAX_COORD,
+                    MortonND::<3, $t>::MAX_COORD,
+                ),
+            };
+
+            /// whether the cell is inside of the box, flag is ignored.
+            pub const fn contains(self, v: MortonND<3, $t>) -> bool {
+                v.is_within(self.min, self.max)
+            }
+
+            /// overlapping part of two boxes, `None` if they don't overlap.
+            pub const fn intersection(self, other: Self) -> Option<Self> {
+                let min = self.min.component_max(other.min);
+                let max = self.max.component_min(other.max);
+                // empty if min exceeds max on any axis
+                if min.is_within(min, max) {
+                    Some(Self { min, max })
+                } else {
+                    None
+                }
+            }
+        }
+
+        impl From<(MortonND<3, $t>, MortonND<3, $t>)> for MortonBox<$t> {
+            fn from((a, b): (MortonND<3, $t>, MortonND<3, $t>)) -> Self {
+                let mut min = a.component_min(b);
+                let mut max = a.component_max(b);
+                min.unset_flag();
+                max.unset_flag();
+                Self { min, max }
+            }
+        }
+    };
+}
+
+morton_box!(u16);
+morton_box!(u32);
+morton_box!(u64);
+morton_box!(u128);
+morton_box!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::MortonBox;
+    use crate::Morton3D;
+
+    #[test]
+    fn test_box() {
+        let a = Morton3D::from_coords(8, 2, 5);
+        let mut b = Morton3D::from_coords(3, 9, 5);
+        b.set_flag();
+        let bounds = MortonBox::new(a, b);
+        assert_eq!(bounds.min().to_coords(), (3, 2, 5));
+        assert_eq!(bounds.max().to_coords(), (8, 9, 5));
+        assert!(!bounds.max().is_flag_set());
+        assert!(bounds.contains(b));
+        assert!(bounds.contains(Morton3D::from_coords(4, 4, 5)));
+        assert!(!bounds.contains(Morton3D::from_coords(4, 4, 6)));
+        assert!(MortonBox::<usize>::FULL.contains(a));
+
+        let other = MortonBox::new(
+            Morton3D::from_coords(6, 0, 0),
+            Morton3D::from_coords(10, 3, 10),
+        );
+        let overlap = bounds.intersection(other).unwrap();
+        assert_eq!(overlap.min().to_coords(), (6, 2, 5));
+        assert_eq!(overlap.max().to_coords(), (8, 3, 5));
+        assert_eq!(MortonBox::<usize>::FULL.intersection(bounds), Some(bounds));
+
+        let apart = MortonBox::new(a, a.increase_z());
+        assert_eq!(
+            MortonBox::new(Morton3D::default(), Morton3D::from_coords(9, 9, 4)).intersection(apart),
+            None
+        );
+    }
+}
@@ -0,0 +1,1601 @@
+#[cfg(feature = "nightly")]
+use core::convert::TryFrom;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+
+#[cfg(feature = "lut")]
+use crate::bits::{compact_lut, expand_lut};
+use crate::{Dilated21, Direction, MortonError, MortonStorage, Offset3D};
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// `D`-dimension morton code backed by `S`, `(S::BITS - 1) / D`-level, first bit is used for 1-bit flag.
+///
+/// Axis `n` is stored in bits `n, n + D, n + 2D, ...`, bits between the last level and flag bit are not used.
+/// `D` must be in `2..=8`.
+pub struct MortonND<const D: usize, S: MortonStorage = usize>(S);
+
+#[cfg(target_pointer_width = "64")]
+/// 2-dimension morton code(yx), 31-level, first bit is used for 1-bit flag.
+///
+/// Second bit is not used.
+pub type Morton2D = MortonND<2>;
+
+#[cfg(target_pointer_width = "32")]
+/// 2-dimension morton code(yx), 15-level, first bit is used for 1-bit flag.
+///
+/// Second bit is not used.
+pub type Morton2D = MortonND<2>;
+
+#[cfg(target_pointer_width = "64")]
+/// 3-dimension morton code(zyx), 21-level, first bit is used for 1-bit flag.
+///
+/// This is `MortonND<3, usize>`, use [`MortonND<3, S>`](MortonND) to pick another storage.
+pub type Morton3D = MortonND<3>;
+
+#[cfg(target_pointer_width = "32")]
+/// 3-dimension morton code(zyx), 10-level, first bit is used for 1-bit flag.
+///
+/// To be compatible with 64-bit Morton3D, second bit is not used.
+/// This is `MortonND<3, usize>`, use [`MortonND<3, S>`](MortonND) to pick another storage.
+pub type Morton3D = MortonND<3>;
+
+/// 3-dimension morton code(zyx) backed by `u16`, 5-level, first bit is used for 1-bit flag.
+///
+/// Small enough for occupancy grids on microcontrollers, the layout is the same on every target.
+pub type Morton3D16 = MortonND<3, u16>;
+
+/// 3-dimension morton code(zyx) backed by `u32`, 10-level, first bit is used for 1-bit flag.
+///
+/// Unlike [`Morton3D`], the layout is the same on every target. Second bit is not used.
+pub type Morton3D32 = MortonND<3, u32>;
+
+/// 3-dimension morton code(zyx) backed by `u64`, 21-level, first bit is used for 1-bit flag.
+///
+/// Unlike [`Morton3D`], the layout is the same on every target.
+pub type Morton3D64 = MortonND<3, u64>;
+
+/// 3-dimension morton code(zyx) backed by `u128`, 42-level, first bit is used for 1-bit flag.
+///
+/// The layout is the same on every target. Second bit is not used.
+pub type Morton3D128 = MortonND<3, u128>;
+
+/// Implement morton code operations for `MortonND<D, $t>` with coordinates of type `$c`.
+///
+/// Operations are generated for each storage (instead of being generic over [`MortonStorage`])
+/// so that they can be `const fn`.
+macro_rules! morton_nd {
+    ($t:ty, $c:ty) => {
+        impl<const D: usize> MortonND<D, $t> {
+            /// Number of bits of the backing integer.
+            const BITS: u32 = <$t>::BITS;
+
+            /// Fail to compile if `D` is not supported.
+            const VALID_DIMENSION: () = assert!(D >= 2 && D <= 8, "dimension must be in 2..=8");
+
+            /// Max number of depth (bits per axis).
+            pub const MAX_DEPTH: u32 = (Self::BITS - 1) / D as u32;
+
+            /// Max coordinate of each axis.
+            pub const MAX_COORD: $c = (1 << Self::MAX_DEPTH) - 1;
+
+            /// Bit used for 1-bit flag.
+            const FLAG: $t = 1 << (Self::BITS - 1);
+
+            /// Number of dilation stages needed to spread `MAX_DEPTH` bits.
+            #[cfg(not(feature = "lut"))]
+            const STAGES: usize = {
+                let mut stages = 0;
+                while (1 << stages) < Self::MAX_DEPTH {
+                    stages += 1;
+                }
+                stages
+            };
+
+            /// Masks used to spread `MAX_DEPTH` bits `D`-way.
+            ///
+            /// `DILATION_MASKS[k]` has the bits occupied after grouping the value into chunks of `2^k` bits,
+            /// so `DILATION_MASKS[0]` is the fully dilated layout and `DILATION_MASKS[STAGES]` is the lower `MAX_DEPTH` bits.
+            const DILATION_MASKS: [$t; 7] = {
+                let mut masks = [0; 7];
+                let mut k = 0;
+                while k < masks.len() {
+                    let chunk = 1 << k;
+                    let mut i = 0;
+                    while i < Self::MAX_DEPTH as usize {
+                        masks[k] |= 1 << ((i / chunk) * chunk * D + i % chunk);
+                        i += 1;
+                    }
+                    k += 1;
+                }
+                masks
+            };
+
+            /// Bits of x axis.
+            const MASK: $t = Self::DILATION_MASKS[0];
+
+            /// Bytes spread `D`-way, used to encode with the `lut` feature.
+            #[cfg(feature = "lut")]
+            const EXPAND_LUT: [u64; 256] = expand_lut(D);
+
+            /// Every `D`-th bit of bytes gathered, used to decode with the `lut` feature.
+            #[cfg(feature = "lut")]
+            const COMPACT_LUT: [u8; 256] = compact_lut(D);
+
+            /// Spread the lower `MAX_DEPTH` bits of `v`, bit `i` is moved to bit `D * i`.
+            #[cfg(not(feature = "lut"))]
+            const fn expand(v: $c) -> $t {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::VALID_DIMENSION;
+                let mut x = v as $t & Self::DILATION_MASKS[Self::STAGES];
+                let mut k = Self::STAGES;
+                while k > 0 {
+                    k -= 1;
+                    x = (x | x << ((D - 1) << k)) & Self::DILATION_MASKS[k];
+                }
+                x
+            }
+
+            /// Spread the lower `MAX_DEPTH` bits of `v`, bit `i` is moved to bit `D * i`.
+            ///
+            /// A byte of `v` is spread with a table lookup at a time.
+            #[cfg(feature = "lut")]
+            const fn expand(v: $c) -> $t {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::VALID_DIMENSION;
+                let v = v & Self::MAX_COORD;
+                let mut x = 0;
+                let mut i = 0;
+                while i < Self::MAX_DEPTH as usize {
+                    x |= (Self::EXPAND_LUT[(v >> i) as usize & 0xff] as $t) << (D * i);
+                    i += 8;
+                }
+                x
+            }
+
+            /// Gather every `D`-th bit of `v`, bit `D * i` is moved to bit `i`.
+            #[cfg(not(feature = "lut"))]
+            const fn compact(v: $t) -> $c {
+                let mut x = v & Self::DILATION_MASKS[0];
+                let mut k = 1;
+                while k <= Self::STAGES {
+                    x = (x | x >> ((D - 1) << (k - 1))) & Self::DILATION_MASKS[k];
+                    k += 1;
+                }
+                x as $c
+            }
+
+            /// Gather every `D`-th bit of `v`, bit `D * i` is moved to bit `i`.
+            ///
+            /// The bits in a byte of `v` are gathered with a table lookup at a time.
+            #[cfg(feature = "lut")]
+            const fn compact(v: $t) -> $c {
+                // coordinate bits in a byte of `v`
+                let chunk = 8usize.div_ceil(D);
+                let mut x = 0;
+                let mut i = 0;
+                while i < Self::MAX_DEPTH as usize {
+                    x |= (Self::COMPACT_LUT[(v >> (D * i)) as usize & 0xff] as $c) << i;
+                    i += chunk;
+                }
+                x & Self::MAX_COORD
+            }
+
+            /// encode coordinates into morton code.
+            ///
+            /// Only the lower `MAX_DEPTH` bits of each coordinate are used,
+            /// so the flag bit is never set by encoding.
+            pub const fn from_array(coords: [$c; D]) -> Self {
+                let mut v = 0;
+                let mut n = 0;
+                while n < D {
+                    v |= Self::expand(coords[n]) << n;
+                    n += 1;
+                }
+                Self(v)
+            }
+
+            /// encode coordinates into morton code,
+            /// return error if any coordinate exceeds `MAX_COORD`.
+            pub const fn try_from_array(coords: [$c; D]) -> Result<Self, MortonError> {
+                let mut axis = 0;
+                while axis < D {
+                    if coords[axis] > Self::MAX_COORD {
+                        return Err(MortonError::CoordinateOutOfRange {
+                            axis,
+                            value: coords[axis] as u64,
+                            max: Self::MAX_COORD as u64,
+                        });
+                    }
+                    axis += 1;
+                }
+                Ok(Self::from_array(coords))
+            }
+
+            /// decode morton code into coordinates, flag bit is ignored.
+            pub const fn to_array(self) -> [$c; D] {
+                let mut coords = [0; D];
+                let mut n = 0;
+                while n < D {
+                    coords[n] = self.nth_dim(n);
+                    n += 1;
+                }
+                coords
+            }
+
+            /// build from raw bits, same as `From<$t>` but usable in const context
+            pub const fn from_raw(raw: $t) -> Self {
+                Self(raw)
+            }
+
+            /// raw bits including flag, same as `From<Self> for $t` but usable in const context
+            pub const fn raw(self) -> $t {
+                self.0
+            }
+
+            pub const fn is_flag_set(self) -> bool {
+                (self.0 >> (Self::BITS - 1)) == 1
+            }
+            pub const fn set_flag(&mut self) {
+                self.0 |= Self::FLAG
+            }
+            pub const fn unset_flag(&mut self) {
+                self.0 &= !Self::FLAG
+            }
+
+            /// next code along the curve, the flag and the unused bits are kept,
+            /// `None` if this is the last cell.
+            pub const fn next_on_curve(self) -> Option<Self> {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let code = self.0 & last;
+                if code == last {
+                    return None;
+                }
+                Some(Self(self.0 & !last | (code + 1)))
+            }
+            /// previous code along the curve, the flag and the unused bits are kept,
+            /// `None` if this is the first cell.
+            pub const fn prev_on_curve(self) -> Option<Self> {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let code = self.0 & last;
+                if code == 0 {
+                    return None;
+                }
+                Some(Self(self.0 & !last | (code - 1)))
+            }
+
+            /// position of `self` along the curve counted from `origin`, flags are masked,
+            /// `None` if `self` is before `origin`.
+            pub const fn offset_from(self, origin: Self) -> Option<$t> {
+                (self.0 & !Self::FLAG).checked_sub(origin.0 & !Self::FLAG)
+            }
+            /// code `n` cells after `self` along the curve, the flag and the unused bits are kept,
+            /// `None` if it is past the last cell.
+            pub const fn offset_by(self, n: $t) -> Option<Self> {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let code = self.0 & last;
+                if n > last - code {
+                    return None;
+                }
+                Some(Self(self.0 & !last | (code + n)))
+            }
+
+            /// generate mask bits
+            const fn mask_n(n: usize) -> $t {
+                assert!(n < D, "axis out of range");
+                Self::MASK << n
+            }
+
+            /// decrease n-th dim morton code,
+            /// panic if it can't
+            pub const fn decrease_nth_dim(self, n: usize) -> Self {
+                Self(
+                    (((self.0 & Self::mask_n(n)) - 1) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+            /// increase n-th dim morton code,
+            /// panic if it can't
+            pub const fn increase_nth_dim(self, n: usize) -> Self {
+                Self(
+                    (((self.0 | !Self::mask_n(n)) + 1) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+
+            /// decrease n-th dim morton code,
+            /// return `None` if n-th dim coordinate is already 0
+            pub const fn checked_decrease_nth_dim(self, n: usize) -> Option<Self> {
+                if self.0 & Self::mask_n(n) == 0 {
+                    None
+                } else {
+                    Some(self.decrease_nth_dim(n))
+                }
+            }
+            /// increase n-th dim morton code,
+            /// return `None` if n-th dim coordinate is already `MAX_COORD`
+            pub const fn checked_increase_nth_dim(self, n: usize) -> Option<Self> {
+                if self.0 & Self::mask_n(n) == Self::mask_n(n) {
+                    None
+                } else {
+                    Some(self.increase_nth_dim(n))
+                }
+            }
+
+            /// decrease n-th dim morton code,
+            /// keep it as is if n-th dim coordinate is already 0
+            pub const fn saturating_decrease_nth_dim(self, n: usize) -> Self {
+                match self.checked_decrease_nth_dim(n) {
+                    Some(v) => v,
+                    None => self,
+                }
+            }
+            /// increase n-th dim morton code,
+            /// keep it as is if n-th dim coordinate is already `MAX_COORD`
+            pub const fn saturating_increase_nth_dim(self, n: usize) -> Self {
+                match self.checked_increase_nth_dim(n) {
+                    Some(v) => v,
+                    None => self,
+                }
+            }
+
+            /// decrease n-th dim morton code,
+            /// wrap around to `MAX_COORD` if n-th dim coordinate is 0
+            pub const fn wrapping_decrease_nth_dim(self, n: usize) -> Self {
+                Self(
+                    ((self.0 & Self::mask_n(n)).wrapping_sub(1) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+            /// increase n-th dim morton code,
+            /// wrap around to 0 if n-th dim coordinate is `MAX_COORD`
+            pub const fn wrapping_increase_nth_dim(self, n: usize) -> Self {
+                Self(
+                    ((self.0 | !Self::mask_n(n)).wrapping_add(1) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+
+            /// decrease n-th dim morton code like [`wrapping_decrease_nth_dim`](Self::wrapping_decrease_nth_dim),
+            /// also return whether the coordinate wrapped around
+            pub const fn overflowing_decrease_nth_dim(self, n: usize) -> (Self, bool) {
+                (
+                    self.wrapping_decrease_nth_dim(n),
+                    self.0 & Self::mask_n(n) == 0,
+                )
+            }
+            /// increase n-th dim morton code like [`wrapping_increase_nth_dim`](Self::wrapping_increase_nth_dim),
+            /// also return whether the coordinate wrapped around
+            pub const fn overflowing_increase_nth_dim(self, n: usize) -> (Self, bool) {
+                (
+                    self.wrapping_increase_nth_dim(n),
+                    self.0 & Self::mask_n(n) == Self::mask_n(n),
+                )
+            }
+
+            /// decrease n-th dim morton code on a periodic grid of `size` cells,
+            /// wrap around to `size - 1` if n-th dim coordinate is 0.
+            ///
+            /// n-th dim coordinate must be less than `size`.
+            pub const fn wrapping_decrease_nth_dim_mod(self, n: usize, size: $c) -> Self {
+                if self.0 & Self::mask_n(n) == 0 {
+                    self.with_nth_dim(n, size - 1)
+                } else {
+                    self.decrease_nth_dim(n)
+                }
+            }
+            /// increase n-th dim morton code on a periodic grid of `size` cells,
+            /// wrap around to 0 if n-th dim coordinate is `size - 1`.
+            ///
+            /// n-th dim coordinate must be less than `size`.
+            pub const fn wrapping_increase_nth_dim_mod(self, n: usize, size: $c) -> Self {
+                if self.0 & Self::mask_n(n) == Self::expand(size - 1) << n {
+                    self.with_nth_dim(n, 0)
+                } else {
+                    self.increase_nth_dim(n)
+                }
+            }
+
+            /// add `v` to n-th dim of morton code,
+            /// panic if it can't
+            pub const fn add_nth_dim(self, n: usize, v: $c) -> Self {
+                Self(
+                    (((self.0 | !Self::mask_n(n)) + (Self::expand(v) << n)) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+            /// subtract `v` from n-th dim of morton code,
+            /// panic if it can't
+            pub const fn sub_nth_dim(self, n: usize, v: $c) -> Self {
+                Self(
+                    (((self.0 & Self::mask_n(n)) - (Self::expand(v) << n)) & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+
+            /// add `v` to n-th dim of morton code,
+            /// return `None` if the coordinate exceeds `MAX_COORD`
+            pub const fn checked_add_nth_dim(self, n: usize, v: $c) -> Option<Self> {
+                if v > Self::MAX_COORD {
+                    return None;
+                }
+                match (self.0 | !Self::mask_n(n)).overflowing_add(Self::expand(v) << n) {
+                    (_, true) => None,
+                    (sum, false) => Some(Self(
+                        (sum & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)),
+                    )),
+                }
+            }
+            /// subtract `v` from n-th dim of morton code,
+            /// return `None` if the coordinate goes below 0
+            pub const fn checked_sub_nth_dim(self, n: usize, v: $c) -> Option<Self> {
+                if v > Self::MAX_COORD {
+                    return None;
+                }
+                match (self.0 & Self::mask_n(n)).overflowing_sub(Self::expand(v) << n) {
+                    (_, true) => None,
+                    (diff, false) => Some(Self(
+                        (diff & Self::mask_n(n)) | ((self.0) & !Self::mask_n(n)),
+                    )),
+                }
+            }
+
+            /// add `v` to n-th dim of morton code,
+            /// clamp the coordinate at `MAX_COORD`
+            pub const fn saturating_add_nth_dim(self, n: usize, v: $c) -> Self {
+                match self.checked_add_nth_dim(n, v) {
+                    Some(v) => v,
+                    None => self.with_nth_dim(n, Self::MAX_COORD),
+                }
+            }
+            /// subtract `v` from n-th dim of morton code,
+            /// clamp the coordinate at 0
+            pub const fn saturating_sub_nth_dim(self, n: usize, v: $c) -> Self {
+                match self.checked_sub_nth_dim(n, v) {
+                    Some(v) => v,
+                    None => self.with_nth_dim(n, 0),
+                }
+            }
+
+            /// add `v` to n-th dim of morton code,
+            /// wrap around modulo `MAX_COORD + 1`
+            pub const fn wrapping_add_nth_dim(self, n: usize, v: $c) -> Self {
+                Self(
+                    ((self.0 | !Self::mask_n(n)).wrapping_add(Self::expand(v) << n)
+                        & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+            /// subtract `v` from n-th dim of morton code,
+            /// wrap around modulo `MAX_COORD + 1`
+            pub const fn wrapping_sub_nth_dim(self, n: usize, v: $c) -> Self {
+                Self(
+                    ((self.0 & Self::mask_n(n)).wrapping_sub(Self::expand(v) << n)
+                        & Self::mask_n(n))
+                        | ((self.0) & !Self::mask_n(n)),
+                )
+            }
+
+            /// move n-th dim of morton code by signed `d`,
+            /// return `None` if the coordinate leaves `0..=MAX_COORD`
+            pub const fn checked_offset_nth_dim(self, n: usize, d: i32) -> Option<Self> {
+                let v = d.unsigned_abs() as u64;
+                if v > Self::MAX_COORD as u64 {
+                    None
+                } else if d < 0 {
+                    self.checked_sub_nth_dim(n, v as $c)
+                } else {
+                    self.checked_add_nth_dim(n, v as $c)
+                }
+            }
+
+            /// add coordinates of `rhs` to each axis, flag of `self` is kept.
+            ///
+            /// Also return whether any axis wrapped around.
+            pub const fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                let mut v = self.0 & Self::FLAG;
+                let mut overflow = false;
+                let mut n = 0;
+                while n < D {
+                    let m = Self::mask_n(n);
+                    let (sum, o) = (self.0 | !m).overflowing_add(rhs.0 & m);
+                    v |= sum & m;
+                    overflow |= o;
+                    n += 1;
+                }
+                (Self(v), overflow)
+            }
+            /// subtract coordinates of `rhs` from each axis, flag of `self` is kept.
+            ///
+            /// Also return whether any axis wrapped around.
+            pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+                let mut v = self.0 & Self::FLAG;
+                let mut overflow = false;
+                let mut n = 0;
+                while n < D {
+                    let m = Self::mask_n(n);
+                    let (diff, o) = (self.0 & m).overflowing_sub(rhs.0 & m);
+                    v |= diff & m;
+                    overflow |= o;
+                    n += 1;
+                }
+                (Self(v), overflow)
+            }
+
+            pub const fn wrapping_add(self, rhs: Self) -> Self {
+                self.overflowing_add(rhs).0
+            }
+            pub const fn wrapping_sub(self, rhs: Self) -> Self {
+                self.overflowing_sub(rhs).0
+            }
+
+            pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+                match self.overflowing_add(rhs) {
+                    (v, false) => Some(v),
+                    (_, true) => None,
+                }
+            }
+            pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+                match self.overflowing_sub(rhs) {
+                    (v, false) => Some(v),
+                    (_, true) => None,
+                }
+            }
+
+            /// per-axis minimum of two morton codes, flag of `self` is kept.
+            ///
+            /// Masked dilated coordinates compare like the coordinates themselves,
+            /// so this doesn't need to decode.
+            pub const fn component_min(self, other: Self) -> Self {
+                let mut v = self.0 & Self::FLAG;
+                let mut n = 0;
+                while n < D {
+                    let (a, b) = (self.0 & Self::mask_n(n), other.0 & Self::mask_n(n));
+                    v |= if a < b { a } else { b };
+                    n += 1;
+                }
+                Self(v)
+            }
+            /// per-axis maximum of two morton codes, flag of `self` is kept.
+            pub const fn component_max(self, other: Self) -> Self {
+                let mut v = self.0 & Self::FLAG;
+                let mut n = 0;
+                while n < D {
+                    let (a, b) = (self.0 & Self::mask_n(n), other.0 & Self::mask_n(n));
+                    v |= if a > b { a } else { b };
+                    n += 1;
+                }
+                Self(v)
+            }
+
+            /// whether every coordinate is within the ones of `min` and `max`, flag is ignored.
+            ///
+            /// The dilated bit groups are compared directly, without decoding the coordinates.
+            pub const fn is_within(self, min: Self, max: Self) -> bool {
+                let mut n = 0;
+                while n < D {
+                    let m = Self::mask_n(n);
+                    if self.0 & m < min.0 & m || self.0 & m > max.0 & m {
+                        return false;
+                    }
+                    n += 1;
+                }
+                true
+            }
+
+            /// smallest code greater than `self` whose coordinates are within the ones of `min` and `max`,
+            /// flag is ignored and never set in the result.
+            ///
+            /// This is the BIGMIN of Tropf and Herzog, used to jump over the sections of the curve
+            /// outside of a query box. `self` must be outside of the box and less than `max`.
+            pub const fn bigmin(self, min: Self, max: Self) -> Self {
+                let v = self.0 & !Self::FLAG;
+                let mut min = min.0 & !Self::FLAG;
+                let mut max = max.0 & !Self::FLAG;
+                let mut bigmin = 0;
+                let mut i = D * Self::MAX_DEPTH as usize;
+                while i > 0 {
+                    i -= 1;
+                    let bit: $t = 1 << i;
+                    // lower bits of the same axis
+                    let below = Self::mask_n(i % D) & (bit - 1);
+                    match (v & bit != 0, min & bit != 0, max & bit != 0) {
+                        (false, false, false) | (true, true, true) => {}
+                        (false, false, true) => {
+                            bigmin = (min & !below) | bit;
+                            max = (max & !bit) | below;
+                        }
+                        (false, true, true) => return Self(min),
+                        (true, false, false) => return Self(bigmin),
+                        (true, false, true) => min = (min & !below) | bit,
+                        _ => panic!("min must not exceed max on any axis"),
+                    }
+                }
+                Self(bigmin)
+            }
+
+            /// largest code less than `self` whose coordinates are within the ones of `min` and `max`,
+            /// flag is ignored and never set in the result.
+            ///
+            /// This is the LITMAX of Tropf and Herzog, the counterpart of [`bigmin`](Self::bigmin).
+            /// `self` must be outside of the box and greater than `min`.
+            pub const fn litmax(self, min: Self, max: Self) -> Self {
+                let v = self.0 & !Self::FLAG;
+                let mut min = min.0 & !Self::FLAG;
+                let mut max = max.0 & !Self::FLAG;
+                let mut litmax = 0;
+                let mut i = D * Self::MAX_DEPTH as usize;
+                while i > 0 {
+                    i -= 1;
+                    let bit: $t = 1 << i;
+                    // lower bits of the same axis
+                    let below = Self::mask_n(i % D) & (bit - 1);
+                    match (v & bit != 0, min & bit != 0, max & bit != 0) {
+                        (false, false, false) | (true, true, true) => {}
+                        (false, false, true) => max = (max & !bit) | below,
+                        (false, true, true) => return Self(litmax),
+                        (true, false, false) => return Self(max),
+                        (true, false, true) => {
+                            litmax = (max & !bit) | below;
+                            min = (min & !below) | bit;
+                        }
+                        _ => panic!("min must not exceed max on any axis"),
+                    }
+                }
+                Self(litmax)
+            }
+
+            /// number of leading code bits (below the unused bits) shared by both codes, flag is ignored.
+            ///
+            /// `D * MAX_DEPTH` if the codes are the same.
+            pub const fn common_prefix_bits(self, other: Self) -> u32 {
+                let used = D as u32 * Self::MAX_DEPTH;
+                let diff = (self.0 ^ other.0) & ((1 << used) - 1);
+                diff.leading_zeros() - (Self::BITS - used)
+            }
+
+            /// first level (1-based, from the coarsest) whose `D`-bit digit differs between both codes,
+            /// `None` if the codes are the same. Flag is ignored.
+            ///
+            /// One less than it is the depth of the deepest cell containing both cells.
+            pub const fn first_diff_level(self, other: Self) -> Option<u32> {
+                let prefix = self.common_prefix_bits(other);
+                if prefix == D as u32 * Self::MAX_DEPTH {
+                    None
+                } else {
+                    Some(prefix / D as u32 + 1)
+                }
+            }
+
+            /// whether n-th dim coordinate is 0
+            pub const fn is_min_nth_dim(self, n: usize) -> bool {
+                self.0 & Self::mask_n(n) == 0
+            }
+            /// whether n-th dim coordinate is the last one of a grid of `depth` levels,
+            /// i.e. `2^depth - 1`
+            pub const fn is_max_nth_dim(self, n: usize, depth: u32) -> bool {
+                assert!(depth <= Self::MAX_DEPTH, "depth out of range");
+                let max = Self::DILATION_MASKS[0] & ((1 << (D as u32 * depth)) - 1);
+                self.0 & Self::mask_n(n) == max << n
+            }
+
+            /// get n-th dim coordinate
+            pub const fn nth_dim(self, n: usize) -> $c {
+                Self::compact((self.0 & Self::mask_n(n)) >> n)
+            }
+
+            /// replace n-th dim coordinate, other dims and flag are kept
+            pub const fn with_nth_dim(self, n: usize, v: $c) -> Self {
+                Self((self.0 & !Self::mask_n(n)) | (Self::expand(v) << n))
+            }
+
+            pub const fn set_nth_dim(&mut self, n: usize, v: $c) {
+                *self = self.with_nth_dim(n, v)
+            }
+        }
+
+        impl MortonND<2, $t> {
+            /// encode coordinates into morton code.
+            ///
+            /// Only the lower `MAX_DEPTH` bits of each coordinate are used,
+            /// so the flag bit is never set by encoding.
+            pub const fn from_coords(x: $c, y: $c) -> Self {
+                Self::from_array([x, y])
+            }
+
+            /// encode coordinates into morton code,
+            /// return error if any coordinate exceeds `MAX_COORD`.
+            pub const fn try_from_coords(x: $c, y: $c) -> Result<Self, MortonError> {
+                Self::try_from_array([x, y])
+            }
+
+            /// decode morton code into coordinates `(x, y)`, flag bit is ignored.
+            pub const fn to_coords(self) -> ($c, $c) {
+                (self.x(), self.y())
+            }
+
+            pub const fn x(self) -> $c {
+                self.nth_dim(0)
+            }
+            pub const fn y(self) -> $c {
+                self.nth_dim(1)
+            }
+
+            pub const fn with_x(self, x: $c) -> Self {
+                self.with_nth_dim(0, x)
+            }
+            pub const fn with_y(self, y: $c) -> Self {
+                self.with_nth_dim(1, y)
+            }
+
+            pub const fn set_x(&mut self, x: $c) {
+                self.set_nth_dim(0, x)
+            }
+            pub const fn set_y(&mut self, y: $c) {
+                self.set_nth_dim(1, y)
+            }
+
+            pub const fn decrease_x(self) -> Self {
+                self.decrease_nth_dim(0)
+            }
+            pub const fn decrease_y(self) -> Self {
+                self.decrease_nth_dim(1)
+            }
+
+            pub const fn increase_x(self) -> Self {
+                self.increase_nth_dim(0)
+            }
+            pub const fn increase_y(self) -> Self {
+                self.increase_nth_dim(1)
+            }
+
+            pub const fn checked_decrease_x(self) -> Option<Self> {
+                self.checked_decrease_nth_dim(0)
+            }
+            pub const fn checked_decrease_y(self) -> Option<Self> {
+                self.checked_decrease_nth_dim(1)
+            }
+
+            pub const fn checked_increase_x(self) -> Option<Self> {
+                self.checked_increase_nth_dim(0)
+            }
+            pub const fn checked_increase_y(self) -> Option<Self> {
+                self.checked_increase_nth_dim(1)
+            }
+
+            pub const fn saturating_decrease_x(self) -> Self {
+                self.saturating_decrease_nth_dim(0)
+            }
+            pub const fn saturating_decrease_y(self) -> Self {
+                self.saturating_decrease_nth_dim(1)
+            }
+
+            pub const fn saturating_increase_x(self) -> Self {
+                self.saturating_increase_nth_dim(0)
+            }
+            pub const fn saturating_increase_y(self) -> Self {
+                self.saturating_increase_nth_dim(1)
+            }
+
+            pub const fn wrapping_decrease_x(self) -> Self {
+                self.wrapping_decrease_nth_dim(0)
+            }
+            pub const fn wrapping_decrease_y(self) -> Self {
+                self.wrapping_decrease_nth_dim(1)
+            }
+
+            pub const fn wrapping_increase_x(self) -> Self {
+                self.wrapping_increase_nth_dim(0)
+            }
+            pub const fn wrapping_increase_y(self) -> Self {
+                self.wrapping_increase_nth_dim(1)
+            }
+
+            pub const fn wrapping_decrease_x_mod(self, size: $c) -> Self {
+                self.wrapping_decrease_nth_dim_mod(0, size)
+            }
+            pub const fn wrapping_decrease_y_mod(self, size: $c) -> Self {
+                self.wrapping_decrease_nth_dim_mod(1, size)
+            }
+
+            pub const fn wrapping_increase_x_mod(self, size: $c) -> Self {
+                self.wrapping_increase_nth_dim_mod(0, size)
+            }
+            pub const fn wrapping_increase_y_mod(self, size: $c) -> Self {
+                self.wrapping_increase_nth_dim_mod(1, size)
+            }
+
+            pub const fn overflowing_decrease_x(self) -> (Self, bool) {
+                self.overflowing_decrease_nth_dim(0)
+            }
+            pub const fn overflowing_decrease_y(self) -> (Self, bool) {
+                self.overflowing_decrease_nth_dim(1)
+            }
+
+            pub const fn overflowing_increase_x(self) -> (Self, bool) {
+                self.overflowing_increase_nth_dim(0)
+            }
+            pub const fn overflowing_increase_y(self) -> (Self, bool) {
+                self.overflowing_increase_nth_dim(1)
+            }
+
+            pub const fn add_x(self, x: $c) -> Self {
+                self.add_nth_dim(0, x)
+            }
+            pub const fn add_y(self, y: $c) -> Self {
+                self.add_nth_dim(1, y)
+            }
+
+            pub const fn sub_x(self, x: $c) -> Self {
+                self.sub_nth_dim(0, x)
+            }
+            pub const fn sub_y(self, y: $c) -> Self {
+                self.sub_nth_dim(1, y)
+            }
+
+            pub const fn checked_add_x(self, x: $c) -> Option<Self> {
+                self.checked_add_nth_dim(0, x)
+            }
+            pub const fn checked_add_y(self, y: $c) -> Option<Self> {
+                self.checked_add_nth_dim(1, y)
+            }
+
+            pub const fn checked_sub_x(self, x: $c) -> Option<Self> {
+                self.checked_sub_nth_dim(0, x)
+            }
+            pub const fn checked_sub_y(self, y: $c) -> Option<Self> {
+                self.checked_sub_nth_dim(1, y)
+            }
+
+            pub const fn saturating_add_x(self, x: $c) -> Self {
+                self.saturating_add_nth_dim(0, x)
+            }
+            pub const fn saturating_add_y(self, y: $c) -> Self {
+                self.saturating_add_nth_dim(1, y)
+            }
+
+            pub const fn saturating_sub_x(self, x: $c) -> Self {
+                self.saturating_sub_nth_dim(0, x)
+            }
+            pub const fn saturating_sub_y(self, y: $c) -> Self {
+                self.saturating_sub_nth_dim(1, y)
+            }
+
+            pub const fn wrapping_add_x(self, x: $c) -> Self {
+                self.wrapping_add_nth_dim(0, x)
+            }
+            pub const fn wrapping_add_y(self, y: $c) -> Self {
+                self.wrapping_add_nth_dim(1, y)
+            }
+
+            pub const fn wrapping_sub_x(self, x: $c) -> Self {
+                self.wrapping_sub_nth_dim(0, x)
+            }
+            pub const fn wrapping_sub_y(self, y: $c) -> Self {
+                self.wrapping_sub_nth_dim(1, y)
+            }
+
+            pub const fn is_min_x(self) -> bool {
+                self.is_min_nth_dim(0)
+            }
+            pub const fn is_min_y(self) -> bool {
+                self.is_min_nth_dim(1)
+            }
+
+            pub const fn is_max_x(self, depth: u32) -> bool {
+                self.is_max_nth_dim(0, depth)
+            }
+            pub const fn is_max_y(self, depth: u32) -> bool {
+                self.is_max_nth_dim(1, depth)
+            }
+        }
+
+        impl MortonND<3, $t> {
+            /// encode coordinates into morton code.
+            ///
+            /// Only the lower `MAX_DEPTH` bits of each coordinate are used,
+            /// so the flag bit is never set by encoding.
+            pub const fn from_coords(x: $c, y: $c, z: $c) -> Self {
+                Self::from_array([x, y, z])
+            }
+
+            /// encode coordinates into morton code,
+            /// return error if any coordinate exceeds `MAX_COORD`.
+            pub const fn try_from_coords(x: $c, y: $c, z: $c) -> Result<Self, MortonError> {
+                Self::try_from_array([x, y, z])
+            }
+
+            /// decode morton code into coordinates `(x, y, z)`, flag bit is ignored.
+            pub const fn to_coords(self) -> ($c, $c, $c) {
+                (self.x(), self.y(), self.z())
+            }
+
+            pub const fn x(self) -> $c {
+                self.nth_dim(0)
+            }
+            pub const fn y(self) -> $c {
+                self.nth_dim(1)
+            }
+            pub const fn z(self) -> $c {
+                self.nth_dim(2)
+            }
+
+            pub const fn with_x(self, x: $c) -> Self {
+                self.with_nth_dim(0, x)
+            }
+            pub const fn with_y(self, y: $c) -> Self {
+                self.with_nth_dim(1, y)
+            }
+            pub const fn with_z(self, z: $c) -> Self {
+                self.with_nth_dim(2, z)
+            }
+
+            pub const fn set_x(&mut self, x: $c) {
+                self.set_nth_dim(0, x)
+            }
+            pub const fn set_y(&mut self, y: $c) {
+                self.set_nth_dim(1, y)
+            }
+            pub const fn set_z(&mut self, z: $c) {
+                self.set_nth_dim(2, z)
+            }
+
+            pub const fn decrease_x(self) -> Self {
+                self.decrease_nth_dim(0)
+            }
+            pub const fn decrease_y(self) -> Self {
+                self.decrease_nth_dim(1)
+            }
+            pub const fn decrease_z(self) -> Self {
+                self.decrease_nth_dim(2)
+            }
+
+            pub const fn increase_x(self) -> Self {
+                self.increase_nth_dim(0)
+            }
+            pub const fn increase_y(self) -> Self {
+                self.increase_nth_dim(1)
+            }
+            pub const fn increase_z(self) -> Self {
+                self.increase_nth_dim(2)
+            }
+
+            pub const fn checked_decrease_x(self) -> Option<Self> {
+                self.checked_decrease_nth_dim(0)
+            }
+            pub const fn checked_decrease_y(self) -> Option<Self> {
+                self.checked_decrease_nth_dim(1)
+            }
+            pub const fn checked_decrease_z(self) -> Option<Self> {
+                self.checked_decrease_nth_dim(2)
+            }
+
+            pub const fn checked_increase_x(self) -> Option<Self> {
+                self.checked_increase_nth_dim(0)
+            }
+            pub const fn checked_increase_y(self) -> Option<Self> {
+                self.checked_increase_nth_dim(1)
+            }
+            pub const fn checked_increase_z(self) -> Option<Self> {
+                self.checked_increase_nth_dim(2)
+            }
+
+            pub const fn saturating_decrease_x(self) -> Self {
+                self.saturating_decrease_nth_dim(0)
+            }
+            pub const fn saturating_decrease_y(self) -> Self {
+                self.saturating_decrease_nth_dim(1)
+            }
+            pub const fn saturating_decrease_z(self) -> Self {
+                self.saturating_decrease_nth_dim(2)
+            }
+
+            pub const fn saturating_increase_x(self) -> Self {
+                self.saturating_increase_nth_dim(0)
+            }
+            pub const fn saturating_increase_y(self) -> Self {
+                self.saturating_increase_nth_dim(1)
+            }
+            pub const fn saturating_increase_z(self) -> Self {
+                self.saturating_increase_nth_dim(2)
+            }
+
+            pub const fn wrapping_decrease_x(self) -> Self {
+                self.wrapping_decrease_nth_dim(0)
+            }
+            pub const fn wrapping_decrease_y(self) -> Self {
+                self.wrapping_decrease_nth_dim(1)
+            }
+            pub const fn wrapping_decrease_z(self) -> Self {
+                self.wrapping_decrease_nth_dim(2)
+            }
+
+            pub const fn wrapping_increase_x(self) -> Self {
+                self.wrapping_increase_nth_dim(0)
+            }
+            pub const fn wrapping_increase_y(self) -> Self {
+                self.wrapping_increase_nth_dim(1)
+            }
+            pub const fn wrapping_increase_z(self) -> Self {
+                self.wrapping_increase_nth_dim(2)
+            }
+
+            pub const fn wrapping_decrease_x_mod(self, size: $c) -> Self {
+                self.wrapping_decrease_nth_dim_mod(0, size)
+            }
+            pub const fn wrapping_decrease_y_mod(self, size: $c) -> Self {
+                self.wrapping_decrease_nth_dim_mod(1, size)
+            }
+            pub const fn wrapping_decrease_z_mod(self, size: $c) -> Self {
+                self.wrapping_decrease_nth_dim_mod(2, size)
+            }
+
+            pub const fn wrapping_increase_x_mod(self, size: $c) -> Self {
+                self.wrapping_increase_nth_dim_mod(0, size)
+            }
+            pub const fn wrapping_increase_y_mod(self, size: $c) -> Self {
+                self.wrapping_increase_nth_dim_mod(1, size)
+            }
+            pub const fn wrapping_increase_z_mod(self, size: $c) -> Self {
+                self.wrapping_increase_nth_dim_mod(2, size)
+            }
+
+            pub const fn overflowing_decrease_x(self) -> (Self, bool) {
+                self.overflowing_decrease_nth_dim(0)
+            }
+            pub const fn overflowing_decrease_y(self) -> (Self, bool) {
+                self.overflowing_decrease_nth_dim(1)
+            }
+            pub const fn overflowing_decrease_z(self) -> (Self, bool) {
+                self.overflowing_decrease_nth_dim(2)
+            }
+
+            pub const fn overflowing_increase_x(self) -> (Self, bool) {
+                self.overflowing_increase_nth_dim(0)
+            }
+            pub const fn overflowing_increase_y(self) -> (Self, bool) {
+                self.overflowing_increase_nth_dim(1)
+            }
+            pub const fn overflowing_increase_z(self) -> (Self, bool) {
+                self.overflowing_increase_nth_dim(2)
+            }
+
+            pub const fn add_x(self, x: $c) -> Self {
+                self.add_nth_dim(0, x)
+            }
+            pub const fn add_y(self, y: $c) -> Self {
+                self.add_nth_dim(1, y)
+            }
+            pub const fn add_z(self, z: $c) -> Self {
+                self.add_nth_dim(2, z)
+            }
+
+            pub const fn sub_x(self, x: $c) -> Self {
+                self.sub_nth_dim(0, x)
+            }
+            pub const fn sub_y(self, y: $c) -> Self {
+                self.sub_nth_dim(1, y)
+            }
+            pub const fn sub_z(self, z: $c) -> Self {
+                self.sub_nth_dim(2, z)
+            }
+
+            pub const fn checked_add_x(self, x: $c) -> Option<Self> {
+                self.checked_add_nth_dim(0, x)
+            }
+            pub const fn checked_add_y(self, y: $c) -> Option<Self> {
+                self.checked_add_nth_dim(1, y)
+            }
+            pub const fn checked_add_z(self, z: $c) -> Option<Self> {
+                self.checked_add_nth_dim(2, z)
+            }
+
+            pub const fn checked_sub_x(self, x: $c) -> Option<Self> {
+                self.checked_sub_nth_dim(0, x)
+            }
+            pub const fn checked_sub_y(self, y: $c) -> Option<Self> {
+                self.checked_sub_nth_dim(1, y)
+            }
+            pub const fn checked_sub_z(self, z: $c) -> Option<Self> {
+                self.checked_sub_nth_dim(2, z)
+            }
+
+            pub const fn saturating_add_x(self, x: $c) -> Self {
+                self.saturating_add_nth_dim(0, x)
+            }
+            pub const fn saturating_add_y(self, y: $c) -> Self {
+                self.saturating_add_nth_dim(1, y)
+            }
+            pub const fn saturating_add_z(self, z: $c) -> Self {
+                self.saturating_add_nth_dim(2, z)
+            }
+
+            pub const fn saturating_sub_x(self, x: $c) -> Self {
+                self.saturating_sub_nth_dim(0, x)
+            }
+            pub const fn saturating_sub_y(self, y: $c) -> Self {
+                self.saturating_sub_nth_dim(1, y)
+            }
+            pub const fn saturating_sub_z(self, z: $c) -> Self {
+                self.saturating_sub_nth_dim(2, z)
+            }
+
+            pub const fn wrapping_add_x(self, x: $c) -> Self {
+                self.wrapping_add_nth_dim(0, x)
+            }
+            pub const fn wrapping_add_y(self, y: $c) -> Self {
+                self.wrapping_add_nth_dim(1, y)
+            }
+            pub const fn wrapping_add_z(self, z: $c) -> Self {
+                self.wrapping_add_nth_dim(2, z)
+            }
+
+            pub const fn wrapping_sub_x(self, x: $c) -> Self {
+                self.wrapping_sub_nth_dim(0, x)
+            }
+            pub const fn wrapping_sub_y(self, y: $c) -> Self {
+                self.wrapping_sub_nth_dim(1, y)
+            }
+            pub const fn wrapping_sub_z(self, z: $c) -> Self {
+                self.wrapping_sub_nth_dim(2, z)
+            }
+
+            pub const fn is_min_x(self) -> bool {
+                self.is_min_nth_dim(0)
+            }
+            pub const fn is_min_y(self) -> bool {
+                self.is_min_nth_dim(1)
+            }
+            pub const fn is_min_z(self) -> bool {
+                self.is_min_nth_dim(2)
+            }
+
+            pub const fn is_max_x(self, depth: u32) -> bool {
+                self.is_max_nth_dim(0, depth)
+            }
+            pub const fn is_max_y(self, depth: u32) -> bool {
+                self.is_max_nth_dim(1, depth)
+            }
+            pub const fn is_max_z(self, depth: u32) -> bool {
+                self.is_max_nth_dim(2, depth)
+            }
+
+            /// move morton code `n` steps toward `dir`, flag is kept.
+            ///
+            /// panic if it can't
+            pub const fn shift(self, dir: Direction, n: $c) -> Self {
+                if dir.is_positive() {
+                    self.add_nth_dim(dir.axis(), n)
+                } else {
+                    self.sub_nth_dim(dir.axis(), n)
+                }
+            }
+
+            /// move morton code `n` steps toward `dir`, flag is kept.
+            ///
+            /// return `None` if the coordinate leaves `0..=MAX_COORD`.
+            pub const fn checked_shift(self, dir: Direction, n: $c) -> Option<Self> {
+                if dir.is_positive() {
+                    self.checked_add_nth_dim(dir.axis(), n)
+                } else {
+                    self.checked_sub_nth_dim(dir.axis(), n)
+                }
+            }
+
+            /// move morton code by `offset`, flag is kept.
+            ///
+            /// return `None` if any coordinate leaves `0..=MAX_COORD`.
+            pub const fn translate(self, offset: Offset3D) -> Option<Self> {
+                let v = match self.checked_offset_nth_dim(0, offset.x) {
+                    Some(v) => v,
+                    None => return None,
+                };
+                let v = match v.checked_offset_nth_dim(1, offset.y) {
+                    Some(v) => v,
+                    None => return None,
+                };
+                v.checked_offset_nth_dim(2, offset.z)
+            }
+        }
+
+        /// Steps along the curve, the flag and the unused bits are excluded from the stepping
+        /// and kept as they are (like [`next_on_curve`](MortonND::next_on_curve)),
+        /// so both ends of a range should have the same flag.
+        #[cfg(feature = "nightly")]
+        impl<const D: usize> core::iter::Step for MortonND<D, $t> {
+            fn steps_between(start: &Self, end: &Self) -> (usize, Option<usize>) {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let (a, b) = (start.0 & last, end.0 & last);
+                if a > b {
+                    return (0, None);
+                }
+                match usize::try_from(b - a) {
+                    Ok(n) => (n, Some(n)),
+                    Err(_) => (usize::MAX, None),
+                }
+            }
+            fn forward_checked(start: Self, count: usize) -> Option<Self> {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let count = <$t>::try_from(count).ok()?;
+                let v = (start.0 & last).checked_add(count)?;
+                if v > last {
+                    return None;
+                }
+                Some(Self(v | start.0 & !last))
+            }
+            fn backward_checked(start: Self, count: usize) -> Option<Self> {
+                let last = (1 << (D as u32 * Self::MAX_DEPTH)) - 1;
+                let count = <$t>::try_from(count).ok()?;
+                let v = (start.0 & last).checked_sub(count)?;
+                Some(Self(v | start.0 & !last))
+            }
+        }
+
+        impl<const D: usize> Add for MortonND<D, $t> {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                let (v, overflow) = self.overflowing_add(rhs);
+                debug_assert!(!overflow, "attempt to add with overflow");
+                v
+            }
+        }
+
+        impl<const D: usize> Sub for MortonND<D, $t> {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                let (v, overflow) = self.overflowing_sub(rhs);
+                debug_assert!(!overflow, "attempt to subtract with overflow");
+                v
+            }
+        }
+
+        impl<const D: usize> AddAssign for MortonND<D, $t> {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs
+            }
+        }
+
+        impl<const D: usize> SubAssign for MortonND<D, $t> {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs
+            }
+        }
+
+        impl<const D: usize> From<$t> for MortonND<D, $t> {
+            fn from(v: $t) -> Self {
+                Self(v)
+            }
+        }
+
+        impl<const D: usize> From<MortonND<D, $t>> for $t {
+            fn from(v: MortonND<D, $t>) -> Self {
+                v.0
+            }
+        }
+
+        impl<const D: usize> From<[$c; D]> for MortonND<D, $t> {
+            fn from(coords: [$c; D]) -> Self {
+                Self::from_array(coords)
+            }
+        }
+
+        impl<const D: usize> From<MortonND<D, $t>> for [$c; D] {
+            fn from(v: MortonND<D, $t>) -> Self {
+                v.to_array()
+            }
+        }
+
+        impl From<($c, $c)> for MortonND<2, $t> {
+            fn from((x, y): ($c, $c)) -> Self {
+                Self::from_coords(x, y)
+            }
+        }
+
+        impl From<MortonND<2, $t>> for ($c, $c) {
+            fn from(v: MortonND<2, $t>) -> Self {
+                v.to_coords()
+            }
+        }
+
+        impl From<($c, $c, $c)> for MortonND<3, $t> {
+            fn from((x, y, z): ($c, $c, $c)) -> Self {
+                Self::from_coords(x, y, z)
+            }
+        }
+
+        impl From<MortonND<3, $t>> for ($c, $c, $c) {
+            fn from(v: MortonND<3, $t>) -> Self {
+                v.to_coords()
+            }
+        }
+    };
+}
+
+morton_nd!(u16, u16);
+morton_nd!(u32, u32);
+morton_nd!(u64, u32);
+morton_nd!(u128, u64);
+morton_nd!(usize, u32);
+
+impl MortonND<3> {
+    /// build morton code from dilated coordinates
+    pub const fn from_dilated(x: Dilated21, y: Dilated21, z: Dilated21) -> Self {
+        let v = x.raw() | (y.raw() << 1) | (z.raw() << 2);
+        Self((v as usize) & (Self::MASK | Self::MASK << 1 | Self::MASK << 2))
+    }
+
+    /// get n-th dim (0: x, 1: y, 2: z) coordinate in dilated form
+    const fn nth_dim_dilated(self, n: usize) -> Dilated21 {
+        Dilated21::from_raw(((self.0 & Self::mask_n(n)) >> n) as u64)
+    }
+
+    pub const fn dilated_x(self) -> Dilated21 {
+        self.nth_dim_dilated(0)
+    }
+    pub const fn dilated_y(self) -> Dilated21 {
+        self.nth_dim_dilated(1)
+    }
+    pub const fn dilated_z(self) -> Dilated21 {
+        self.nth_dim_dilated(2)
+    }
+}
+
+/// Implement signed differences and distances for storage `$t`, whose coordinates fit in `i32`.
+macro_rules! morton_delta {
+    ($t:ty) => {
+        impl<const D: usize> MortonND<D, $t> {
+            /// signed difference of n-th dim coordinate, `other - self`.
+            pub const fn delta_nth_dim(self, other: Self, n: usize) -> i32 {
+                let (a, b) = (self.0 & Self::mask_n(n), other.0 & Self::mask_n(n));
+                if a <= b {
+                    Self::compact(((b - a) & Self::mask_n(n)) >> n) as i32
+                } else {
+                    -(Self::compact(((a - b) & Self::mask_n(n)) >> n) as i32)
+                }
+            }
+
+            /// max of per-axis distances, flag is ignored.
+            pub const fn chebyshev_distance(self, other: Self) -> u32 {
+                let mut d = 0;
+                let mut n = 0;
+                while n < D {
+                    let v = self.delta_nth_dim(other, n).unsigned_abs();
+                    if v > d {
+                        d = v;
+                    }
+                    n += 1;
+                }
+                d
+            }
+
+            /// sum of per-axis distances, flag is ignored.
+            pub const fn manhattan_distance(self, other: Self) -> u64 {
+                let mut d = 0;
+                let mut n = 0;
+                while n < D {
+                    d += self.delta_nth_dim(other, n).unsigned_abs() as u64;
+                    n += 1;
+                }
+                d
+            }
+
+            /// squared euclidean distance, flag is ignored.
+            pub const fn euclidean_distance_squared(self, other: Self) -> u64 {
+                let mut d = 0;
+                let mut n = 0;
+                while n < D {
+                    let v = self.delta_nth_dim(other, n).unsigned_abs() as u64;
+                    d += v * v;
+                    n += 1;
+                }
+                d
+            }
+        }
+
+        impl MortonND<2, $t> {
+            /// signed per-axis difference `other - self`, flag is ignored.
+            pub const fn delta(self, other: Self) -> (i32, i32) {
+                (self.delta_nth_dim(other, 0), self.delta_nth_dim(other, 1))
+            }
+        }
+
+        impl MortonND<3, $t> {
+            /// signed per-axis difference `other - self`, flag is ignored.
+            ///
+            /// `self.translate(self.delta(other).into())` is `other` without its flag.
+            pub const fn delta(self, other: Self) -> (i32, i32, i32) {
+                (
+                    self.delta_nth_dim(other, 0),
+                    self.delta_nth_dim(other, 1),
+                    self.delta_nth_dim(other, 2),
+                )
+            }
+        }
+    };
+}
+
+morton_delta!(u16);
+morton_delta!(u32);
+morton_delta!(u64);
+morton_delta!(usize);
+
+/// Define conversions between morton codes of narrow storage `$nt` and wide storage `$wt`.
+///
+/// Narrow to wide is lossless (`From`), wide to narrow fails if any coordinate doesn't fit (`TryFrom`),
+/// or drops the higher bits of each coordinate (`$truncate`). Flag is kept in every conversion.
+macro_rules! morton_widen {
+    ($nt:ty, $wt:ty, $truncate:ident) => {
+        impl<const D: usize> From<MortonND<D, $nt>> for MortonND<D, $wt> {
+            fn from(v: MortonND<D, $nt>) -> Self {
+                let flag = if v.is_flag_set() { Self::FLAG } else { 0 };
+                Self((v.0 & !MortonND::<D, $nt>::FLAG) as $wt | flag)
+            }
+        }
+
+        impl<const D: usize> core::convert::TryFrom<MortonND<D, $wt>> for MortonND<D, $nt> {
+            type Error = MortonError;
+            fn try_from(v: MortonND<D, $wt>) -> Result<Self, MortonError> {
+                for axis in 0..D {
+                    let value = v.nth_dim(axis) as u64;
+                    if value > Self::MAX_COORD as u64 {
+                        return Err(MortonError::CoordinateOutOfRange {
+                            axis,
+                            value,
+                            max: Self::MAX_COORD as u64,
+                        });
+                    }
+                }
+                Ok(v.$truncate())
+            }
+        }
+
+        impl<const D: usize> MortonND<D, $wt> {
+            #[doc = concat!("convert to `", stringify!($nt), "` storage, higher bits of each coordinate are dropped.")]
+            pub const fn $truncate(self) -> MortonND<D, $nt> {
+                let depth = MortonND::<D, $nt>::MAX_DEPTH as usize;
+                let bits = self.0 & ((1 << (D * depth)) - 1);
+                let flag = if self.is_flag_set() { MortonND::<D, $nt>::FLAG } else { 0 };
+                MortonND(bits as $nt | flag)
+            }
+        }
+    };
+}
+
+morton_widen!(u16, u32, truncate_16);
+morton_widen!(u16, u64, truncate_16);
+morton_widen!(u16, u128, truncate_16);
+morton_widen!(u32, u64, truncate_32);
+morton_widen!(u64, u128, truncate_64);
+morton_widen!(u32, u128, truncate_32);
+
+/// Define lossless conversions between morton codes of `usize` and the storage `$t` of the same width.
+macro_rules! morton_same_width {
+    ($t:ty) => {
+        impl<const D: usize> From<MortonND<D>> for MortonND<D, $t> {
+            fn from(v: MortonND<D>) -> Self {
+                Self(v.0 as $t)
+            }
+        }
+
+        impl<const D: usize> From<MortonND<D, $t>> for MortonND<D> {
+            fn from(v: MortonND<D, $t>) -> Self {
+                Self(v.0 as usize)
+            }
+        }
+    };
+}
+
+#[cfg(target_pointer_width = "64")]
+morton_same_width!(u64);
+
+#[cfg(target_pointer_width = "32")]
+morton_same_width!(u32);
+
+#[cfg(test)]
+mod tests {
+    use super::{Morton3D, Morton3D16, Morton3D32, Morton3D64, MortonND};
+    use crate::MortonStorage;
+
+    /// number of levels of a 3-dimension code, only from the storage
+    fn levels<S: MortonStorage>(_: MortonND<3, S>) -> u32 {
+        (S::BITS - 1) / 3
+    }
+
+    #[test]
+    fn test_storage() {
+        assert_eq!(levels(Morton3D32::default()), Morton3D32::MAX_DEPTH);
+        assert_eq!(levels(Morton3D64::default()), Morton3D64::MAX_DEPTH);
+        assert_eq!(levels(Morton3D::default()), Morton3D::MAX_DEPTH);
+
+        assert_eq!(MortonND::<2, u32>::MAX_DEPTH, 15);
+        assert_eq!(MortonND::<2, u128>::MAX_DEPTH, 63);
+        assert_eq!(MortonND::<4, u64>::MAX_DEPTH, 15);
+
+        let morton = MortonND::<2, u128>::from_coords(1 << 62, 5);
+        assert_eq!(morton.increase_y().to_coords(), (1 << 62, 6));
+        let morton = MortonND::<2, u32>::from_coords(0x7fff, 3);
+        assert_eq!(u32::from(morton), 0x1555_555f);
+    }
+
+    #[test]
+    fn test_16() {
+        assert_eq!(Morton3D16::MAX_DEPTH, 5);
+        assert_eq!(Morton3D16::MAX_COORD, 31);
+
+        let mut morton = Morton3D16::from_coords(31, 0, 17);
+        assert!(!morton.is_flag_set());
+        morton.set_flag();
+        assert!(morton.is_flag_set());
+        assert_eq!(morton.to_coords(), (31, 0, 17));
+        assert_eq!(
+            morton.decrease_x().increase_y().increase_z().to_coords(),
+            (30, 1, 18)
+        );
+        morton.set_y(9);
+        assert_eq!(morton.with_x(1).to_coords(), (1, 9, 17));
+        morton.unset_flag();
+        assert_eq!(
+            u16::from(morton),
+            u16::from(Morton3D16::from_coords(31, 9, 17))
+        );
+
+        assert_eq!(
+            u16::from(Morton3D16::from_coords(31, 31, 31)),
+            0b0_111_111_111_111_111
+        );
+        assert!(Morton3D16::try_from_coords(32, 0, 0).is_err());
+
+        let wide = Morton3D64::from(morton);
+        assert_eq!(wide.to_coords(), (31, 9, 17));
+        assert_eq!(wide.truncate_16(), morton);
+    }
+
+    /// bit `i` of `coords[n]` at bit `coords.len() * i + n`
+    fn interleave(coords: &[u64], depth: u32) -> u128 {
+        let mut v = 0;
+        for i in 0..depth as usize {
+            for (n, &c) in coords.iter().enumerate() {
+                v |= u128::from((c >> i) & 1) << (coords.len() * i + n);
+            }
+        }
+        v
+    }
+
+    #[test]
+    fn test_dilation() {
+        for i in 0..200u64 {
+            let c = |axis: u64| (i + axis).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (i % 40);
+            let v = MortonND::<3, u16>::from_array([0, 1, 2].map(|a| c(a) as u16));
+            let expected = interleave(&[0, 1, 2].map(|a| c(a) & 31), 5);
+            assert_eq!(u128::from(u16::from(v)), expected);
+            assert_eq!(v.to_array(), [0, 1, 2].map(|a| (c(a) & 31) as u16));
+
+            let v = MortonND::<2, u32>::from_array([0, 1].map(|a| c(a) as u32));
+            let expected = interleave(&[0, 1].map(|a| c(a) & 0x7fff), 15);
+            assert_eq!(u128::from(u32::from(v)), expected);
+
+            let v = MortonND::<5, u64>::from_array([0, 1, 2, 3, 4].map(|a| c(a) as u32));
+            let expected = interleave(&[0, 1, 2, 3, 4].map(|a| c(a) & 0xfff), 12);
+            assert_eq!(u128::from(u64::from(v)), expected);
+            assert_eq!(v.to_array(), [0, 1, 2, 3, 4].map(|a| (c(a) & 0xfff) as u32));
+
+            let v = MortonND::<8, u128>::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(c));
+            let expected = interleave(&[0, 1, 2, 3, 4, 5, 6, 7].map(|a| c(a) & 0x7fff), 15);
+            assert_eq!(u128::from(v), expected);
+
+            let mut v = Morton3D64::from_array([0, 1, 2].map(|a| c(a) as u32));
+            let expected = interleave(&[0, 1, 2].map(|a| c(a) & 0x1f_ffff), 21);
+            assert_eq!(u128::from(u64::from(v)), expected);
+            v.set_flag();
+            assert_eq!(v.to_array(), [0, 1, 2].map(|a| (c(a) & 0x1f_ffff) as u32));
+        }
+    }
+
+    #[test]
+    fn test_storage_conversion() {
+        let mut morton = MortonND::<2, u32>::from_coords(100, 200);
+        morton.set_flag();
+        let wide = MortonND::<2, u128>::from(morton);
+        assert!(wide.is_flag_set());
+        assert_eq!(wide.to_coords(), (100, 200));
+        assert_eq!(wide.truncate_32(), morton);
+    }
+}
@@ -0,0 +1,403 @@
+use core::iter::FusedIterator;
+use core::slice;
+
+use crate::{MortonBox, MortonND, MortonStorage, Offset3D};
+
+/// Offsets in `{-1, 0, 1}^3` with 1 to `max_nonzero` non-zero components,
+/// ordered by `(z, y, x)`.
+const fn unit_offsets<const N: usize>(max_nonzero: u32) -> [Offset3D; N] {
+    let mut offsets = [Offset3D::ZERO; N];
+    let mut i = 0;
+    let mut z = -1;
+    while z <= 1 {
+        let mut y = -1;
+        while y <= 1 {
+            let mut x = -1;
+            while x <= 1 {
+                let nonzero = (x != 0) as u32 + (y != 0) as u32 + (z != 0) as u32;
+                if nonzero > 0 && nonzero <= max_nonzero {
+                    offsets[i] = Offset3D::new(x, y, z);
+                    i += 1;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        z += 1;
+    }
+    assert!(i == N, "wrong number of offsets");
+    offsets
+}
+
+/// Offsets to the cells sharing a face.
+pub(crate) const FACE_OFFSETS: [Offset3D; 6] = unit_offsets(1);
+
+/// Offsets to the cells sharing a face or an edge.
+pub(crate) const EDGE_OFFSETS: [Offset3D; 18] = unit_offsets(2);
+
+/// Offsets to all the surrounding cells.
+pub(crate) const MOORE_OFFSETS: [Offset3D; 26] = unit_offsets(3);
+
+/// Iterator over neighbor cells of a morton code, neighbors out of range or out of the bounds are skipped.
+///
+/// Flag of the center is kept in every neighbor.
+#[derive(Debug, Clone)]
+pub struct Neighbors<S: MortonStorage = usize> {
+    center: MortonND<3, S>,
+    offsets: slice::Iter<'static, Offset3D>,
+    bounds: MortonBox<S>,
+}
+
+/// Iterator over cells within a Chebyshev radius of a morton code in Z-order, the center is skipped.
+///
+/// Flag of the center is kept in every neighbor.
+#[derive(Debug, Clone)]
+pub struct NeighborsWithin<S: MortonStorage = usize> {
+    center: MortonND<3, S>,
+    next: Option<MortonND<3, S>>,
+    bounds: MortonBox<S>,
+}
+
+/// Implement neighbor iteration for 3-dimension morton codes of storage `$t`.
+macro_rules! neighbors {
+    ($t:ty, $c:ty) => {
+        impl MortonND<3, $t> {
+            /// Dilated offsets to the cells sharing a face, `v.wrapping_add(offset)` is the neighbor of `v`.
+            ///
+            /// Negative deltas are stored as two's complement of each axis,
+            /// so neighbors across the edges of the grid wrap around.
+            pub const FACE_OFFSETS: [Self; 6] = Self::dilated_offsets(FACE_OFFSETS);
+
+            /// Dilated offsets to the cells sharing a face or an edge, see [`FACE_OFFSETS`](Self::FACE_OFFSETS).
+            pub const EDGE_OFFSETS: [Self; 18] = Self::dilated_offsets(EDGE_OFFSETS);
+
+            /// Dilated offsets to all the surrounding cells, see [`FACE_OFFSETS`](Self::FACE_OFFSETS).
+            pub const MOORE_OFFSETS: [Self; 26] = Self::dilated_offsets(MOORE_OFFSETS);
+
+            /// convert unit offsets into dilated form
+            const fn dilated_offsets<const N: usize>(offsets: [Offset3D; N]) -> [Self; N] {
+                const fn unit(d: i32) -> $c {
+                    if d < 0 {
+                        MortonND::<3, $t>::MAX_COORD
+                    } else {
+                        d as $c
+                    }
+                }
+                let mut dilated = [Self::from_coords(0, 0, 0); N];
+                let mut i = 0;
+                while i < N {
+                    let o = offsets[i];
+                    dilated[i] = Self::from_coords(unit(o.x), unit(o.y), unit(o.z));
+                    i += 1;
+                }
+                dilated
+            }
+
+            /// iterate over the up-to-6 cells sharing a face with this cell.
+            pub fn face_neighbors(self) -> Neighbors<$t> {
+                self.face_neighbors_in(MortonBox::<$t>::FULL)
+            }
+            /// same as [`face_neighbors`](Self::face_neighbors), but only neighbors inside `bounds` are returned.
+            pub fn face_neighbors_in(self, bounds: MortonBox<$t>) -> Neighbors<$t> {
+                Neighbors {
+                    center: self,
+                    offsets: FACE_OFFSETS.iter(),
+                    bounds,
+                }
+            }
+
+            /// iterate over the up-to-18 cells sharing a face or an edge with this cell.
+            pub fn edge_neighbors(self) -> Neighbors<$t> {
+                self.edge_neighbors_in(MortonBox::<$t>::FULL)
+            }
+            /// same as [`edge_neighbors`](Self::edge_neighbors), but only neighbors inside `bounds` are returned.
+            pub fn edge_neighbors_in(self, bounds: MortonBox<$t>) -> Neighbors<$t> {
+                Neighbors {
+                    center: self,
+                    offsets: EDGE_OFFSETS.iter(),
+                    bounds,
+                }
+            }
+
+            /// iterate over the up-to-26 cells surrounding this cell.
+            pub fn moore_neighbors(self) -> Neighbors<$t> {
+                self.moore_neighbors_in(MortonBox::<$t>::FULL)
+            }
+            /// same as [`moore_neighbors`](Self::moore_neighbors), but only neighbors inside `bounds` are returned.
+            pub fn moore_neighbors_in(self, bounds: MortonBox<$t>) -> Neighbors<$t> {
+                Neighbors {
+                    center: self,
+                    offsets: MOORE_OFFSETS.iter(),
+                    bounds,
+                }
+            }
+
+            /// iterate over the up-to-26 cells surrounding the ancestor of this cell at `depth`.
+            ///
+            /// This cell is at `MAX_DEPTH`, returned cells are at `depth`,
+            /// i.e. their coordinates are in `0..2^depth`. Flag is kept.
+            pub fn coarse_neighbors(self, depth: u32) -> Neighbors<$t> {
+                let ancestor = self.ancestor_at(depth);
+                let max = Self::MAX_COORD >> (Self::MAX_DEPTH - depth);
+                let bounds =
+                    MortonBox::new(Self::from_coords(0, 0, 0), Self::from_coords(max, max, max));
+                ancestor.moore_neighbors_in(bounds)
+            }
+
+            /// iterate over the cells within Chebyshev distance `radius` of this cell in Z-order,
+            /// out-of-range cells and this cell itself are skipped.
+            pub fn neighbors_within(self, radius: $c) -> NeighborsWithin<$t> {
+                self.neighbors_within_in(radius, MortonBox::<$t>::FULL)
+            }
+            /// same as [`neighbors_within`](Self::neighbors_within), but only cells inside `bounds` are returned.
+            pub fn neighbors_within_in(
+                self,
+                radius: $c,
+                bounds: MortonBox<$t>,
+            ) -> NeighborsWithin<$t> {
+                let mut min = self;
+                let mut max = self;
+                for n in 0..3 {
+                    min = min.saturating_sub_nth_dim(n, radius);
+                    max = max.saturating_add_nth_dim(n, radius);
+                }
+                let bounds = MortonBox::new(min, max).intersection(bounds);
+                NeighborsWithin {
+                    center: self,
+                    next: bounds.map(MortonBox::min),
+                    bounds: bounds.unwrap_or(MortonBox::<$t>::FULL),
+                }
+            }
+        }
+
+        impl Iterator for Neighbors<$t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let (center, bounds) = (self.center, self.bounds);
+                self.offsets
+                    .find_map(|&offset| center.translate(offset).filter(|&n| bounds.contains(n)))
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(self.offsets.len()))
+            }
+        }
+
+        impl FusedIterator for Neighbors<$t> {}
+
+        impl Iterator for NeighborsWithin<$t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let mut center = self.center;
+                center.unset_flag();
+                loop {
+                    let current = self.next?;
+                    let (min, max) = (self.bounds.min(), self.bounds.max());
+                    self.next = if current == max {
+                        None
+                    } else {
+                        let next = MortonND::from(<$t>::from(current) + 1);
+                        if self.bounds.contains(next) {
+                            Some(next)
+                        } else {
+                            Some(next.bigmin(min, max))
+                        }
+                    };
+                    if current != center {
+                        let mut current = current;
+                        if self.center.is_flag_set() {
+                            current.set_flag();
+                        }
+                        return Some(current);
+                    }
+                }
+            }
+        }
+
+        impl FusedIterator for NeighborsWithin<$t> {}
+    };
+}
+
+neighbors!(u16, u16);
+neighbors!(u32, u32);
+neighbors!(u64, u32);
+neighbors!(u128, u64);
+neighbors!(usize, u32);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Morton3D, Morton3D16, Morton3D64, MortonBox};
+
+    #[test]
+    fn test_face_neighbors() {
+        let center = Morton3D::from_coords(5, 5, 5);
+        let neighbors: Vec<_> = center.face_neighbors().map(Morton3D::to_coords).collect();
+        assert_eq!(
+            neighbors,
+            [
+                (5, 5, 4),
+                (5, 4, 5),
+                (4, 5, 5),
+                (6, 5, 5),
+                (5, 6, 5),
+                (5, 5, 6)
+            ]
+        );
+
+        let mut corner = Morton3D::from_coords(0, 0, Morton3D::MAX_COORD);
+        corner.set_flag();
+        let neighbors: Vec<_> = corner.face_neighbors().collect();
+        assert_eq!(neighbors.len(), 3);
+        assert!(neighbors.iter().all(|n| n.is_flag_set()));
+        assert!(neighbors.contains(&corner.increase_x()));
+        assert!(neighbors.contains(&corner.increase_y()));
+        assert!(neighbors.contains(&corner.decrease_z()));
+
+        let small = Morton3D16::from_coords(31, 31, 31);
+        assert_eq!(small.face_neighbors().count(), 3);
+    }
+
+    #[test]
+    fn test_edge_neighbors() {
+        let center = Morton3D::from_coords(5, 5, 5);
+        let neighbors: Vec<_> = center.edge_neighbors().collect();
+        assert_eq!(neighbors.len(), 18);
+        assert!(neighbors.iter().all(|&n| {
+            let (dx, dy, dz) = center.delta(n);
+            dx.abs() + dy.abs() + dz.abs() <= 2
+        }));
+        assert!(center.face_neighbors().all(|n| neighbors.contains(&n)));
+        assert!(!neighbors.contains(&Morton3D::from_coords(6, 6, 6)));
+
+        let corner = Morton3D::from_coords(0, 0, 0);
+        assert_eq!(corner.edge_neighbors().count(), 6);
+        let edge = Morton3D::from_coords(0, 0, 1);
+        assert_eq!(edge.edge_neighbors().count(), 9);
+    }
+
+    #[test]
+    fn test_moore_neighbors() {
+        let center = Morton3D::from_coords(5, 5, 5);
+        let neighbors: Vec<_> = center.moore_neighbors().collect();
+        assert_eq!(neighbors.len(), 26);
+        assert!(neighbors.iter().all(|&n| center.chebyshev_distance(n) == 1));
+        assert!(center.edge_neighbors().all(|n| neighbors.contains(&n)));
+        assert_eq!(neighbors.first(), Some(&Morton3D::from_coords(4, 4, 4)));
+        assert_eq!(neighbors.last(), Some(&Morton3D::from_coords(6, 6, 6)));
+
+        let max = Morton3D::MAX_COORD;
+        assert_eq!(
+            Morton3D::from_coords(max, max, max)
+                .moore_neighbors()
+                .count(),
+            7
+        );
+        assert_eq!(Morton3D::from_coords(0, 3, 3).moore_neighbors().count(), 17);
+    }
+
+    #[test]
+    fn test_neighbors_within() {
+        let center = Morton3D::from_coords(5, 6, 7);
+        let neighbors: Vec<_> = center.neighbors_within(2).collect();
+        assert_eq!(neighbors.len(), 5 * 5 * 5 - 1);
+        assert!(neighbors.windows(2).all(|w| w[0] < w[1]));
+        assert!(neighbors.iter().all(|&n| center.chebyshev_distance(n) <= 2));
+        assert!(!neighbors.contains(&center));
+
+        let mut moore: Vec<_> = center.moore_neighbors().collect();
+        moore.sort();
+        assert_eq!(center.neighbors_within(1).collect::<Vec<_>>(), moore);
+        assert_eq!(center.neighbors_within(0).count(), 0);
+
+        let mut corner = Morton3D::from_coords(1, 0, Morton3D::MAX_COORD);
+        corner.set_flag();
+        let neighbors: Vec<_> = corner.neighbors_within(3).collect();
+        assert_eq!(neighbors.len(), 5 * 4 * 4 - 1);
+        assert!(neighbors.iter().all(|n| n.is_flag_set()));
+
+        // box crossing a high level boundary of the curve
+        let center = Morton3D64::from_coords(1 << 20, (1 << 20) - 1, 1 << 10);
+        let neighbors: Vec<_> = center.neighbors_within(1).collect();
+        assert_eq!(neighbors.len(), 26);
+        assert!(neighbors.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_neighbors_in() {
+        let bounds = MortonBox::new(
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(7, 7, 7),
+        );
+        let edge = Morton3D::from_coords(7, 3, 3);
+        assert_eq!(edge.face_neighbors_in(bounds).count(), 5);
+        assert_eq!(edge.edge_neighbors_in(bounds).count(), 13);
+        assert_eq!(edge.moore_neighbors_in(bounds).count(), 17);
+        assert_eq!(edge.neighbors_within_in(2, bounds).count(), 3 * 5 * 5 - 1);
+        assert!(edge
+            .neighbors_within_in(2, bounds)
+            .all(|n| bounds.contains(n)));
+        assert_eq!(
+            edge.moore_neighbors_in(MortonBox::<usize>::FULL).count(),
+            edge.moore_neighbors().count()
+        );
+
+        // center outside of the bounds
+        let outside = Morton3D::from_coords(9, 3, 3);
+        assert_eq!(outside.face_neighbors_in(bounds).count(), 0);
+        assert_eq!(outside.neighbors_within_in(1, bounds).count(), 0);
+        assert_eq!(outside.neighbors_within_in(2, bounds).count(), 5 * 5);
+    }
+
+    #[test]
+    fn test_dilated_offsets() {
+        let center = Morton3D::from_coords(5, 6, 7);
+        let table: Vec<_> = Morton3D::FACE_OFFSETS
+            .iter()
+            .map(|&offset| center.wrapping_add(offset))
+            .collect();
+        assert_eq!(table, center.face_neighbors().collect::<Vec<_>>());
+        let table: Vec<_> = Morton3D::EDGE_OFFSETS
+            .iter()
+            .map(|&offset| center.wrapping_add(offset))
+            .collect();
+        assert_eq!(table, center.edge_neighbors().collect::<Vec<_>>());
+        let table: Vec<_> = Morton3D16::MOORE_OFFSETS
+            .iter()
+            .map(|&offset| Morton3D16::from_coords(5, 6, 7).wrapping_add(offset))
+            .collect();
+        assert_eq!(
+            table,
+            Morton3D16::from_coords(5, 6, 7)
+                .moore_neighbors()
+                .collect::<Vec<_>>()
+        );
+
+        // wraps around at the edges of the grid
+        let origin = Morton3D::from_coords(0, 0, 0);
+        assert_eq!(
+            origin.wrapping_add(Morton3D::FACE_OFFSETS[0]).to_coords(),
+            (0, 0, Morton3D::MAX_COORD)
+        );
+    }
+
+    #[test]
+    fn test_coarse_neighbors() {
+        let shift = Morton3D::MAX_DEPTH - 2;
+        let cell = Morton3D::from_coords(3 << shift, 1 << shift, (2 << shift) + 5);
+        let mut neighbors: Vec<_> = cell.coarse_neighbors(2).map(Morton3D::to_coords).collect();
+        neighbors.sort();
+        assert_eq!(neighbors.len(), 2 * 3 * 3 - 1);
+        assert!(neighbors.iter().all(|&(x, y, z)| x < 4 && y < 4 && z < 4));
+        assert!(neighbors.contains(&(2, 0, 1)));
+        assert!(!neighbors.contains(&(3, 1, 2)));
+
+        let mut flagged = cell;
+        flagged.set_flag();
+        assert!(flagged.coarse_neighbors(5).all(|n| n.is_flag_set()));
+        assert_eq!(cell.coarse_neighbors(0).count(), 0);
+        assert_eq!(
+            cell.coarse_neighbors(Morton3D::MAX_DEPTH)
+                .collect::<Vec<_>>(),
+            cell.moore_neighbors().collect::<Vec<_>>()
+        );
+    }
+}
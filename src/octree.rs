@@ -0,0 +1,355 @@
+use alloc::collections::{btree_map, BTreeMap};
+use core::iter::{FromIterator, FusedIterator};
+
+use crate::search::distance2;
+use crate::{Morton3D, MortonKey, Region};
+
+/// Pointerless octree storing values at its leaves.
+///
+/// Leaves are addressed by [`MortonKey`]s and kept in preorder, they never overlap each other
+/// but don't have to cover the whole space. Inner cells are implicit, so the tree is navigated by
+/// range lookups on the sorted leaves instead of following child pointers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Octree<T> {
+    leaves: BTreeMap<MortonKey, T>,
+}
+
+impl<T> Default for Octree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Octree<T> {
+    pub fn new() -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+        }
+    }
+
+    /// number of leaves
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// value of the leaf `key`, `None` if `key` isn't a leaf.
+    pub fn get(&self, key: MortonKey) -> Option<&T> {
+        self.leaves.get(&key)
+    }
+    pub fn get_mut(&mut self, key: MortonKey) -> Option<&mut T> {
+        self.leaves.get_mut(&key)
+    }
+
+    /// remove the leaf `key`, return its value.
+    ///
+    /// The space of the leaf isn't covered by any leaf afterwards.
+    pub fn remove(&mut self, key: MortonKey) -> Option<T> {
+        self.leaves.remove(&key)
+    }
+
+    /// first leaf in preorder which is `key` or below it
+    fn first_below(&self, key: MortonKey) -> Option<MortonKey> {
+        self.leaves
+            .range(key..)
+            .next()
+            .map(|(&leaf, _)| leaf)
+            .filter(|&leaf| leaf == key || key.is_ancestor_of(leaf))
+    }
+
+    /// leaf containing `key` (or `key` itself).
+    pub fn leaf_containing(&self, key: MortonKey) -> Option<(MortonKey, &T)> {
+        self.leaves
+            .range(..=key)
+            .next_back()
+            .filter(|(&leaf, _)| leaf == key || leaf.is_ancestor_of(key))
+            .map(|(&leaf, value)| (leaf, value))
+    }
+
+    /// leaf containing the cell `code` at [`MortonKey::MAX_DEPTH`].
+    pub fn leaf_at(&self, code: Morton3D) -> Option<(MortonKey, &T)> {
+        self.leaf_containing(MortonKey::from_leaf(code, MortonKey::MAX_DEPTH))
+    }
+
+    /// leaf closest to the cell `code` at [`MortonKey::MAX_DEPTH`], measured by euclidean distance
+    /// between `code` and the closest cell of the leaf, ties are broken by Z-order.
+    ///
+    /// Subtrees without leaves or farther than the best leaf so far are skipped,
+    /// so only few leaves around `code` are visited.
+    pub fn nearest(&self, code: Morton3D) -> Option<(MortonKey, &T)> {
+        let mut best = None;
+        self.nearest_in(MortonKey::ROOT, code.to_coords(), &mut best);
+        let (_, leaf) = best?;
+        Some((leaf, &self.leaves[&leaf]))
+    }
+
+    fn nearest_in(
+        &self,
+        key: MortonKey,
+        point: (u32, u32, u32),
+        best: &mut Option<(u64, MortonKey)>,
+    ) {
+        let first = match self.first_below(key) {
+            Some(first) => first,
+            None => return,
+        };
+        if first == key {
+            let d = distance2(key, point);
+            if best.is_none_or(|b| (d, key) < b) {
+                *best = Some((d, key));
+            }
+            return;
+        }
+        let mut children = key.children().map(|child| (distance2(child, point), child));
+        children.sort_unstable();
+        for &(d, child) in children.iter() {
+            // subtrees at the same distance may hold a leaf earlier in Z-order
+            if best.is_some_and(|(b, _)| d > b) {
+                break;
+            }
+            self.nearest_in(child, point, best);
+        }
+    }
+
+    /// leaves overlapping `region`, in preorder.
+    pub fn query<R: Region>(&self, region: R) -> OctreeQuery<'_, T, R> {
+        OctreeQuery {
+            octree: self,
+            region,
+            next: Some(MortonKey::ROOT),
+            checked: None,
+        }
+    }
+
+    /// leaves and their values in preorder
+    pub fn iter(&self) -> btree_map::Iter<'_, MortonKey, T> {
+        self.leaves.iter()
+    }
+    /// leaves in preorder
+    pub fn keys(&self) -> btree_map::Keys<'_, MortonKey, T> {
+        self.leaves.keys()
+    }
+}
+
+impl<T: Clone> Octree<T> {
+    /// make `key` a leaf holding `value`, return the previous value of the leaf `key`.
+    ///
+    /// Leaves below `key` are removed. If a coarser leaf contains `key`, it is split
+    /// down to `key` and the new siblings get clones of its value.
+    pub fn insert(&mut self, key: MortonKey, value: T) -> Option<T> {
+        if let Some((coarse, _)) = self.leaf_containing(key).filter(|&(leaf, _)| leaf != key) {
+            let coarse_value = self.leaves.remove(&coarse).unwrap();
+            let mut node = coarse;
+            while node != key {
+                let next = MortonKey::from_leaf(key.anchor(), node.depth() + 1);
+                for &child in node.children().iter().filter(|&&c| c != next) {
+                    self.leaves.insert(child, coarse_value.clone());
+                }
+                node = next;
+            }
+        }
+        while let Some(leaf) = self.first_below(key).filter(|&leaf| leaf != key) {
+            self.leaves.remove(&leaf);
+        }
+        self.leaves.insert(key, value)
+    }
+}
+
+impl<T: Clone> Extend<(MortonKey, T)> for Octree<T> {
+    fn extend<I: IntoIterator<Item = (MortonKey, T)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<T: Clone> FromIterator<(MortonKey, T)> for Octree<T> {
+    fn from_iter<I: IntoIterator<Item = (MortonKey, T)>>(iter: I) -> Self {
+        let mut octree = Self::new();
+        octree.extend(iter);
+        octree
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Octree<T> {
+    type Item = (&'a MortonKey, &'a T);
+    type IntoIter = btree_map::Iter<'a, MortonKey, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the leaves of an [`Octree`] overlapping a region, see [`Octree::query`].
+///
+/// Subtrees outside of the region are skipped as a whole.
+#[derive(Debug, Clone)]
+pub struct OctreeQuery<'a, T, R> {
+    octree: &'a Octree<T>,
+    region: R,
+    /// leaves before this key are done
+    next: Option<MortonKey>,
+    /// cell known to overlap the region, so do its ancestors
+    checked: Option<MortonKey>,
+}
+
+impl<'a, T, R: Region> Iterator for OctreeQuery<'a, T, R> {
+    type Item = (MortonKey, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (&leaf, value) = self.octree.leaves.range(self.next?..).next()?;
+            let unknown = self
+                .checked
+                .map_or(0, |checked| checked.common_ancestor(leaf).depth() + 1);
+            let outside = (unknown..=leaf.depth())
+                .map(|depth| MortonKey::from_leaf(leaf.anchor(), depth))
+                .find(|&cell| !self.region.intersects_key(cell));
+            match outside {
+                Some(cell) => {
+                    self.checked = cell.parent();
+                    self.next = cell.next_subtree(MortonKey::ROOT);
+                }
+                None => {
+                    self.checked = Some(leaf);
+                    self.next = leaf.next_subtree(MortonKey::ROOT);
+                    return Some((leaf, value));
+                }
+            }
+        }
+    }
+}
+
+impl<T, R: Region> FusedIterator for OctreeQuery<'_, T, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Octree;
+    use crate::{Morton3D, MortonBox, MortonKey, Region, Sphere};
+
+    #[test]
+    fn test_insert() {
+        let root = MortonKey::ROOT;
+        let mut octree = Octree::new();
+        assert_eq!(octree.insert(root, 0), None);
+        assert_eq!(octree.len(), 1);
+
+        // splitting the root keeps the rest of the space covered
+        let key = root.child(3).child(5);
+        assert_eq!(octree.insert(key, 1), None);
+        assert_eq!(octree.len(), 7 + 8);
+        assert_eq!(octree.get(root), None);
+        assert_eq!(octree.get(root.child(0)), Some(&0));
+        assert_eq!(octree.get(root.child(3).child(4)), Some(&0));
+        assert_eq!(octree.leaf_containing(key.child(2)), Some((key, &1)));
+        assert_eq!(octree.insert(key, 2), Some(1));
+
+        // coarsening drops the leaves below
+        assert_eq!(octree.insert(root.child(3), 3), None);
+        assert_eq!(octree.len(), 8);
+        assert_eq!(octree.get(key), None);
+        assert!(octree.keys().copied().eq(root.children().iter().copied()));
+
+        assert_eq!(octree.remove(root.child(3)), Some(3));
+        assert_eq!(octree.leaf_containing(key), None);
+        assert_eq!(
+            octree.leaf_at(Morton3D::from_coords(0, 0, 0)),
+            Some((root.child(0), &0))
+        );
+    }
+
+    #[test]
+    fn test_nearest() {
+        let octree: Octree<_> = vec![
+            (
+                MortonKey::from_leaf(Morton3D::from_coords(0, 0, 0), 20),
+                'a',
+            ),
+            (
+                MortonKey::from_leaf(Morton3D::from_coords(100, 0, 0), 21),
+                'b',
+            ),
+            (
+                MortonKey::from_leaf(Morton3D::from_coords(0, 1 << 20, 0), 1),
+                'c',
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let nearest = |x, y, z| {
+            octree
+                .nearest(Morton3D::from_coords(x, y, z))
+                .map(|(_, v)| *v)
+        };
+        assert_eq!(nearest(0, 0, 0), Some('a'));
+        assert_eq!(nearest(60, 0, 0), Some('b'));
+        assert_eq!(nearest(40, 0, 0), Some('a'));
+        assert_eq!(nearest(0, 1 << 19, 0), Some('a'));
+        assert_eq!(nearest(0, (1 << 19) + 10, 0), Some('c'));
+        assert_eq!(nearest(5, 1 << 20, 5), Some('c'));
+        assert_eq!(
+            Octree::<()>::new().nearest(Morton3D::from_coords(0, 0, 0)),
+            None
+        );
+
+        // brute force
+        let points = [
+            (3, 7, 1),
+            (99, 5, 200),
+            (1 << 19, 1 << 19, 1 << 19),
+            (77, 0, 3),
+        ];
+        for &(x, y, z) in points.iter() {
+            let code = Morton3D::from_coords(x, y, z);
+            let expected = octree
+                .keys()
+                .copied()
+                .min_by_key(|&k| (crate::search::distance2(k, (x, y, z)), k));
+            assert_eq!(octree.nearest(code).map(|(k, _)| k), expected);
+        }
+
+        // ties go to the leaf first in Z-order, whatever the visit order
+        let leaf = |x| MortonKey::from_leaf(Morton3D::from_coords(x, 0, 0), MortonKey::MAX_DEPTH);
+        let tied: Octree<_> = vec![(leaf(3), 'a'), (leaf(7), 'b')].into_iter().collect();
+        assert_eq!(
+            tied.nearest(Morton3D::from_coords(5, 0, 0)),
+            Some((leaf(3), &'a'))
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let depth = 3;
+        let mut octree = Octree::new();
+        octree.insert(MortonKey::ROOT, ());
+        // fine leaves around the origin, coarse ones elsewhere
+        octree.insert(
+            MortonKey::from_leaf(Morton3D::from_coords(0, 0, 0), depth),
+            (),
+        );
+        let size = MortonKey::ROOT.child(0).size();
+
+        let region = MortonBox::new(
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(size - 1, 10, 10),
+        );
+        let expected: Vec<_> = octree
+            .keys()
+            .copied()
+            .filter(|&k| region.intersects_key(k))
+            .collect();
+        let got: Vec<_> = octree.query(&region).map(|(k, _)| k).collect();
+        assert_eq!(got, expected);
+        // the leaf at the origin and one sibling along x at each depth
+        assert_eq!(got.len(), 3);
+
+        let sphere = Sphere::new([f64::from(size); 3], 1.0);
+        let got: Vec<_> = octree.query(sphere).map(|(k, _)| k).collect();
+        let mut expected = vec![MortonKey::ROOT.child(0).child(7)];
+        expected.extend(MortonKey::ROOT.children()[1..].iter());
+        assert_eq!(got, expected);
+
+        let far = Sphere::new([-10.0; 3], 1.0);
+        assert_eq!(octree.query(far).count(), 0);
+    }
+}
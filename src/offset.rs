@@ -0,0 +1,64 @@
+use core::ops::Neg;
+
+/// Signed per-axis delta between two cells, used to translate [`MortonND<3, S>`](crate::MortonND).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Offset3D {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Offset3D {
+    /// Offset which doesn't move.
+    pub const ZERO: Self = Self::new(0, 0, 0);
+
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// offset in the opposite direction, `None` if a component is `i32::MIN`.
+    pub const fn checked_neg(self) -> Option<Self> {
+        match (
+            self.x.checked_neg(),
+            self.y.checked_neg(),
+            self.z.checked_neg(),
+        ) {
+            (Some(x), Some(y), Some(z)) => Some(Self::new(x, y, z)),
+            _ => None,
+        }
+    }
+
+    /// offset in the opposite direction, components equal to `i32::MIN` are kept as they are.
+    pub const fn wrapping_neg(self) -> Self {
+        Self::new(
+            self.x.wrapping_neg(),
+            self.y.wrapping_neg(),
+            self.z.wrapping_neg(),
+        )
+    }
+}
+
+/// Like `-` on `i32`, panic in debug and wrap in release if a component is `i32::MIN`,
+/// see [`Offset3D::checked_neg`] and [`Offset3D::wrapping_neg`].
+impl Neg for Offset3D {
+    type Output = Self;
+    fn neg(self) -> Self {
+        debug_assert!(
+            self.checked_neg().is_some(),
+            "attempt to negate with overflow"
+        );
+        self.wrapping_neg()
+    }
+}
+
+impl From<(i32, i32, i32)> for Offset3D {
+    fn from((x, y, z): (i32, i32, i32)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<Offset3D> for (i32, i32, i32) {
+    fn from(v: Offset3D) -> Self {
+        (v.x, v.y, v.z)
+    }
+}
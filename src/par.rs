@@ -0,0 +1,45 @@
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::{Morton3D, Point3D};
+
+/// encode points into codes in parallel.
+pub fn par_encode<P: Point3D + Sync>(points: &[P]) -> Vec<Morton3D> {
+    points.par_iter().map(P::morton).collect()
+}
+
+/// decode codes into coordinates `(x, y, z)` in parallel, the flag is ignored.
+pub fn par_decode(codes: &[Morton3D]) -> Vec<(u32, u32, u32)> {
+    codes.par_iter().map(|v| v.to_coords()).collect()
+}
+
+/// sort points in Z-order in place in parallel, see [`crate::SortByMorton::sort_by_morton`].
+pub fn par_sort_by_morton<P: Point3D + Send>(points: &mut [P]) {
+    points.par_sort_unstable_by_key(P::morton);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{par_decode, par_encode, par_sort_by_morton};
+    use crate::{Point3D, SortByMorton};
+
+    #[test]
+    fn test_par() {
+        let points: Vec<_> = (0..10_000u32)
+            .map(|i| ((i * 37) % 1000, (i * 11) % 999, (i * 5) % 998))
+            .collect();
+        let codes = par_encode(&points);
+        assert!(codes.iter().eq(points
+            .iter()
+            .map(Point3D::morton)
+            .collect::<Vec<_>>()
+            .iter()));
+        assert_eq!(par_decode(&codes), points);
+
+        let (mut a, mut b) = (points.clone(), points);
+        par_sort_by_morton(&mut a);
+        b.sort_by_morton();
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,222 @@
+use core::iter::FusedIterator;
+use core::slice;
+
+use crate::neighbors::{EDGE_OFFSETS, FACE_OFFSETS, MOORE_OFFSETS};
+use crate::{Direction, Morton3D, Offset3D};
+
+/// Grid of `size_x * size_y * size_z` cells with periodic boundaries (3-torus topology),
+/// neighbors of [`Morton3D`] wrap around at the extents of the grid.
+///
+/// Cells passed to the methods must be inside of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeriodicGrid {
+    size_x: u32,
+    size_y: u32,
+    size_z: u32,
+}
+
+impl PeriodicGrid {
+    /// create grid of `size_x * size_y * size_z` cells.
+    ///
+    /// panic if any size is 0 or exceeds `Morton3D::MAX_COORD + 1`.
+    pub fn new(size_x: u32, size_y: u32, size_z: u32) -> Self {
+        let max = Morton3D::MAX_COORD as u64 + 1;
+        assert!(
+            [size_x, size_y, size_z]
+                .iter()
+                .all(|&size| size > 0 && size as u64 <= max),
+            "size must be in 1..={}",
+            max
+        );
+        Self {
+            size_x,
+            size_y,
+            size_z,
+        }
+    }
+
+    pub fn size_x(&self) -> u32 {
+        self.size_x
+    }
+    pub fn size_y(&self) -> u32 {
+        self.size_y
+    }
+    pub fn size_z(&self) -> u32 {
+        self.size_z
+    }
+
+    /// size of n-th dim
+    fn size(&self, n: usize) -> u32 {
+        [self.size_x, self.size_y, self.size_z][n]
+    }
+
+    /// whether the cell is inside of the grid, flag is ignored.
+    pub fn contains(&self, v: Morton3D) -> bool {
+        (0..3).all(|n| v.nth_dim(n) < self.size(n))
+    }
+
+    /// move cell by one step toward `dir`, wrapping around at the extents.
+    pub fn neighbor(&self, v: Morton3D, dir: Direction) -> Morton3D {
+        let n = dir.axis();
+        if dir.is_positive() {
+            v.wrapping_increase_nth_dim_mod(n, self.size(n))
+        } else {
+            v.wrapping_decrease_nth_dim_mod(n, self.size(n))
+        }
+    }
+
+    /// move cell by `offset`, wrapping around at the extents, flag is kept.
+    pub fn translate(&self, v: Morton3D, offset: Offset3D) -> Morton3D {
+        let (x, y, z) = v.to_coords();
+        let wrap = |c: u32, d: i32, n: usize| {
+            let size = self.size(n) as i64;
+            (c as i64 + d as i64).rem_euclid(size) as u32
+        };
+        let mut moved = Morton3D::from_coords(
+            wrap(x, offset.x, 0),
+            wrap(y, offset.y, 1),
+            wrap(z, offset.z, 2),
+        );
+        if v.is_flag_set() {
+            moved.set_flag();
+        }
+        moved
+    }
+
+    /// iterate over the 6 cells sharing a face with `v`.
+    ///
+    /// The same cell can appear more than once on axes of size 1 or 2.
+    pub fn face_neighbors(&self, v: Morton3D) -> PeriodicNeighbors {
+        self.neighbors(v, &FACE_OFFSETS)
+    }
+
+    /// iterate over the 18 cells sharing a face or an edge with `v`.
+    ///
+    /// The same cell can appear more than once on axes of size 1 or 2.
+    pub fn edge_neighbors(&self, v: Morton3D) -> PeriodicNeighbors {
+        self.neighbors(v, &EDGE_OFFSETS)
+    }
+
+    /// iterate over the 26 cells surrounding `v`.
+    ///
+    /// The same cell can appear more than once on axes of size 1 or 2.
+    pub fn moore_neighbors(&self, v: Morton3D) -> PeriodicNeighbors {
+        self.neighbors(v, &MOORE_OFFSETS)
+    }
+
+    fn neighbors(&self, v: Morton3D, offsets: &'static [Offset3D]) -> PeriodicNeighbors {
+        PeriodicNeighbors {
+            grid: *self,
+            center: v,
+            offsets: offsets.iter(),
+        }
+    }
+}
+
+/// Iterator over neighbor cells on a [`PeriodicGrid`].
+///
+/// Flag of the center is kept in every neighbor.
+#[derive(Debug, Clone)]
+pub struct PeriodicNeighbors {
+    grid: PeriodicGrid,
+    center: Morton3D,
+    offsets: slice::Iter<'static, Offset3D>,
+}
+
+impl Iterator for PeriodicNeighbors {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        let offset = self.offsets.next()?;
+        let mut v = self.center;
+        for (n, &d) in [offset.x, offset.y, offset.z].iter().enumerate() {
+            let size = self.grid.size(n);
+            v = match d {
+                1 => v.wrapping_increase_nth_dim_mod(n, size),
+                -1 => v.wrapping_decrease_nth_dim_mod(n, size),
+                _ => v,
+            };
+        }
+        Some(v)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.offsets.size_hint()
+    }
+}
+
+impl ExactSizeIterator for PeriodicNeighbors {}
+
+impl FusedIterator for PeriodicNeighbors {}
+
+#[cfg(test)]
+mod tests {
+    use super::PeriodicGrid;
+    use crate::{Direction, Morton3D, Offset3D};
+
+    #[test]
+    fn test_periodic_neighbors() {
+        let grid = PeriodicGrid::new(10, 4, 7);
+        let corner = Morton3D::from_coords(9, 0, 6);
+        assert!(grid.contains(corner));
+        assert!(!grid.contains(Morton3D::from_coords(10, 0, 0)));
+
+        assert_eq!(
+            grid.neighbor(corner, Direction::PosX).to_coords(),
+            (0, 0, 6)
+        );
+        assert_eq!(
+            grid.neighbor(corner, Direction::NegY).to_coords(),
+            (9, 3, 6)
+        );
+        assert_eq!(
+            grid.neighbor(corner, Direction::PosZ).to_coords(),
+            (9, 0, 0)
+        );
+        assert_eq!(
+            grid.neighbor(corner, Direction::NegX).to_coords(),
+            (8, 0, 6)
+        );
+
+        let mut neighbors: Vec<_> = grid
+            .face_neighbors(corner)
+            .map(Morton3D::to_coords)
+            .collect();
+        neighbors.sort();
+        assert_eq!(
+            neighbors,
+            [
+                (0, 0, 6),
+                (8, 0, 6),
+                (9, 0, 0),
+                (9, 0, 5),
+                (9, 1, 6),
+                (9, 3, 6)
+            ]
+        );
+        assert_eq!(grid.edge_neighbors(corner).len(), 18);
+        assert!(grid.moore_neighbors(corner).all(|n| grid.contains(n)));
+        assert!(grid
+            .moore_neighbors(corner)
+            .any(|n| n.to_coords() == (0, 3, 0)));
+
+        let mut flagged = corner;
+        flagged.set_flag();
+        assert!(grid.moore_neighbors(flagged).all(|n| n.is_flag_set()));
+
+        assert_eq!(
+            grid.translate(corner, Offset3D::new(13, -5, 8)).to_coords(),
+            (2, 3, 0)
+        );
+        for (n, offset) in grid
+            .moore_neighbors(corner)
+            .zip(super::MOORE_OFFSETS.iter())
+        {
+            assert_eq!(n, grid.translate(corner, *offset));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_periodic_empty() {
+        PeriodicGrid::new(4, 0, 4);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{Morton3D, MortonKey, MAX_DEPTH};
+
+/// Maps floating-point points inside an axis-aligned bounding box to [`Morton3D`] and back.
+///
+/// The box is divided into `2^depth` cells along each axis,
+/// a point is quantized to the code of the cell containing it.
+/// Points outside of the box are clamped into it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantizer {
+    min: [f64; 3],
+    max: [f64; 3],
+    depth: u32,
+}
+
+impl Quantizer {
+    /// create quantizer for the box `[min, max]` and `2^depth` cells per axis.
+    ///
+    /// panic if `depth` exceeds max depth or the box is empty on any axis.
+    pub fn new(min: [f64; 3], max: [f64; 3], depth: u32) -> Self {
+        assert!(
+            depth as usize <= MAX_DEPTH,
+            "depth must be less than or equal to {}",
+            MAX_DEPTH
+        );
+        assert!(
+            (0..3).all(|i| min[i] < max[i]),
+            "min must be less than max on every axis"
+        );
+        Self { min, max, depth }
+    }
+
+    pub fn min(&self) -> [f64; 3] {
+        self.min
+    }
+    pub fn max(&self) -> [f64; 3] {
+        self.max
+    }
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// number of cells along each axis
+    pub fn cells_per_axis(&self) -> u32 {
+        1 << self.depth
+    }
+
+    /// size of a cell
+    pub fn cell_size(&self) -> [f64; 3] {
+        let n = f64::from(self.cells_per_axis());
+        [
+            (self.max[0] - self.min[0]) / n,
+            (self.max[1] - self.min[1]) / n,
+            (self.max[2] - self.min[2]) / n,
+        ]
+    }
+
+    /// quantize one axis, NaN is mapped to 0
+    fn quantize_axis(&self, axis: usize, v: f64) -> u32 {
+        let t = (v - self.min[axis]) / (self.max[axis] - self.min[axis]);
+        // `as` saturates negative values and NaN to 0
+        let cell = (t * f64::from(self.cells_per_axis())) as u32;
+        cell.min(self.cells_per_axis() - 1)
+    }
+
+    /// code of the cell containing `p`
+    pub fn quantize(&self, p: [f64; 3]) -> Morton3D {
+        Morton3D::from_coords(
+            self.quantize_axis(0, p[0]),
+            self.quantize_axis(1, p[1]),
+            self.quantize_axis(2, p[2]),
+        )
+    }
+    /// code of the cell containing `p`
+    pub fn quantize_f32(&self, p: [f32; 3]) -> Morton3D {
+        self.quantize([f64::from(p[0]), f64::from(p[1]), f64::from(p[2])])
+    }
+
+    /// center of the cell of `morton`, flag bit is ignored.
+    pub fn cell_center(&self, morton: Morton3D) -> [f64; 3] {
+        let size = self.cell_size();
+        let (x, y, z) = morton.to_coords();
+        [
+            self.min[0] + (f64::from(x) + 0.5) * size[0],
+            self.min[1] + (f64::from(y) + 0.5) * size[1],
+            self.min[2] + (f64::from(z) + 0.5) * size[2],
+        ]
+    }
+    /// center of the cell of `morton`, flag bit is ignored.
+    pub fn cell_center_f32(&self, morton: Morton3D) -> [f32; 3] {
+        let [x, y, z] = self.cell_center(morton);
+        [x as f32, y as f32, z as f32]
+    }
+
+    /// min and max corner of the cell of `key`, which can be at any depth.
+    pub fn key_bounds(&self, key: MortonKey) -> ([f64; 3], [f64; 3]) {
+        let n = f64::from(1u32 << key.depth());
+        let (x, y, z) = key.code().to_coords();
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+        for (axis, &c) in [x, y, z].iter().enumerate() {
+            let size = (self.max[axis] - self.min[axis]) / n;
+            min[axis] = self.min[axis] + f64::from(c) * size;
+            max[axis] = min[axis] + size;
+        }
+        (min, max)
+    }
+
+    /// center of the cell of `key`, which can be at any depth.
+    pub fn key_center(&self, key: MortonKey) -> [f64; 3] {
+        let (min, max) = self.key_bounds(key);
+        [
+            (min[0] + max[0]) / 2.0,
+            (min[1] + max[1]) / 2.0,
+            (min[2] + max[2]) / 2.0,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Quantizer;
+    use crate::{Morton3D, MortonKey};
+
+    #[test]
+    fn test_quantize() {
+        let q = Quantizer::new([0.0, -1.0, 10.0], [8.0, 1.0, 12.0], 3);
+        assert_eq!(q.cells_per_axis(), 8);
+        assert_eq!(q.cell_size(), [1.0, 0.25, 0.25]);
+        assert_eq!(
+            q.quantize([0.0, -1.0, 10.0]),
+            Morton3D::from_coords(0, 0, 0)
+        );
+        assert_eq!(q.quantize([3.5, 0.1, 11.0]), Morton3D::from_coords(3, 4, 4));
+        assert_eq!(
+            q.quantize_f32([7.9, 0.9, 11.9]),
+            Morton3D::from_coords(7, 7, 7)
+        );
+    }
+
+    #[test]
+    fn test_quantize_clamp() {
+        let q = Quantizer::new([0.0; 3], [1.0; 3], 4);
+        assert_eq!(
+            q.quantize([-5.0, 2.0, 1.0]),
+            Morton3D::from_coords(0, 15, 15)
+        );
+        assert_eq!(
+            q.quantize([f64::NAN, 0.0, 0.0]),
+            Morton3D::from_coords(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_cell_center() {
+        let q = Quantizer::new([0.0; 3], [8.0, 16.0, 4.0], 2);
+        assert_eq!(
+            q.cell_center(Morton3D::from_coords(0, 1, 3)),
+            [1.0, 6.0, 3.5]
+        );
+        let p = [5.1, 9.9, 0.3];
+        let center = q.cell_center_f32(q.quantize(p));
+        assert_eq!(q.quantize_f32(center), q.quantize(p));
+    }
+
+    #[test]
+    fn test_key_geometry() {
+        let q = Quantizer::new([0.0; 3], [8.0, 16.0, 4.0], 2);
+        let key = MortonKey::new(Morton3D::from_coords(1, 0, 1), 1);
+        assert_eq!(q.key_bounds(key), ([4.0, 0.0, 2.0], [8.0, 8.0, 4.0]));
+        assert_eq!(q.key_center(key), [6.0, 4.0, 3.0]);
+        assert_eq!(q.key_bounds(MortonKey::ROOT), (q.min(), q.max()));
+
+        let cell = Morton3D::from_coords(0, 1, 3);
+        assert_eq!(q.key_center(MortonKey::new(cell, 2)), q.cell_center(cell));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_deep() {
+        Quantizer::new([0.0; 3], [1.0; 3], 64);
+    }
+}
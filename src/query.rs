@@ -0,0 +1,389 @@
+use core::iter::FusedIterator;
+
+use crate::{Morton3D, MortonBox, MortonND, MortonRange, MortonStorage};
+
+/// Iterator over the codes of a sorted slice inside of a [`MortonBox`], in order.
+///
+/// Instead of scanning the slice linearly, sections of the curve outside of the box are skipped
+/// by binary searching the next code inside of it ([`bigmin`](MortonND::bigmin)).
+#[derive(Debug, Clone)]
+pub struct QueryBox<'a, S: MortonStorage = usize> {
+    sorted: &'a [MortonND<3, S>],
+    bounds: MortonBox<S>,
+}
+
+impl<S: MortonStorage> QueryBox<'_, S> {
+    /// number of codes of the slice not visited yet
+    #[cfg(feature = "alloc")]
+    pub(crate) fn remaining(&self) -> usize {
+        self.sorted.len()
+    }
+}
+
+/// Iterator over the smallest set of contiguous code ranges covering a [`MortonBox`], in order.
+///
+/// Each range is maximal, so that a spatial query can be turned into as few key-range scans as possible.
+#[derive(Debug, Clone)]
+pub struct BoxRanges<S: MortonStorage = usize> {
+    next: Option<MortonND<3, S>>,
+    bounds: MortonBox<S>,
+}
+
+/// Iterator over every cell inside of a [`MortonBox`], in curve order.
+///
+/// Sections of the curve outside of the box are skipped by walking the [`BoxRanges`] of the box.
+#[derive(Debug, Clone)]
+pub struct BoxIter<S: MortonStorage = usize> {
+    ranges: BoxRanges<S>,
+    current: Option<(MortonND<3, S>, MortonND<3, S>)>,
+}
+
+/// Iterator over the cells of a [`MortonBox`] on its boundary, in curve order.
+///
+/// These are the cells with at least one face on the surface of the box,
+/// sections of the curve inside of the hollow part are skipped as a whole.
+#[derive(Debug, Clone)]
+pub struct BoxShell<S: MortonStorage = usize> {
+    next: Option<MortonND<3, S>>,
+    bounds: MortonBox<S>,
+    interior: Option<MortonBox<S>>,
+}
+
+/// Implement box queries for 3-dimension morton codes of storage `$t`.
+macro_rules! query {
+    ($t:ty) => {
+        impl MortonBox<$t> {
+            /// iterate over the codes of `sorted` inside of the box.
+            ///
+            /// `sorted` must be sorted and the codes must not have the flag set.
+            pub fn query(self, sorted: &[MortonND<3, $t>]) -> QueryBox<'_, $t> {
+                let first = sorted.partition_point(|&v| v < self.min());
+                QueryBox {
+                    sorted: &sorted[first..],
+                    bounds: self,
+                }
+            }
+
+            /// iterate over every cell inside of the box, see [`BoxIter`].
+            pub fn iter(self) -> BoxIter<$t> {
+                BoxIter {
+                    ranges: self.ranges(),
+                    current: None,
+                }
+            }
+
+            /// iterate over the cells on the boundary of the box, see [`BoxShell`].
+            pub fn shell(self) -> BoxShell<$t> {
+                BoxShell {
+                    next: Some(self.min()),
+                    bounds: self,
+                    interior: self.interior(),
+                }
+            }
+
+            /// contiguous code ranges covering the box, see [`BoxRanges`].
+            pub fn ranges(self) -> BoxRanges<$t> {
+                BoxRanges {
+                    next: Some(self.min()),
+                    bounds: self,
+                }
+            }
+        }
+
+        impl MortonBox<$t> {
+            /// last code of the largest aligned cell starting at `v` which is inside of the box.
+            fn block_end(self, v: MortonND<3, $t>) -> MortonND<3, $t> {
+                let max_depth = MortonND::<3, $t>::MAX_DEPTH;
+                let mut level = (v.raw().trailing_zeros() / 3).min(max_depth);
+                loop {
+                    let end = MortonND::<3, $t>::from_raw(v.raw() | ((1 << (3 * level)) - 1));
+                    // the cell is inside if both of its corners are
+                    if level == 0 || self.contains(end) {
+                        return end;
+                    }
+                    level -= 1;
+                }
+            }
+
+            /// last code of the contiguous section of the curve inside of the box starting at `v`.
+            fn run_end(self, v: MortonND<3, $t>) -> MortonND<3, $t> {
+                let mut end = v;
+                loop {
+                    end = self.block_end(end);
+                    if end == self.max() {
+                        return end;
+                    }
+                    let after = MortonND::<3, $t>::from_raw(end.raw() + 1);
+                    if !self.contains(after) {
+                        return end;
+                    }
+                    end = after;
+                }
+            }
+
+            /// first code inside of the box after `v`, `None` if there is no more.
+            fn after(self, v: MortonND<3, $t>) -> Option<MortonND<3, $t>> {
+                if v >= self.max() {
+                    return None;
+                }
+                let after = MortonND::<3, $t>::from_raw(v.raw() + 1);
+                if self.contains(after) {
+                    Some(after)
+                } else {
+                    Some(after.bigmin(self.min(), self.max()))
+                }
+            }
+
+            /// box of the cells not touching the boundary, `None` if every cell touches it.
+            fn interior(self) -> Option<Self> {
+                let (min, max) = (self.min().to_coords(), self.max().to_coords());
+                if max.0 - min.0 < 2 || max.1 - min.1 < 2 || max.2 - min.2 < 2 {
+                    return None;
+                }
+                Some(MortonBox::new(
+                    MortonND::<3, $t>::from_coords(min.0 + 1, min.1 + 1, min.2 + 1),
+                    MortonND::<3, $t>::from_coords(max.0 - 1, max.1 - 1, max.2 - 1),
+                ))
+            }
+        }
+
+        impl Iterator for BoxRanges<$t> {
+            type Item = MortonRange<$t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let start = self.next?;
+                let end = self.bounds.run_end(start);
+                self.next = self.bounds.after(end);
+                Some(MortonRange::new(start, end))
+            }
+        }
+
+        impl Iterator for BoxShell<$t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    let v = self.next?;
+                    match self.interior.filter(|interior| interior.contains(v)) {
+                        Some(interior) => self.next = self.bounds.after(interior.run_end(v)),
+                        None => {
+                            self.next = self.bounds.after(v);
+                            return Some(v);
+                        }
+                    }
+                }
+            }
+        }
+
+        impl FusedIterator for BoxShell<$t> {}
+
+        impl FusedIterator for BoxRanges<$t> {}
+
+        impl Iterator for BoxIter<$t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let (v, end) = match self.current {
+                    Some(current) => current,
+                    None => {
+                        let range = self.ranges.next()?;
+                        (range.start(), range.end())
+                    }
+                };
+                self.current = if v == end {
+                    None
+                } else {
+                    Some((MortonND::<3, $t>::from_raw(v.raw() + 1), end))
+                };
+                Some(v)
+            }
+        }
+
+        impl FusedIterator for BoxIter<$t> {}
+
+        impl<'a> Iterator for QueryBox<'a, $t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let (min, max) = (self.bounds.min(), self.bounds.max());
+                loop {
+                    let &v = self.sorted.first()?;
+                    if v > max {
+                        self.sorted = &[];
+                        return None;
+                    }
+                    if self.bounds.contains(v) {
+                        self.sorted = &self.sorted[1..];
+                        return Some(v);
+                    }
+                    let next = v.bigmin(min, max);
+                    let skip = self.sorted.partition_point(|&c| c < next);
+                    self.sorted = &self.sorted[skip..];
+                }
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(self.sorted.len()))
+            }
+        }
+
+        impl<'a> FusedIterator for QueryBox<'a, $t> {}
+    };
+}
+
+query!(u16);
+query!(u32);
+query!(u64);
+query!(u128);
+query!(usize);
+
+/// iterate over the codes of `sorted` inside of the box spanned by the cells `min` and `max`,
+/// see [`MortonBox::query`].
+pub fn query_box(sorted: &[Morton3D], min: Morton3D, max: Morton3D) -> QueryBox<'_> {
+    MortonBox::new(min, max).query(sorted)
+}
+
+/// iterate over every cell whose coordinates are within `min_coords` and `max_coords`, in curve order,
+/// see [`MortonBox::iter`].
+pub fn iter_box(min_coords: (u32, u32, u32), max_coords: (u32, u32, u32)) -> BoxIter {
+    MortonBox::new(Morton3D::from(min_coords), Morton3D::from(max_coords)).iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{iter_box, query_box};
+    use crate::{Morton3D, Morton3D16, MortonBox, MortonRange};
+
+    #[test]
+    fn test_ranges() {
+        let scan = |bounds: MortonBox| {
+            let mut ranges = Vec::new();
+            let mut start = None;
+            for v in (0..1 << 12).map(Morton3D::from) {
+                match (bounds.contains(v), start) {
+                    (true, None) => start = Some(v),
+                    (false, Some(s)) => {
+                        ranges.push(MortonRange::new(s, Morton3D::from(usize::from(v) - 1)));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(s) = start {
+                ranges.push(MortonRange::new(s, Morton3D::from((1 << 12) - 1)));
+            }
+            ranges
+        };
+        for (min, max) in [
+            ((3, 9, 2), (7, 12, 10)),
+            ((0, 0, 0), (15, 15, 15)),
+            ((0, 0, 0), (7, 7, 7)),
+            ((5, 5, 5), (5, 5, 5)),
+            ((1, 0, 0), (14, 15, 15)),
+        ] {
+            let bounds = MortonBox::new(Morton3D::from(min), Morton3D::from(max));
+            assert_eq!(bounds.ranges().collect::<Vec<_>>(), scan(bounds));
+        }
+
+        let full = MortonBox::<usize>::FULL.ranges().collect::<Vec<_>>();
+        assert_eq!(full.len(), 1);
+        assert_eq!(
+            full[0],
+            MortonRange::new(
+                MortonBox::<usize>::FULL.min(),
+                MortonBox::<usize>::FULL.max()
+            )
+        );
+        let bounds = MortonBox::new(
+            Morton3D16::from_coords(0, 0, 0),
+            Morton3D16::from_coords(31, 31, 0),
+        );
+        assert_eq!(bounds.ranges().count(), 32 * 32 / 4);
+    }
+
+    #[test]
+    fn test_iter_box() {
+        let cells: Vec<_> = iter_box((7, 12, 10), (3, 9, 2)).collect();
+        assert_eq!(cells.len(), 5 * 4 * 9);
+        assert!(cells.windows(2).all(|w| w[0] < w[1]));
+        assert!(cells.iter().all(|v| {
+            let (x, y, z) = v.to_coords();
+            (3..=7).contains(&x) && (9..=12).contains(&y) && (2..=10).contains(&z)
+        }));
+
+        assert_eq!(
+            iter_box((4, 5, 6), (4, 5, 6)).collect::<Vec<_>>(),
+            [Morton3D::from_coords(4, 5, 6)]
+        );
+        let bounds = MortonBox::new(
+            Morton3D16::from_coords(30, 0, 0),
+            Morton3D16::from_coords(31, 31, 31),
+        );
+        assert_eq!(bounds.iter().count(), 2 * 32 * 32);
+        assert_eq!(bounds.iter().last(), Some(bounds.max()));
+    }
+
+    #[test]
+    fn test_shell() {
+        let on_boundary = |bounds: MortonBox, v: Morton3D| {
+            let (min, max, v) = (
+                bounds.min().to_coords(),
+                bounds.max().to_coords(),
+                v.to_coords(),
+            );
+            v.0 == min.0
+                || v.0 == max.0
+                || v.1 == min.1
+                || v.1 == max.1
+                || v.2 == min.2
+                || v.2 == max.2
+        };
+        for (min, max) in [
+            ((3, 9, 2), (7, 12, 10)),
+            ((0, 0, 0), (15, 15, 15)),
+            ((4, 4, 4), (5, 9, 9)),
+            ((5, 5, 5), (5, 5, 5)),
+        ] {
+            let bounds = MortonBox::new(Morton3D::from(min), Morton3D::from(max));
+            let expected: Vec<_> = bounds.iter().filter(|&v| on_boundary(bounds, v)).collect();
+            assert_eq!(bounds.shell().collect::<Vec<_>>(), expected);
+        }
+        let bounds = MortonBox::new(
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(15, 15, 15),
+        );
+        assert_eq!(bounds.shell().count(), 16 * 16 * 16 - 14 * 14 * 14);
+    }
+
+    #[test]
+    fn test_query_box() {
+        let mut cells: Vec<_> = (0..16)
+            .flat_map(|x| (0..16).flat_map(move |y| (0..16).map(move |z| (x, y, z))))
+            .filter(|&(x, y, z)| (x + 2 * y + 3 * z) % 5 != 0)
+            .map(Morton3D::from)
+            .collect();
+        cells.sort();
+
+        let min = Morton3D::from_coords(3, 9, 2);
+        let max = Morton3D::from_coords(7, 12, 10);
+        let found: Vec<_> = query_box(&cells, max, min).collect();
+        let expected: Vec<_> = cells
+            .iter()
+            .copied()
+            .filter(|v| {
+                let (x, y, z) = v.to_coords();
+                (3..=7).contains(&x) && (9..=12).contains(&y) && (2..=10).contains(&z)
+            })
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+
+        let outside = Morton3D::from_coords(20, 20, 20);
+        assert_eq!(query_box(&cells, outside, outside).count(), 0);
+        assert_eq!(query_box(&[], min, max).count(), 0);
+
+        let small: Vec<_> = (0..32).map(|x| Morton3D16::from_coords(x, 1, 1)).collect();
+        let mut small = small;
+        small.sort();
+        let bounds = MortonBox::new(
+            Morton3D16::from_coords(4, 0, 0),
+            Morton3D16::from_coords(9, 1, 1),
+        );
+        assert_eq!(bounds.query(&small).count(), 6);
+    }
+}
@@ -0,0 +1,373 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::iter::FusedIterator;
+use core::ops::RangeInclusive;
+
+use crate::{MortonND, MortonStorage};
+
+/// Contiguous section of the curve of 3-dimension cells, given by its inclusive start and end codes.
+///
+/// This is what coverings and query decompositions return, each range being one key-range scan.
+/// Codes are compared as they are, so they should not have the flag set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MortonRange<S: MortonStorage = usize> {
+    start: MortonND<3, S>,
+    end: MortonND<3, S>,
+}
+
+impl<S: MortonStorage> MortonRange<S> {
+    /// create range between two codes, in any order.
+    pub fn new(a: MortonND<3, S>, b: MortonND<3, S>) -> Self {
+        if a <= b {
+            Self { start: a, end: b }
+        } else {
+            Self { start: b, end: a }
+        }
+    }
+
+    pub fn start(self) -> MortonND<3, S> {
+        self.start
+    }
+    pub fn end(self) -> MortonND<3, S> {
+        self.end
+    }
+
+    /// whether the code is inside of the range.
+    pub fn contains(self, v: MortonND<3, S>) -> bool {
+        self.start <= v && v <= self.end
+    }
+
+    /// whether the two ranges share at least one code.
+    pub fn overlaps(self, other: Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl<S: MortonStorage> From<RangeInclusive<MortonND<3, S>>> for MortonRange<S> {
+    fn from(range: RangeInclusive<MortonND<3, S>>) -> Self {
+        let (a, b) = range.into_inner();
+        Self::new(a, b)
+    }
+}
+
+impl<S: MortonStorage> From<MortonRange<S>> for RangeInclusive<MortonND<3, S>> {
+    fn from(range: MortonRange<S>) -> Self {
+        range.start..=range.end
+    }
+}
+
+/// Iterator over the pieces of a [`MortonRange`] cut at the boundaries of the cells at a depth, in order.
+///
+/// Each piece is inside of one octree cell, so a sorted key array can be partitioned recursively
+/// by splitting its range one level deeper each time.
+#[derive(Debug, Clone)]
+pub struct RangeSplit<S: MortonStorage = usize> {
+    rest: Option<MortonRange<S>>,
+    /// low bits of the codes inside of a cell
+    cell_mask: S,
+}
+
+/// Iterator over every k-th code of a [`MortonRange`] along the curve, in order.
+///
+/// Sampling the curve gives a coarse preview of a volume that is refined by smaller strides.
+#[derive(Debug, Clone)]
+pub struct Strided<S: MortonStorage = usize> {
+    next: Option<MortonND<3, S>>,
+    end: MortonND<3, S>,
+    stride: S,
+}
+
+/// Iterator over a [`MortonRange`] as the fewest aligned octree cells covering it exactly, in order.
+///
+/// Items are `(code, depth)` with the code at its depth, as in [`MortonKey::new`](crate::MortonKey::new),
+/// so that whole subtrees can be processed at once.
+#[derive(Debug, Clone)]
+pub struct Blocks<S: MortonStorage = usize> {
+    rest: Option<MortonRange<S>>,
+}
+
+/// Implement [`MortonRange`] operations for storage `$t`.
+macro_rules! morton_range {
+    ($t:ty) => {
+        impl MortonRange<$t> {
+            /// number of codes in the range, never zero.
+            #[allow(clippy::len_without_is_empty)]
+            pub const fn len(self) -> $t {
+                self.end.raw() - self.start.raw() + 1
+            }
+
+            /// code halfway between start and end, rounded toward start.
+            pub const fn midpoint(self) -> MortonND<3, $t> {
+                let (start, end) = (self.start.raw(), self.end.raw());
+                MortonND::<3, $t>::from_raw(start + (end - start) / 2)
+            }
+
+            /// split the range at the boundaries of the cells at `depth`,
+            /// so that each sub-range is inside of one of these cells, see [`RangeSplit`].
+            ///
+            /// Codes are the ones at `MAX_DEPTH`. panic if `depth` exceeds `MAX_DEPTH`.
+            pub fn split_at_level(self, depth: u32) -> RangeSplit<$t> {
+                let max_depth = MortonND::<3, $t>::MAX_DEPTH;
+                assert!(depth <= max_depth, "depth out of range");
+                RangeSplit {
+                    rest: Some(self),
+                    cell_mask: (1 << (3 * (max_depth - depth))) - 1,
+                }
+            }
+
+            /// iterate over every `stride`-th code of the range starting at its start, see [`Strided`].
+            ///
+            /// panic if `stride` is 0.
+            pub fn strided(self, stride: $t) -> Strided<$t> {
+                assert!(stride != 0, "stride must not be 0");
+                Strided {
+                    next: Some(self.start),
+                    end: self.end,
+                    stride,
+                }
+            }
+
+            /// walk the range as maximal aligned octree cells, see [`Blocks`].
+            pub fn blocks(self) -> Blocks<$t> {
+                Blocks { rest: Some(self) }
+            }
+
+            /// union of two ranges, `None` if they neither overlap nor touch.
+            pub fn merge(self, other: Self) -> Option<Self> {
+                let (first, second) = if self <= other {
+                    (self, other)
+                } else {
+                    (other, self)
+                };
+                if first.end.raw().saturating_add(1) < second.start.raw() {
+                    return None;
+                }
+                Some(Self {
+                    start: first.start,
+                    end: first.end.max(second.end),
+                })
+            }
+
+            /// sort ranges and merge the ones overlapping or touching,
+            /// so that they are disjoint, non-adjacent and in order.
+            #[cfg(feature = "alloc")]
+            pub fn normalize(ranges: &mut Vec<Self>) {
+                ranges.sort_unstable();
+                ranges.dedup_by(|next, last| match last.merge(*next) {
+                    Some(merged) => {
+                        *last = merged;
+                        true
+                    }
+                    None => false,
+                });
+            }
+        }
+
+        impl Iterator for RangeSplit<$t> {
+            type Item = MortonRange<$t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let rest = self.rest?;
+                let cell_end = MortonND::<3, $t>::from_raw(rest.start.raw() | self.cell_mask);
+                if cell_end >= rest.end {
+                    self.rest = None;
+                    return Some(rest);
+                }
+                self.rest = Some(MortonRange {
+                    start: MortonND::<3, $t>::from_raw(cell_end.raw() + 1),
+                    end: rest.end,
+                });
+                Some(MortonRange {
+                    start: rest.start,
+                    end: cell_end,
+                })
+            }
+        }
+
+        impl FusedIterator for RangeSplit<$t> {}
+
+        impl Iterator for Strided<$t> {
+            type Item = MortonND<3, $t>;
+            fn next(&mut self) -> Option<Self::Item> {
+                let v = self.next?;
+                self.next = v
+                    .raw()
+                    .checked_add(self.stride)
+                    .filter(|&next| next <= self.end.raw())
+                    .map(MortonND::<3, $t>::from_raw);
+                Some(v)
+            }
+        }
+
+        impl FusedIterator for Strided<$t> {}
+
+        impl Iterator for Blocks<$t> {
+            type Item = (MortonND<3, $t>, u32);
+            fn next(&mut self) -> Option<Self::Item> {
+                let rest = self.rest?;
+                let max_depth = MortonND::<3, $t>::MAX_DEPTH;
+                let (start, end) = (rest.start.raw(), rest.end.raw());
+                // largest cell aligned at start which ends within the range
+                let mut level = (start.trailing_zeros() / 3).min(max_depth);
+                while start | ((1 << (3 * level)) - 1) > end {
+                    level -= 1;
+                }
+                let block_end = start | ((1 << (3 * level)) - 1);
+                self.rest = if block_end == end {
+                    None
+                } else {
+                    Some(MortonRange {
+                        start: MortonND::<3, $t>::from_raw(block_end + 1),
+                        end: rest.end,
+                    })
+                };
+                Some((
+                    MortonND::<3, $t>::from_raw(start >> (3 * level)),
+                    max_depth - level,
+                ))
+            }
+        }
+
+        impl FusedIterator for Blocks<$t> {}
+    };
+}
+
+morton_range!(u16);
+morton_range!(u32);
+morton_range!(u64);
+morton_range!(u128);
+morton_range!(usize);
+
+#[cfg(test)]
+mod tests {
+    use super::MortonRange;
+    use crate::{Morton3D, Morton3D16, MortonKey};
+
+    fn range(a: usize, b: usize) -> MortonRange {
+        MortonRange::new(Morton3D::from(a), Morton3D::from(b))
+    }
+
+    #[test]
+    fn test_range() {
+        let r = range(20, 10);
+        assert_eq!(r.start(), Morton3D::from(10));
+        assert_eq!(r.end(), Morton3D::from(20));
+        assert_eq!(r.len(), 11);
+        assert_eq!(range(5, 5).len(), 1);
+        assert!(r.contains(Morton3D::from(10)) && r.contains(Morton3D::from(20)));
+        assert!(!r.contains(Morton3D::from(21)));
+
+        assert!(r.overlaps(range(20, 30)));
+        assert!(!r.overlaps(range(21, 30)));
+        assert_eq!(r.merge(range(21, 30)), Some(range(10, 30)));
+        assert_eq!(range(0, 9).merge(r), Some(range(0, 20)));
+        assert_eq!(r.merge(range(12, 15)), Some(r));
+        assert_eq!(r.merge(range(22, 30)), None);
+
+        let small = MortonRange::new(
+            Morton3D16::from_coords(0, 0, 0),
+            Morton3D16::from_coords(31, 31, 31),
+        );
+        assert_eq!(small.len(), 1 << 15);
+        assert_eq!(small.merge(small), Some(small));
+        assert_eq!(
+            MortonRange::from(range(3, 4).start()..=range(3, 4).end()),
+            range(3, 4)
+        );
+    }
+
+    #[test]
+    fn test_strided() {
+        let codes: Vec<_> = range(5, 20).strided(4).map(usize::from).collect();
+        assert_eq!(codes, [5, 9, 13, 17]);
+        let codes: Vec<_> = range(5, 21).strided(4).map(usize::from).collect();
+        assert_eq!(codes, [5, 9, 13, 17, 21]);
+        assert_eq!(range(5, 20).strided(1).count(), 16);
+        assert_eq!(range(5, 20).strided(usize::MAX).count(), 1);
+
+        let full = MortonRange::new(Morton3D16::from_raw(0), Morton3D16::from_raw(u16::MAX >> 1));
+        assert_eq!(full.strided(1 << 12).count(), 8);
+    }
+
+    #[test]
+    fn test_blocks() {
+        let max = Morton3D::MAX_DEPTH;
+        let blocks: Vec<_> = range(5, 130).blocks().collect();
+        let mut expected = vec![
+            (Morton3D::from(5), max),
+            (Morton3D::from(6), max),
+            (Morton3D::from(7), max),
+        ];
+        expected.extend((1..8).map(|i| (Morton3D::from(i), max - 1)));
+        expected.push((Morton3D::from(1), max - 2));
+        expected.extend((128..=130).map(|i| (Morton3D::from(i), max)));
+        assert_eq!(blocks, expected);
+
+        // blocks are octree cells covering the range exactly
+        let keys: Vec<_> = blocks
+            .iter()
+            .map(|&(code, depth)| MortonKey::new(code, depth))
+            .collect();
+        let mut next = 5;
+        for key in keys {
+            let cells = key.descendants_range(max);
+            assert_eq!(usize::from(*cells.start()), next);
+            next = usize::from(*cells.end()) + 1;
+        }
+        assert_eq!(next, 131);
+
+        let full = MortonRange::new(Morton3D16::from_raw(0), Morton3D16::from_raw(u16::MAX >> 1));
+        assert_eq!(
+            full.blocks().collect::<Vec<_>>(),
+            [(Morton3D16::from_raw(0), 0)]
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(range(10, 20).midpoint(), Morton3D::from(15));
+        assert_eq!(range(10, 21).midpoint(), Morton3D::from(15));
+        assert_eq!(range(7, 7).midpoint(), Morton3D::from(7));
+
+        let max = Morton3D::MAX_DEPTH;
+        let pieces: Vec<_> = range(5, 130).split_at_level(max - 1).collect();
+        assert_eq!(pieces.len(), 17);
+        assert_eq!(pieces[0], range(5, 7));
+        assert_eq!(pieces[1], range(8, 15));
+        assert_eq!(pieces[16], range(128, 130));
+        assert!(pieces
+            .windows(2)
+            .all(|w| usize::from(w[0].end()) + 1 == usize::from(w[1].start())));
+
+        let pieces: Vec<_> = range(5, 130).split_at_level(max - 2).collect();
+        assert_eq!(pieces, [range(5, 63), range(64, 127), range(128, 130)]);
+        assert_eq!(
+            range(5, 130).split_at_level(0).collect::<Vec<_>>(),
+            [range(5, 130)]
+        );
+        assert_eq!(range(8, 15).split_at_level(max).count(), 8);
+
+        let full = MortonRange::new(Morton3D16::from_raw(0), Morton3D16::from_raw(u16::MAX >> 1));
+        assert_eq!(full.split_at_level(1).count(), 8);
+        assert_eq!(full.midpoint(), Morton3D16::from_raw((1 << 14) - 1));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_normalize() {
+        let mut ranges = vec![
+            range(30, 40),
+            range(0, 3),
+            range(41, 41),
+            range(5, 8),
+            range(2, 4),
+            range(35, 36),
+            range(10, 20),
+        ];
+        MortonRange::<usize>::normalize(&mut ranges);
+        assert_eq!(ranges, [range(0, 8), range(10, 20), range(30, 41)]);
+
+        let mut ranges = Vec::new();
+        MortonRange::<usize>::normalize(&mut ranges);
+        assert!(ranges.is_empty());
+    }
+}
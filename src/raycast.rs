@@ -0,0 +1,207 @@
+use core::iter::FusedIterator;
+
+use crate::{Morton3D, MortonKey};
+
+/// codes of the cells at `depth` a ray passes through, in order along the ray.
+///
+/// The ray starts at `origin` and goes through `origin + t * direction` for `t` in `0..=max_t`.
+/// Positions are in units of cells at `depth`, the grid spanning `[0, 2^depth)` on each axis,
+/// and the parts of the ray outside of the grid are skipped. Cells are visited by 3D-DDA
+/// (Amanatides and Woo), so consecutive cells share a face and a ray crossing an edge or
+/// a corner exactly steps through one of the cells touching it.
+///
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn raycast(origin: [f64; 3], direction: [f64; 3], max_t: f64, depth: u32) -> Raycast {
+    Raycast::new(origin, direction, max_t, depth, false)
+}
+
+/// Iterator over the cells a ray passes through, see [`raycast`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raycast {
+    /// next cell to yield
+    cell: Option<[u32; 3]>,
+    /// -1, 0 or 1 along each axis
+    step: [i32; 3],
+    /// `t` where the ray leaves the current cell along each axis
+    t_max: [f64; 3],
+    /// increase of `t` between cell boundaries along each axis
+    t_delta: [f64; 3],
+    /// `t` where the ray ends or leaves the grid
+    t_end: f64,
+    /// max coordinate of the grid
+    last: u32,
+    /// whether to visit every cell touched at an edge or a corner too
+    supercover: bool,
+    /// cells touched at the last edge or corner crossing, yielded before `cell`
+    pending: [[u32; 3]; 6],
+    pending_len: usize,
+    pending_pos: usize,
+}
+
+impl Raycast {
+    /// see [`raycast`] and [`crate::voxelize_segment`].
+    pub(crate) fn new(
+        origin: [f64; 3],
+        direction: [f64; 3],
+        max_t: f64,
+        depth: u32,
+        supercover: bool,
+    ) -> Self {
+        assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+        let n = f64::from(1u32 << depth);
+        let (mut enter, mut exit) = (0f64, max_t);
+        for axis in 0..3 {
+            let (o, d) = (origin[axis], direction[axis]);
+            if d == 0.0 {
+                if !(0.0..n).contains(&o) {
+                    exit = f64::NEG_INFINITY;
+                }
+            } else {
+                let (t0, t1) = ((0.0 - o) / d, (n - o) / d);
+                enter = enter.max(t0.min(t1));
+                exit = exit.min(t0.max(t1));
+            }
+        }
+        let last = (1u32 << depth) - 1;
+        let mut ray = Self {
+            cell: None,
+            step: [0; 3],
+            t_max: [f64::INFINITY; 3],
+            t_delta: [f64::INFINITY; 3],
+            t_end: exit,
+            last,
+            supercover,
+            pending: [[0; 3]; 6],
+            pending_len: 0,
+            pending_pos: 0,
+        };
+        if enter.is_nan() || exit.is_nan() || enter > exit {
+            return ray;
+        }
+        let mut cell = [0; 3];
+        for axis in 0..3 {
+            let (o, d) = (origin[axis], direction[axis]);
+            // saturates to 0 for tiny negative values from rounding
+            cell[axis] = ((o + d * enter) as u32).min(last);
+            if d > 0.0 {
+                ray.step[axis] = 1;
+                ray.t_max[axis] = (f64::from(cell[axis]) + 1.0 - o) / d;
+                ray.t_delta[axis] = 1.0 / d;
+            } else if d < 0.0 {
+                ray.step[axis] = -1;
+                ray.t_max[axis] = (f64::from(cell[axis]) - o) / d;
+                ray.t_delta[axis] = -1.0 / d;
+            }
+        }
+        ray.cell = Some(cell);
+        ray
+    }
+
+    /// `cell` moved by one step along the axes of `mask`, `None` if it leaves the grid.
+    fn stepped(&self, cell: [u32; 3], mask: usize) -> Option<[u32; 3]> {
+        let mut next = cell;
+        for axis in (0..3).filter(|axis| mask & (1 << axis) != 0) {
+            let v = i64::from(cell[axis]) + i64::from(self.step[axis]);
+            if v < 0 || v > i64::from(self.last) {
+                return None;
+            }
+            next[axis] = v as u32;
+        }
+        Some(next)
+    }
+}
+
+impl Iterator for Raycast {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        if self.pending_pos < self.pending_len {
+            let [x, y, z] = self.pending[self.pending_pos];
+            self.pending_pos += 1;
+            return Some(Morton3D::from_coords(x, y, z));
+        }
+        let cell = self.cell?;
+        let t = self.t_max.iter().copied().fold(f64::INFINITY, f64::min);
+        self.cell = None;
+        if t < f64::INFINITY && t <= self.t_end {
+            // axes crossed at `t`, more than one at an edge or a corner
+            let crossed = (0..3)
+                .filter(|&axis| self.t_max[axis] == t)
+                .fold(0, |mask, axis| mask | (1 << axis));
+            let mask = if self.supercover {
+                self.pending_len = 0;
+                self.pending_pos = 0;
+                for sub in (1..crossed).filter(|&sub| sub & crossed == sub) {
+                    if let Some(touched) = self.stepped(cell, sub) {
+                        self.pending[self.pending_len] = touched;
+                        self.pending_len += 1;
+                    }
+                }
+                crossed
+            } else {
+                // lowest crossed axis only, the others follow on the next steps
+                crossed & crossed.wrapping_neg()
+            };
+            self.cell = self.stepped(cell, mask);
+            for axis in (0..3).filter(|axis| mask & (1 << axis) != 0) {
+                self.t_max[axis] += self.t_delta[axis];
+            }
+        }
+        let [x, y, z] = cell;
+        Some(Morton3D::from_coords(x, y, z))
+    }
+}
+
+impl FusedIterator for Raycast {}
+
+#[cfg(test)]
+mod tests {
+    use super::raycast;
+    use crate::Morton3D;
+
+    fn coords(ray: super::Raycast) -> Vec<(u32, u32, u32)> {
+        ray.map(Morton3D::to_coords).collect()
+    }
+
+    #[test]
+    fn test_axis_aligned() {
+        let ray = raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 3.2, 4);
+        assert_eq!(
+            coords(ray),
+            vec![(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]
+        );
+        let ray = raycast([2.5, 7.5, 1.5], [0.0, -2.0, 0.0], 100.0, 3);
+        assert_eq!(
+            coords(ray),
+            (0..8).rev().map(|y| (2, y, 1)).collect::<Vec<_>>()
+        );
+        // starting outside of the grid
+        let ray = raycast([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0], 6.5, 2);
+        assert_eq!(coords(ray), vec![(0, 0, 0), (1, 0, 0)]);
+        assert_eq!(
+            raycast([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0], 4.0, 2).count(),
+            0
+        );
+        assert_eq!(raycast([0.5, 9.0, 0.5], [1.0, 0.0, 0.0], 4.0, 2).count(), 0);
+        assert_eq!(coords(raycast([1.5; 3], [0.0; 3], 4.0, 2)), vec![(1, 1, 1)]);
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let origin = [0.3, 5.7, 2.2];
+        let direction = [0.9, -0.35, 0.6];
+        let max_t = 12.0;
+        let cells = coords(raycast(origin, direction, max_t, 4));
+        // consecutive cells share a face
+        assert!(cells.windows(2).all(|w| {
+            let (a, b) = (w[0], w[1]);
+            a.0.abs_diff(b.0) + a.1.abs_diff(b.1) + a.2.abs_diff(b.2) == 1
+        }));
+        // sampled points along the ray are in the cells
+        for i in 0..=1000 {
+            let t = max_t * f64::from(i) / 1000.0;
+            let p = [0, 1, 2].map(|a| (origin[a] + t * direction[a]) as u32);
+            assert!(cells.contains(&(p[0], p[1], p[2])));
+        }
+        assert_eq!(cells.first(), Some(&(0, 5, 2)));
+    }
+}
@@ -0,0 +1,435 @@
+use alloc::vec::Vec;
+use core::iter::{self, FromIterator, FusedIterator};
+use core::slice;
+
+use crate::{Morton3D, MortonRange};
+
+/// Set of cells stored as sorted, disjoint and non-adjacent [`MortonRange`]s (run-length encoding).
+///
+/// Memory grows with the number of runs instead of the number of cells,
+/// which suits large mostly-solid regions. The flag of codes is ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RleSet {
+    ranges: Vec<MortonRange>,
+}
+
+/// code without flag
+fn plain(mut code: Morton3D) -> Morton3D {
+    code.unset_flag();
+    code
+}
+
+/// range with the flag of its bounds ignored
+fn plain_range(range: MortonRange) -> MortonRange {
+    MortonRange::new(plain(range.start()), plain(range.end()))
+}
+
+impl RleSet {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// the runs of the set, in order
+    pub fn ranges(&self) -> &[MortonRange] {
+        &self.ranges
+    }
+    /// number of cells
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// index of the first run not ending before `code`
+    fn search(&self, code: Morton3D) -> usize {
+        self.ranges.partition_point(|r| r.end() < code)
+    }
+
+    pub fn contains(&self, code: Morton3D) -> bool {
+        let code = plain(code);
+        self.ranges
+            .get(self.search(code))
+            .is_some_and(|r| r.start() <= code)
+    }
+
+    /// add the cell, return whether it was not in the set.
+    pub fn insert(&mut self, code: Morton3D) -> bool {
+        let code = plain(code);
+        if self.contains(code) {
+            return false;
+        }
+        self.insert_range(MortonRange::new(code, code));
+        true
+    }
+
+    /// remove the cell, return whether it was in the set.
+    pub fn remove(&mut self, code: Morton3D) -> bool {
+        let code = plain(code);
+        if !self.contains(code) {
+            return false;
+        }
+        self.remove_range(MortonRange::new(code, code));
+        true
+    }
+
+    /// add every cell of the range.
+    pub fn insert_range(&mut self, range: MortonRange) {
+        let range = plain_range(range);
+        // runs overlapping or touching range are merged into it
+        let first = self
+            .ranges
+            .partition_point(|r| usize::from(r.end()) + 1 < usize::from(range.start()));
+        let mut merged = range;
+        let mut last = first;
+        while let Some(m) = self.ranges.get(last).and_then(|r| r.merge(merged)) {
+            merged = m;
+            last += 1;
+        }
+        self.ranges.splice(first..last, Some(merged));
+    }
+
+    /// remove every cell of the range.
+    pub fn remove_range(&mut self, range: MortonRange) {
+        let range = plain_range(range);
+        let first = self.search(range.start());
+        let mut last = first;
+        while self.ranges.get(last).is_some_and(|r| r.overlaps(range)) {
+            last += 1;
+        }
+        if first == last {
+            return;
+        }
+        // the parts of the first and the last runs sticking out of range remain
+        let (head, tail) = (self.ranges[first], self.ranges[last - 1]);
+        let mut rest = Vec::with_capacity(2);
+        if head.start() < range.start() {
+            let end = Morton3D::from(usize::from(range.start()) - 1);
+            rest.push(MortonRange::new(head.start(), end));
+        }
+        if tail.end() > range.end() {
+            let start = Morton3D::from(usize::from(range.end()) + 1);
+            rest.push(MortonRange::new(start, tail.end()));
+        }
+        self.ranges.splice(first..last, rest);
+    }
+
+    /// iterate over the cells in curve order.
+    pub fn iter(&self) -> RleIter<'_> {
+        RleIter {
+            ranges: self.ranges.iter(),
+            current: None,
+        }
+    }
+}
+
+/// Set algebra, computed on the runs in one pass over both sets.
+impl RleSet {
+    /// append `range` to the sorted runs, merging it with the last one if they touch
+    fn push(ranges: &mut Vec<MortonRange>, range: MortonRange) {
+        match ranges.last_mut() {
+            Some(last) => match last.merge(range) {
+                Some(merged) => *last = merged,
+                None => ranges.push(range),
+            },
+            None => ranges.push(range),
+        }
+    }
+
+    /// cells in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut ranges = Vec::with_capacity(self.ranges.len() + other.ranges.len());
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        loop {
+            let next = match (a.peek(), b.peek()) {
+                (Some(ra), Some(rb)) if ra.start() <= rb.start() => a.next(),
+                (Some(_), Some(_)) => b.next(),
+                (Some(_), None) => a.next(),
+                (None, Some(_)) => b.next(),
+                (None, None) => break,
+            };
+            Self::push(&mut ranges, *next.unwrap());
+        }
+        Self { ranges }
+    }
+
+    /// cells in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let (start, end) = (a.start().max(b.start()), a.end().min(b.end()));
+            if start <= end {
+                Self::push(&mut ranges, MortonRange::new(start, end));
+            }
+            // the run ending first can't overlap anything else
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// cells in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for &a in &self.ranges {
+            let mut start = usize::from(a.start());
+            let end = usize::from(a.end());
+            // skip the runs of other ending before a
+            while other
+                .ranges
+                .get(j)
+                .is_some_and(|b| usize::from(b.end()) < start)
+            {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(b) = other
+                .ranges
+                .get(k)
+                .filter(|b| usize::from(b.start()) <= end)
+            {
+                if usize::from(b.start()) > start {
+                    let piece_end = Morton3D::from(usize::from(b.start()) - 1);
+                    ranges.push(MortonRange::new(Morton3D::from(start), piece_end));
+                }
+                start = usize::from(b.end()) + 1;
+                if start > end {
+                    break;
+                }
+                k += 1;
+            }
+            if start <= end {
+                ranges.push(MortonRange::new(Morton3D::from(start), a.end()));
+            }
+        }
+        Self { ranges }
+    }
+
+    /// start and one past the end of each run, in order
+    fn bounds(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ranges.iter().flat_map(|r| {
+            iter::once(usize::from(r.start())).chain(iter::once(usize::from(r.end()) + 1))
+        })
+    }
+
+    /// cells in exactly one of the sets.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        // a cell is in exactly one set when an odd number of bounds of both sets are not after it,
+        // so the runs go from each even bound to the next one, taking the bounds of both in order
+        let (mut a, mut b) = (self.bounds().peekable(), other.bounds().peekable());
+        let mut bounds = iter::from_fn(|| match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if x <= y => a.next(),
+            (Some(_), Some(_)) => b.next(),
+            _ => a.next().or_else(|| b.next()),
+        });
+        let mut ranges = Vec::new();
+        while let (Some(start), Some(end)) = (bounds.next(), bounds.next()) {
+            if start < end {
+                let range = MortonRange::new(Morton3D::from(start), Morton3D::from(end - 1));
+                Self::push(&mut ranges, range);
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl FromIterator<MortonRange> for RleSet {
+    fn from_iter<I: IntoIterator<Item = MortonRange>>(iter: I) -> Self {
+        let mut ranges: Vec<_> = iter.into_iter().map(plain_range).collect();
+        MortonRange::<usize>::normalize(&mut ranges);
+        Self { ranges }
+    }
+}
+
+impl FromIterator<Morton3D> for RleSet {
+    fn from_iter<I: IntoIterator<Item = Morton3D>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|code| MortonRange::new(plain(code), plain(code)))
+            .collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a RleSet {
+    type Item = Morton3D;
+    type IntoIter = RleIter<'a>;
+    fn into_iter(self) -> RleIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the cells of a [`RleSet`], in curve order.
+#[derive(Debug, Clone)]
+pub struct RleIter<'a> {
+    ranges: slice::Iter<'a, MortonRange>,
+    current: Option<(Morton3D, Morton3D)>,
+}
+
+impl Iterator for RleIter<'_> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        let (v, end) = match self.current {
+            Some(current) => current,
+            None => {
+                let range = self.ranges.next()?;
+                (range.start(), range.end())
+            }
+        };
+        self.current = if v == end {
+            None
+        } else {
+            Some((Morton3D::from(usize::from(v) + 1), end))
+        };
+        Some(v)
+    }
+}
+
+impl FusedIterator for RleIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use super::RleSet;
+    use crate::{Morton3D, MortonRange};
+
+    fn range(a: usize, b: usize) -> MortonRange {
+        MortonRange::new(Morton3D::from(a), Morton3D::from(b))
+    }
+
+    #[test]
+    fn test_rle() {
+        let mut set: RleSet = vec![range(10, 20), range(30, 40)].into_iter().collect();
+        assert_eq!(set.len(), 22);
+        assert!(set.contains(Morton3D::from(10)) && set.contains(Morton3D::from(40)));
+        assert!(!set.contains(Morton3D::from(25)));
+
+        assert!(set.insert(Morton3D::from(21)));
+        assert!(!set.insert(Morton3D::from(21)));
+        assert_eq!(set.ranges(), [range(10, 21), range(30, 40)]);
+        set.insert_range(range(22, 29));
+        assert_eq!(set.ranges(), [range(10, 40)]);
+
+        assert!(set.remove(Morton3D::from(15)));
+        assert!(!set.remove(Morton3D::from(15)));
+        assert_eq!(set.ranges(), [range(10, 14), range(16, 40)]);
+        set.remove_range(range(0, 10));
+        set.remove_range(range(20, 25));
+        assert_eq!(set.ranges(), [range(11, 14), range(16, 19), range(26, 40)]);
+        set.remove_range(range(12, 30));
+        assert_eq!(set.ranges(), [range(11, 11), range(31, 40)]);
+        set.insert_range(range(0, 100));
+        assert_eq!(set.ranges(), [range(0, 100)]);
+        set.remove_range(range(0, 100));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_rle_flagged_ranges() {
+        let flagged = |v: usize| {
+            let mut code = Morton3D::from(v);
+            code.set_flag();
+            code
+        };
+        let mut set: RleSet = vec![MortonRange::new(flagged(10), flagged(20))]
+            .into_iter()
+            .collect();
+        assert_eq!(set.ranges(), [range(10, 20)]);
+        assert!(set.contains(Morton3D::from(15)));
+
+        set.insert_range(MortonRange::new(Morton3D::from(30), flagged(25)));
+        assert_eq!(set.ranges(), [range(10, 20), range(25, 30)]);
+        set.insert_range(MortonRange::new(flagged(21), flagged(24)));
+        assert_eq!(set.ranges(), [range(10, 30)]);
+        set.remove_range(MortonRange::new(flagged(12), flagged(28)));
+        assert_eq!(set.ranges(), [range(10, 11), range(29, 30)]);
+        assert!(!set.contains(flagged(20)));
+    }
+
+    #[test]
+    fn test_rle_algebra() {
+        let a: RleSet = vec![range(0, 10), range(20, 30), range(40, 40), range(50, 60)]
+            .into_iter()
+            .collect();
+        let b: RleSet = vec![range(5, 22), range(25, 26), range(41, 45), range(60, 70)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            a.union(&b).ranges(),
+            [range(0, 30), range(40, 45), range(50, 70)]
+        );
+        assert_eq!(
+            a.intersection(&b).ranges(),
+            [range(5, 10), range(20, 22), range(25, 26), range(60, 60)]
+        );
+        assert_eq!(
+            a.difference(&b).ranges(),
+            [
+                range(0, 4),
+                range(23, 24),
+                range(27, 30),
+                range(40, 40),
+                range(50, 59)
+            ]
+        );
+        assert_eq!(
+            b.difference(&a).ranges(),
+            [range(11, 19), range(41, 45), range(61, 70)]
+        );
+
+        let set_a: BTreeSet<_> = a.iter().collect();
+        let set_b: BTreeSet<_> = b.iter().collect();
+        let xor: Vec<_> = set_a.symmetric_difference(&set_b).copied().collect();
+        assert_eq!(a.symmetric_difference(&b).iter().collect::<Vec<_>>(), xor);
+        assert_eq!(
+            a.symmetric_difference(&b).ranges(),
+            [
+                range(0, 4),
+                range(11, 19),
+                range(23, 24),
+                range(27, 30),
+                range(40, 45),
+                range(50, 59),
+                range(61, 70)
+            ]
+        );
+        assert!(a.symmetric_difference(&a).is_empty());
+        assert_eq!(a.symmetric_difference(&RleSet::new()), a);
+        assert_eq!(RleSet::new().symmetric_difference(&b), b);
+        assert!(a.difference(&a).is_empty());
+        assert_eq!(a.union(&RleSet::new()), a);
+        assert!(a.intersection(&RleSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_rle_cells() {
+        let mut set = RleSet::new();
+        let mut expected = BTreeSet::new();
+        let mut state = 7usize;
+        for i in 0..500 {
+            state = (state * 1103515245 + 12345) % (1 << 16);
+            let code = Morton3D::from(state % 300);
+            if i % 3 == 0 {
+                assert_eq!(set.remove(code), expected.remove(&code));
+            } else {
+                assert_eq!(set.insert(code), expected.insert(code));
+            }
+        }
+        assert_eq!(set.len(), expected.len());
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            expected.iter().copied().collect::<Vec<_>>()
+        );
+        assert!(set.ranges().windows(2).all(|w| w[0].merge(w[1]).is_none()));
+
+        let collected: RleSet = expected.iter().copied().collect();
+        assert_eq!(collected, set);
+    }
+}
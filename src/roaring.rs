@@ -0,0 +1,574 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::iter::{FromIterator, FusedIterator};
+use core::slice;
+
+use crate::Morton3D;
+
+/// Max number of values of an array container, above it a bitmap is smaller.
+const ARRAY_MAX: usize = 4096;
+
+/// Values of one block of `2^16` codes.
+#[derive(Debug, Clone)]
+enum Container {
+    /// sorted values
+    Array(Vec<u16>),
+    /// bit `v` is set for each value `v`
+    Bitmap(Box<[u64; 1024]>),
+    /// sorted disjoint runs as `(start, length - 1)`
+    Run(Vec<(u16, u16)>),
+}
+
+impl Container {
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(words) => words.iter().map(|w| w.count_ones() as usize).sum(),
+            Container::Run(runs) => runs.iter().map(|&(_, n)| n as usize + 1).sum(),
+        }
+    }
+
+    fn contains(&self, v: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&v).is_ok(),
+            Container::Bitmap(words) => words[v as usize / 64] >> (v % 64) & 1 == 1,
+            Container::Run(runs) => {
+                let i = runs.partition_point(|&(start, _)| start <= v);
+                i > 0 && v - runs[i - 1].0 <= runs[i - 1].1
+            }
+        }
+    }
+
+    fn iter(&self) -> ContainerIter<'_> {
+        match self {
+            Container::Array(values) => ContainerIter::Array(values.iter()),
+            Container::Bitmap(words) => ContainerIter::Bitmap {
+                words: &words[..],
+                index: 0,
+                rest: words[0],
+            },
+            Container::Run(runs) => ContainerIter::Run {
+                runs: runs.iter(),
+                current: None,
+            },
+        }
+    }
+
+    /// array or bitmap holding the values, whichever is smaller
+    fn from_values(len: usize, values: impl Iterator<Item = u16>) -> Self {
+        if len <= ARRAY_MAX {
+            Container::Array(values.collect())
+        } else {
+            let mut words = Box::new([0u64; 1024]);
+            for v in values {
+                words[v as usize / 64] |= 1 << (v % 64);
+            }
+            Container::Bitmap(words)
+        }
+    }
+
+    /// values as a bitmap
+    fn words(&self) -> Box<[u64; 1024]> {
+        match self {
+            Container::Bitmap(words) => words.clone(),
+            _ => {
+                let mut words = Box::new([0u64; 1024]);
+                for v in self.iter() {
+                    words[v as usize / 64] |= 1 << (v % 64);
+                }
+                words
+            }
+        }
+    }
+
+    /// array or bitmap holding the bits of `words`, `None` if there is none
+    fn from_words(words: Box<[u64; 1024]>) -> Option<Self> {
+        let len: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+        match len {
+            0 => None,
+            len if len <= ARRAY_MAX => {
+                let container = Container::Bitmap(words);
+                Some(Self::from_values(len, container.iter()))
+            }
+            _ => Some(Container::Bitmap(words)),
+        }
+    }
+
+    /// make the container mutable, runs are expanded to an array or a bitmap
+    fn expand(&mut self) {
+        if let Container::Run(_) = self {
+            *self = Self::from_values(self.len(), self.iter());
+        }
+    }
+
+    fn insert(&mut self, v: u16) -> bool {
+        self.expand();
+        match self {
+            Container::Array(values) => match values.binary_search(&v) {
+                Ok(_) => false,
+                Err(i) => {
+                    values.insert(i, v);
+                    if values.len() > ARRAY_MAX {
+                        *self = Self::from_values(ARRAY_MAX + 1, self.iter());
+                    }
+                    true
+                }
+            },
+            Container::Bitmap(words) => {
+                let (word, bit) = (&mut words[v as usize / 64], 1 << (v % 64));
+                let inserted = *word & bit == 0;
+                *word |= bit;
+                inserted
+            }
+            Container::Run(_) => unreachable!("runs are expanded"),
+        }
+    }
+
+    fn remove(&mut self, v: u16) -> bool {
+        self.expand();
+        match self {
+            Container::Array(values) => match values.binary_search(&v) {
+                Ok(i) => {
+                    values.remove(i);
+                    true
+                }
+                Err(_) => false,
+            },
+            Container::Bitmap(words) => {
+                let (word, bit) = (&mut words[v as usize / 64], 1 << (v % 64));
+                let removed = *word & bit != 0;
+                *word &= !bit;
+                let len = self.len();
+                if len <= ARRAY_MAX {
+                    *self = Self::from_values(len, self.iter());
+                }
+                removed
+            }
+            Container::Run(_) => unreachable!("runs are expanded"),
+        }
+    }
+
+    /// switch to the smallest representation
+    fn optimize(&mut self) {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for v in self.iter() {
+            match runs.last_mut() {
+                Some((start, n)) if *start as u32 + *n as u32 + 1 == v as u32 => *n += 1,
+                _ => runs.push((v, 0)),
+            }
+        }
+        // sizes in bytes
+        let len = self.len();
+        let (run_size, array_size, bitmap_size) = (4 * runs.len(), 2 * len, 8 * 1024);
+        if run_size < array_size.min(bitmap_size) {
+            *self = Container::Run(runs);
+        } else {
+            *self = Self::from_values(len, self.iter());
+        }
+    }
+}
+
+/// Iterator over the values of a [`Container`], in order.
+#[derive(Debug, Clone)]
+enum ContainerIter<'a> {
+    Array(slice::Iter<'a, u16>),
+    Bitmap {
+        words: &'a [u64],
+        index: usize,
+        /// bits of the current word not visited yet
+        rest: u64,
+    },
+    Run {
+        runs: slice::Iter<'a, (u16, u16)>,
+        /// next value and last value of the current run
+        current: Option<(u16, u16)>,
+    },
+}
+
+impl Iterator for ContainerIter<'_> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        match self {
+            ContainerIter::Array(values) => values.next().copied(),
+            ContainerIter::Bitmap { words, index, rest } => {
+                while *rest == 0 {
+                    *index += 1;
+                    *rest = *words.get(*index)?;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                *rest &= *rest - 1;
+                Some((*index * 64 + bit) as u16)
+            }
+            ContainerIter::Run { runs, current } => {
+                let (v, last) = match *current {
+                    Some(current) => current,
+                    None => {
+                        let &(start, n) = runs.next()?;
+                        (start, start + n)
+                    }
+                };
+                *current = if v == last { None } else { Some((v + 1, last)) };
+                Some(v)
+            }
+        }
+    }
+}
+
+/// Compressed set of cells, partitioning the codes by their upper bits into blocks of `2^16` codes.
+///
+/// As in roaring bitmaps, each block is stored in the smallest of a sorted array (sparse blocks),
+/// a bitmap (dense blocks) or a list of runs (see [`optimize`](Self::optimize)),
+/// so hundreds of millions of cells fit in modest memory. The flag of codes is ignored.
+///
+/// Sets are equal if they have the same cells, whatever the representation of their blocks.
+#[derive(Debug, Clone, Default)]
+pub struct CompressedSet {
+    /// sorted by upper bits, blocks are never empty
+    blocks: Vec<(usize, Container)>,
+}
+
+impl PartialEq for CompressedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.blocks.len() == other.blocks.len()
+            && self
+                .blocks
+                .iter()
+                .zip(other.blocks.iter())
+                .all(|((ha, ca), (hb, cb))| ha == hb && ca.iter().eq(cb.iter()))
+    }
+}
+
+impl Eq for CompressedSet {}
+
+impl Hash for CompressedSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.blocks.len().hash(state);
+        for (high, container) in &self.blocks {
+            high.hash(state);
+            container.len().hash(state);
+            for v in container.iter() {
+                v.hash(state);
+            }
+        }
+    }
+}
+
+/// upper and lower bits of the code without flag
+fn split(code: Morton3D) -> (usize, u16) {
+    let mut code = code;
+    code.unset_flag();
+    let bits = usize::from(code);
+    (bits >> 16, bits as u16)
+}
+
+impl CompressedSet {
+    pub fn new() -> Self {
+        Self { blocks: Vec::new() }
+    }
+
+    /// number of cells
+    pub fn len(&self) -> usize {
+        self.blocks.iter().map(|(_, c)| c.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    fn block(&self, high: usize) -> Result<usize, usize> {
+        self.blocks.binary_search_by_key(&high, |&(h, _)| h)
+    }
+
+    pub fn contains(&self, code: Morton3D) -> bool {
+        let (high, low) = split(code);
+        match self.block(high) {
+            Ok(i) => self.blocks[i].1.contains(low),
+            Err(_) => false,
+        }
+    }
+
+    /// add the cell, return whether it was not in the set.
+    pub fn insert(&mut self, code: Morton3D) -> bool {
+        let (high, low) = split(code);
+        let i = match self.block(high) {
+            Ok(i) => i,
+            Err(i) => {
+                self.blocks.insert(i, (high, Container::Array(Vec::new())));
+                i
+            }
+        };
+        self.blocks[i].1.insert(low)
+    }
+
+    /// remove the cell, return whether it was in the set.
+    pub fn remove(&mut self, code: Morton3D) -> bool {
+        let (high, low) = split(code);
+        let i = match self.block(high) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        let removed = self.blocks[i].1.remove(low);
+        if self.blocks[i].1.len() == 0 {
+            self.blocks.remove(i);
+        }
+        removed
+    }
+
+    /// store every block in its smallest representation, turning long runs of cells into run lists.
+    ///
+    /// Blocks stored as runs are expanded again when modified.
+    pub fn optimize(&mut self) {
+        for (_, container) in &mut self.blocks {
+            container.optimize();
+        }
+    }
+
+    /// iterate over the cells in curve order.
+    pub fn iter(&self) -> CompressedIter<'_> {
+        CompressedIter {
+            blocks: self.blocks.iter(),
+            current: None,
+        }
+    }
+}
+
+/// Set algebra, blocks present in only one of the sets are taken or dropped as a whole
+/// and the others are combined 64 cells at a time.
+impl CompressedSet {
+    /// merge the blocks of two sets, the ones present in only one of them are kept if `keep` says so
+    fn combine(&self, other: &Self, keep: (bool, bool), op: fn(u64, u64) -> u64) -> Self {
+        let mut blocks = Vec::new();
+        let (mut a, mut b) = (
+            self.blocks.iter().peekable(),
+            other.blocks.iter().peekable(),
+        );
+        loop {
+            match (a.peek(), b.peek()) {
+                (Some((ha, _)), Some((hb, _))) if ha == hb => {
+                    let (high, ca) = a.next().unwrap();
+                    let (_, cb) = b.next().unwrap();
+                    let (mut wa, wb) = (ca.words(), cb.words());
+                    for (x, &y) in wa.iter_mut().zip(wb.iter()) {
+                        *x = op(*x, y);
+                    }
+                    if let Some(container) = Container::from_words(wa) {
+                        blocks.push((*high, container));
+                    }
+                }
+                (Some((ha, _)), Some((hb, _))) if ha < hb => {
+                    let block = a.next().unwrap();
+                    if keep.0 {
+                        blocks.push(block.clone());
+                    }
+                }
+                (Some(_), Some(_)) | (None, Some(_)) => {
+                    let block = b.next().unwrap();
+                    if keep.1 {
+                        blocks.push(block.clone());
+                    }
+                }
+                (Some(_), None) => {
+                    let block = a.next().unwrap();
+                    if keep.0 {
+                        blocks.push(block.clone());
+                    }
+                }
+                (None, None) => break,
+            }
+        }
+        Self { blocks }
+    }
+
+    /// cells in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, (true, true), |a, b| a | b)
+    }
+    /// cells in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, (false, false), |a, b| a & b)
+    }
+    /// cells in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, (true, false), |a, b| a & !b)
+    }
+    /// cells in exactly one of the sets.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, (true, true), |a, b| a ^ b)
+    }
+}
+
+impl FromIterator<Morton3D> for CompressedSet {
+    fn from_iter<I: IntoIterator<Item = Morton3D>>(iter: I) -> Self {
+        let mut codes: Vec<_> = iter.into_iter().map(split).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        let mut blocks = Vec::new();
+        for chunk in codes.chunk_by(|a, b| a.0 == b.0) {
+            let values = chunk.iter().map(|&(_, low)| low);
+            blocks.push((chunk[0].0, Container::from_values(chunk.len(), values)));
+        }
+        Self { blocks }
+    }
+}
+
+impl<'a> IntoIterator for &'a CompressedSet {
+    type Item = Morton3D;
+    type IntoIter = CompressedIter<'a>;
+    fn into_iter(self) -> CompressedIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the cells of a [`CompressedSet`], in curve order.
+#[derive(Debug, Clone)]
+pub struct CompressedIter<'a> {
+    blocks: slice::Iter<'a, (usize, Container)>,
+    current: Option<(usize, ContainerIter<'a>)>,
+}
+
+impl Iterator for CompressedIter<'_> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        loop {
+            if let Some((high, values)) = &mut self.current {
+                if let Some(low) = values.next() {
+                    return Some(Morton3D::from(*high << 16 | low as usize));
+                }
+            }
+            let (high, container) = self.blocks.next()?;
+            self.current = Some((*high, container.iter()));
+        }
+    }
+}
+
+impl FusedIterator for CompressedIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use super::{CompressedSet, Container};
+    use crate::Morton3D;
+
+    #[test]
+    fn test_compressed() {
+        let mut set = CompressedSet::new();
+        let mut expected = BTreeSet::new();
+        // a sparse block, a dense block and a solid run
+        let codes = (0..3000usize)
+            .map(|i| i * 37 % 65536)
+            .chain((0..10000).map(|i| (1 << 16) + i * 5))
+            .chain((0..30000).map(|i| (5 << 16) + 1000 + i))
+            .map(Morton3D::from);
+        for code in codes {
+            assert_eq!(set.insert(code), expected.insert(code));
+        }
+        assert!(!set.insert(Morton3D::from(37)));
+        assert_eq!(set.len(), expected.len());
+        let kinds: Vec<_> = set
+            .blocks
+            .iter()
+            .map(|(_, c)| core::mem::discriminant(c))
+            .collect();
+        assert_eq!(
+            kinds[0],
+            core::mem::discriminant(&Container::Array(Vec::new()))
+        );
+        assert_ne!(kinds[1], kinds[0]);
+
+        set.optimize();
+        assert!(matches!(set.blocks[2].1, Container::Run(ref runs) if runs.len() == 1));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            expected.iter().copied().collect::<Vec<_>>()
+        );
+
+        for code in expected.iter().copied().step_by(3).collect::<Vec<_>>() {
+            assert!(set.remove(code));
+            assert!(!set.contains(code));
+            expected.remove(&code);
+        }
+        assert!(!set.remove(Morton3D::from(9 << 16)));
+        assert_eq!(set.len(), expected.len());
+        assert!(expected.iter().all(|&code| set.contains(code)));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            expected.iter().copied().collect::<Vec<_>>()
+        );
+
+        let collected: CompressedSet = expected.iter().copied().collect();
+        assert_eq!(
+            collected.iter().collect::<Vec<_>>(),
+            set.iter().collect::<Vec<_>>()
+        );
+        let mut flagged = Morton3D::from(12);
+        flagged.set_flag();
+        let small: CompressedSet = vec![flagged, Morton3D::from(12)].into_iter().collect();
+        assert_eq!(small.len(), 1);
+    }
+
+    #[test]
+    fn test_compressed_eq() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |set: &CompressedSet| {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let a: CompressedSet = (0..30000usize)
+            .map(|i| (3 << 16) + i)
+            .chain((0..10).map(|i| i * 7))
+            .map(Morton3D::from)
+            .collect();
+        let mut b = a.clone();
+        b.optimize();
+        assert!(matches!(b.blocks[1].1, Container::Run(_)));
+        assert!(!matches!(a.blocks[1].1, Container::Run(_)));
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+
+        let mut c = b.clone();
+        c.remove(Morton3D::from(7));
+        assert_ne!(a, c);
+        c.insert(Morton3D::from(7));
+        assert_eq!(a, c);
+        assert_ne!(a, CompressedSet::new());
+    }
+
+    #[test]
+    fn test_compressed_algebra() {
+        let a: CompressedSet = (0..20000usize)
+            .map(|i| i * 3)
+            .chain((0..100).map(|i| (7 << 16) + i))
+            .map(Morton3D::from)
+            .collect();
+        let mut b: CompressedSet = (0..20000usize)
+            .map(|i| i * 2 + 5000)
+            .chain((0..100).map(|i| (9 << 16) + i))
+            .map(Morton3D::from)
+            .collect();
+        b.optimize();
+        let set_a: BTreeSet<_> = a.iter().collect();
+        let set_b: BTreeSet<_> = b.iter().collect();
+        let check = |result: CompressedSet, expected: Vec<Morton3D>| {
+            assert_eq!(result.len(), expected.len());
+            assert_eq!(result.iter().collect::<Vec<_>>(), expected);
+        };
+        check(a.union(&b), set_a.union(&set_b).copied().collect());
+        check(
+            a.intersection(&b),
+            set_a.intersection(&set_b).copied().collect(),
+        );
+        check(
+            a.difference(&b),
+            set_a.difference(&set_b).copied().collect(),
+        );
+        check(
+            a.symmetric_difference(&b),
+            set_a.symmetric_difference(&set_b).copied().collect(),
+        );
+        assert!(a.difference(&a).is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BinaryHeap, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::cmp::Reverse;
+use core::iter::FusedIterator;
+use core::ops::Range;
+use core::slice;
+
+use crate::{Morton3D, MortonKey, RegionRanges, Sphere};
+
+/// squared distance between the cell `(x, y, z)` at `MAX_DEPTH` and the closest cell inside of `key`.
+pub(crate) fn distance2(key: MortonKey, (x, y, z): (u32, u32, u32)) -> u64 {
+    let (lx, ly, lz) = key.anchor().to_coords();
+    let size = key.size();
+    [(x, lx), (y, ly), (z, lz)]
+        .iter()
+        .map(|&(v, lo)| {
+            let gap = if v < lo {
+                lo - v
+            } else {
+                v.saturating_sub(lo + (size - 1))
+            };
+            u64::from(gap) * u64::from(gap)
+        })
+        .sum()
+}
+
+/// codes `start..end` of `sorted` in each child of the cell whose codes are `sorted[start..end]`
+fn split_children(
+    sorted: &[Morton3D],
+    key: MortonKey,
+    start: usize,
+    end: usize,
+) -> [(MortonKey, Range<usize>); 8] {
+    let mut first = start;
+    key.children().map(|child| {
+        let last = *child.descendants_range(MortonKey::MAX_DEPTH).end();
+        let next = first + sorted[first..end].partition_point(|&v| v <= last);
+        let range = first..next;
+        first = next;
+        (child, range)
+    })
+}
+
+/// code of `sorted` closest to `query` by euclidean distance, ties are broken by the smaller code.
+/// `None` if `sorted` is empty.
+///
+/// The codes next to `query` along the curve give a first guess, then the implicit octree over
+/// `sorted` is searched within that distance, so cells across octant boundaries
+/// (close in space but far along the curve) are found too.
+///
+/// `sorted` must be sorted and the codes must not have the flag set.
+pub fn nearest(sorted: &[Morton3D], query: Morton3D) -> Option<Morton3D> {
+    let coords = query.to_coords();
+    let i = sorted.partition_point(|&v| v < query);
+    let mut best = sorted[i.saturating_sub(1)..sorted.len().min(i + 1)]
+        .iter()
+        .map(|&v| (v.euclidean_distance_squared(query), v))
+        .min()?;
+    nearest_in(sorted, MortonKey::ROOT, 0..sorted.len(), coords, &mut best);
+    Some(best.1)
+}
+
+fn nearest_in(
+    sorted: &[Morton3D],
+    key: MortonKey,
+    inside: Range<usize>,
+    coords: (u32, u32, u32),
+    best: &mut (u64, Morton3D),
+) {
+    if key.depth() == MortonKey::MAX_DEPTH {
+        let candidate = (distance2(key, coords), sorted[inside.start]);
+        *best = candidate.min(*best);
+        return;
+    }
+    let mut children = split_children(sorted, key, inside.start, inside.end)
+        .map(|(child, inside)| (distance2(child, coords), child, inside));
+    children.sort_unstable_by_key(|&(d, child, _)| (d, child));
+    for (d, child, inside) in children.iter().cloned() {
+        if d > best.0 {
+            break;
+        }
+        if !inside.is_empty() {
+            nearest_in(sorted, child, inside, coords, best);
+        }
+    }
+}
+
+/// the `k` codes of `sorted` closest to `query` by euclidean distance, closest first,
+/// ties are broken by the smaller code. Fewer if `sorted` has less than `k` codes.
+///
+/// Cells of the implicit octree over `sorted` are visited best-first, i.e. by their distance
+/// to `query`, so the search stops as soon as `k` codes are found.
+///
+/// `sorted` must be sorted and the codes must not have the flag set.
+#[cfg(feature = "alloc")]
+pub fn knn(sorted: &[Morton3D], query: Morton3D, k: usize) -> Vec<Morton3D> {
+    let coords = query.to_coords();
+    let mut found = Vec::with_capacity(k.min(sorted.len()));
+    let mut heap = BinaryHeap::new();
+    if !sorted.is_empty() {
+        heap.push(Reverse((0, MortonKey::ROOT, 0, sorted.len())));
+    }
+    while found.len() < k {
+        let Reverse((_, key, start, end)) = match heap.pop() {
+            Some(cell) => cell,
+            None => break,
+        };
+        if key.depth() == MortonKey::MAX_DEPTH {
+            found.push(sorted[start]);
+            continue;
+        }
+        for (child, inside) in split_children(sorted, key, start, end).iter().cloned() {
+            if !inside.is_empty() {
+                heap.push(Reverse((
+                    distance2(child, coords),
+                    child,
+                    inside.start,
+                    inside.end,
+                )));
+            }
+        }
+    }
+    found
+}
+
+/// codes of `sorted` whose cell centers are within the distance `radius` of `center`, in order.
+///
+/// `center` and `radius` are in units of cells at [`MortonKey::MAX_DEPTH`], so the center of
+/// the cell `(x, y, z)` is `(x + 0.5, y + 0.5, z + 0.5)`. Only the parts of `sorted` inside of
+/// the ranges covering the sphere at a depth with cells about as large as `radius` are scanned.
+///
+/// `sorted` must be sorted and the codes must not have the flag set.
+pub fn within_radius(sorted: &[Morton3D], center: [f64; 3], radius: f64) -> WithinRadius<'_> {
+    // cells larger than the radius, so the sphere touches a few of them
+    let levels = (u64::BITS - (radius as u64).leading_zeros()).min(MortonKey::MAX_DEPTH);
+    let depth = MortonKey::MAX_DEPTH - levels;
+    WithinRadius {
+        sorted,
+        ranges: RegionRanges::new(Sphere::new(center, radius), depth),
+        depth,
+        current: [].iter(),
+        center,
+        radius2: radius * radius,
+    }
+}
+
+/// Iterator over the codes of a sorted slice within a distance, see [`within_radius`].
+#[derive(Debug, Clone)]
+pub struct WithinRadius<'a> {
+    /// codes after the current range
+    sorted: &'a [Morton3D],
+    ranges: RegionRanges<Sphere>,
+    /// depth of `ranges`
+    depth: u32,
+    current: slice::Iter<'a, Morton3D>,
+    center: [f64; 3],
+    radius2: f64,
+}
+
+impl<'a> Iterator for WithinRadius<'a> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        loop {
+            for &v in self.current.by_ref() {
+                let (x, y, z) = v.to_coords();
+                let d2: f64 = [x, y, z]
+                    .iter()
+                    .zip(self.center.iter())
+                    .map(|(&v, &c)| {
+                        let d = f64::from(v) + 0.5 - c;
+                        d * d
+                    })
+                    .sum();
+                if d2 <= self.radius2 {
+                    return Some(v);
+                }
+            }
+            let range = self.ranges.next()?;
+            let first = *MortonKey::new(range.start(), self.depth)
+                .descendants_range(MortonKey::MAX_DEPTH)
+                .start();
+            let last = *MortonKey::new(range.end(), self.depth)
+                .descendants_range(MortonKey::MAX_DEPTH)
+                .end();
+            let start = self.sorted.partition_point(|&v| v < first);
+            let end = start + self.sorted[start..].partition_point(|&v| v <= last);
+            self.current = self.sorted[start..end].iter();
+            self.sorted = &self.sorted[end..];
+        }
+    }
+}
+
+impl FusedIterator for WithinRadius<'_> {}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use super::knn;
+    use super::{nearest, within_radius};
+    use crate::Morton3D;
+
+    /// brute force
+    fn expected(codes: &[Morton3D], query: Morton3D) -> Option<Morton3D> {
+        codes
+            .iter()
+            .copied()
+            .min_by_key(|&v| (v.euclidean_distance_squared(query), v))
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut codes: Vec<_> = (0..500u32)
+            .map(|i| Morton3D::from_coords((i * 97) % 211, (i * 31) % 173, (i * 57) % 199))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        for i in 0..300u32 {
+            let query = Morton3D::from_coords((i * 13) % 260, (i * 29) % 220, (i * 7) % 250);
+            assert_eq!(nearest(&codes, query), expected(&codes, query));
+        }
+        assert_eq!(nearest(&codes, codes[17]), Some(codes[17]));
+
+        // next to each other in space, far apart along the curve
+        let split = vec![
+            Morton3D::from_coords(0, 0, 0),
+            Morton3D::from_coords(1 << 20, 0, 0),
+        ];
+        let query = Morton3D::from_coords((1 << 20) - 1, 0, 0);
+        assert_eq!(nearest(&split, query), Some(split[1]));
+        assert_eq!(nearest(&[], query), None);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_knn() {
+        let mut codes: Vec<_> = (0..400u32)
+            .map(|i| Morton3D::from_coords((i * 71) % 97, (i * 43) % 89, (i * 17) % 101))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        for i in 0..50u32 {
+            let query = Morton3D::from_coords((i * 13) % 120, (i * 29) % 100, (i * 7) % 110);
+            let mut expected = codes.clone();
+            expected.sort_by_key(|&v| (v.euclidean_distance_squared(query), v));
+            for &k in [0, 1, 5, 20].iter() {
+                assert_eq!(knn(&codes, query, k), expected[..k]);
+            }
+            assert_eq!(knn(&codes[..3], query, 10).len(), 3);
+        }
+        assert!(knn(&[], codes[0], 3).is_empty());
+    }
+
+    #[test]
+    fn test_within_radius() {
+        let mut codes: Vec<_> = (0..3000u32)
+            .map(|i| Morton3D::from_coords((i * 71) % 397, (i * 43) % 389, (i * 17) % 401))
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+        let queries = [
+            ([10.0, 20.0, 30.0], 25.0),
+            ([200.5, 200.5, 200.5], 80.0),
+            ([0.0, 0.0, 0.0], 0.5),
+            ([150.0, 10.0, 390.0], 1000.0),
+            ([1e6, 1e6, 1e6], 10.0),
+        ];
+        for &(center, radius) in queries.iter() {
+            let expected: Vec<_> = codes
+                .iter()
+                .copied()
+                .filter(|&v| {
+                    let (x, y, z) = v.to_coords();
+                    let d2: f64 = [x, y, z]
+                        .iter()
+                        .zip(center.iter())
+                        .map(|(&v, &c)| (f64::from(v) + 0.5 - c).powi(2))
+                        .sum();
+                    d2 <= radius * radius
+                })
+                .collect();
+            let got: Vec<_> = within_radius(&codes, center, radius).collect();
+            assert_eq!(got, expected);
+        }
+        let origin = Morton3D::from_coords(0, 0, 0);
+        assert_eq!(
+            within_radius(&[origin], [0.5; 3], 0.0).collect::<Vec<_>>(),
+            vec![origin]
+        );
+    }
+}
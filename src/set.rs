@@ -0,0 +1,518 @@
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+use crate::{Morton3D, MortonKey};
+
+/// Slot of a child which was never allocated.
+const NONE: u32 = u32::MAX;
+
+/// Inner node of [`MortonSet`], covering 64 children (two octant digits).
+#[derive(Debug, Clone)]
+struct Node {
+    /// bit `i` is set if the child `i` holds at least one cell
+    mask: u64,
+    /// index of each child in the next level
+    children: [u32; 64],
+}
+
+impl Node {
+    const EMPTY: Self = Self {
+        mask: 0,
+        children: [NONE; 64],
+    };
+}
+
+/// Set of cells at a fixed depth, stored as a hierarchical bitmap indexed by octant digits.
+///
+/// Each level of the bitmap consumes two octant digits (6 bits) of the code,
+/// the last level is a 64-bit word per group of 64 cells and the levels above tell
+/// which groups are occupied. So membership takes a fixed number of steps,
+/// iteration follows the set bits in curve order and [`any_in`](Self::any_in)
+/// stops at the level of the tested cell.
+///
+/// Sets are equal if they have the same depth and cells, whatever the order of insertion.
+#[derive(Debug, Clone)]
+pub struct MortonSet {
+    depth: u32,
+    /// number of levels, the last one being `leaves`
+    levels: u32,
+    /// inner nodes, the root is the first one (unless the root is a leaf)
+    nodes: Vec<Node>,
+    leaves: Vec<u64>,
+    len: usize,
+}
+
+impl MortonSet {
+    /// create empty set of cells at `depth`, codes are the ones at `depth`.
+    ///
+    /// panic if `depth` exceeds `Morton3D::MAX_DEPTH`.
+    pub fn new(depth: u32) -> Self {
+        assert!(depth <= Morton3D::MAX_DEPTH, "depth out of range");
+        let levels = (3 * depth).div_ceil(6).max(1);
+        let (nodes, leaves) = if levels == 1 {
+            (Vec::new(), alloc::vec![0])
+        } else {
+            (alloc::vec![Node::EMPTY], Vec::new())
+        };
+        Self {
+            depth,
+            levels,
+            nodes,
+            leaves,
+            len: 0,
+        }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// bits of `code` without flag, panic if it doesn't fit the depth
+    fn bits(&self, code: Morton3D) -> usize {
+        let mut code = code;
+        code.unset_flag();
+        let bits = usize::from(code);
+        assert!(bits >> (3 * self.depth) == 0, "code out of depth");
+        bits
+    }
+
+    /// digit of `bits` used at `level`
+    fn digit(&self, bits: usize, level: u32) -> usize {
+        (bits >> (6 * (self.levels - 1 - level))) & 63
+    }
+
+    /// index of the leaf word holding `bits`, `None` if it is not allocated
+    fn leaf(&self, bits: usize) -> Option<usize> {
+        let mut index = 0;
+        for level in 0..self.levels - 1 {
+            let node = &self.nodes[index];
+            let digit = self.digit(bits, level);
+            if node.mask >> digit & 1 == 0 {
+                return None;
+            }
+            index = node.children[digit] as usize;
+        }
+        Some(index)
+    }
+
+    pub fn contains(&self, code: Morton3D) -> bool {
+        let bits = self.bits(code);
+        match self.leaf(bits) {
+            Some(leaf) => self.leaves[leaf] >> (bits & 63) & 1 == 1,
+            None => false,
+        }
+    }
+
+    /// index of the leaf word holding `bits`, the path to it is allocated and marked occupied
+    fn leaf_mut(&mut self, bits: usize) -> usize {
+        let mut index = 0;
+        for level in 0..self.levels - 1 {
+            let digit = self.digit(bits, level);
+            let mut child = self.nodes[index].children[digit];
+            if child == NONE {
+                child = if level + 2 == self.levels {
+                    self.leaves.push(0);
+                    self.leaves.len() - 1
+                } else {
+                    self.nodes.push(Node::EMPTY);
+                    self.nodes.len() - 1
+                } as u32;
+                self.nodes[index].children[digit] = child;
+            }
+            self.nodes[index].mask |= 1 << digit;
+            index = child as usize;
+        }
+        index
+    }
+
+    /// add the cell, return whether it was not in the set.
+    ///
+    /// panic if `code` doesn't fit the depth of the set.
+    pub fn insert(&mut self, code: Morton3D) -> bool {
+        let bits = self.bits(code);
+        let index = self.leaf_mut(bits);
+        let bit = 1 << (bits & 63);
+        let inserted = self.leaves[index] & bit == 0;
+        self.leaves[index] |= bit;
+        self.len += inserted as usize;
+        inserted
+    }
+
+    /// remove the cell, return whether it was in the set.
+    pub fn remove(&mut self, code: Morton3D) -> bool {
+        let bits = self.bits(code);
+        let leaf = match self.leaf(bits) {
+            Some(leaf) => leaf,
+            None => return false,
+        };
+        let bit = 1 << (bits & 63);
+        if self.leaves[leaf] & bit == 0 {
+            return false;
+        }
+        self.leaves[leaf] &= !bit;
+        self.len -= 1;
+        if self.leaves[leaf] != 0 {
+            return true;
+        }
+        // clear the occupancy of the emptied groups, from the bottom
+        for level in (0..self.levels - 1).rev() {
+            let mut index = 0;
+            for l in 0..level {
+                index = self.nodes[index].children[self.digit(bits, l)] as usize;
+            }
+            let digit = self.digit(bits, level);
+            let node = &mut self.nodes[index];
+            node.mask &= !(1 << digit);
+            if node.mask != 0 {
+                break;
+            }
+        }
+        true
+    }
+
+    /// whether a cell of the set is below `key` (or is `key` itself, or contains it).
+    pub fn any_in(&self, key: MortonKey) -> bool {
+        if key.depth() >= self.depth {
+            let shift = 3 * (key.depth() - self.depth);
+            return self.contains(Morton3D::from(usize::from(key.code()) >> shift));
+        }
+        // the cells below key share the upper bits, `free` lower bits are arbitrary
+        let free = 3 * (self.depth - key.depth());
+        let bits = usize::from(key.code()) << free;
+        let mut index = 0;
+        for level in 0..self.levels {
+            let low = 6 * (self.levels - 1 - level);
+            let word = if level + 1 == self.levels {
+                self.leaves[index]
+            } else {
+                self.nodes[index].mask
+            };
+            if free > low {
+                // the digits of this level covered by key form a contiguous group of bits
+                let group = ((1u128 << (1 << (free - low))) - 1) as u64;
+                return word & (group << self.digit(bits, level)) != 0;
+            }
+            let digit = self.digit(bits, level);
+            if word >> digit & 1 == 0 {
+                return false;
+            }
+            index = self.nodes[index].children[digit] as usize;
+        }
+        unreachable!("key coarser than the set covers a group of some level")
+    }
+
+    /// iterate over the cells in curve order.
+    pub fn iter(&self) -> SetIter<'_> {
+        let mut iter = SetIter {
+            set: self,
+            stack: Vec::with_capacity(self.levels as usize),
+            leaf: None,
+        };
+        if self.levels == 1 {
+            iter.leaf = Some((self.leaves[0], 0));
+        } else {
+            iter.stack.push((0, self.nodes[0].mask, 0));
+        }
+        iter
+    }
+}
+
+/// Set algebra, whole subtrees missing from the operands are skipped at once and
+/// the cells are combined 64 at a time.
+impl MortonSet {
+    /// combine the leaf words of two sets of the same depth with `op`,
+    /// visiting the children of each inner node selected by `visit` from the two masks
+    fn combine(&self, other: &Self, visit: fn(u64, u64) -> u64, op: fn(u64, u64) -> u64) -> Self {
+        assert_eq!(self.depth, other.depth, "sets of different depths");
+        let mut result = Self::new(self.depth);
+        if self.levels == 1 {
+            result.leaves[0] = op(self.leaves[0], other.leaves[0]);
+            result.len = result.leaves[0].count_ones() as usize;
+        } else {
+            result.combine_node(self, other, (Some(0), Some(0)), 0, 0, visit, op);
+        }
+        result
+    }
+
+    /// combine the subtrees of the inner nodes `nodes` of `a` and `b` at `level`, with upper bits `upper`
+    #[allow(clippy::too_many_arguments)]
+    fn combine_node(
+        &mut self,
+        a: &Self,
+        b: &Self,
+        nodes: (Option<usize>, Option<usize>),
+        level: u32,
+        upper: usize,
+        visit: fn(u64, u64) -> u64,
+        op: fn(u64, u64) -> u64,
+    ) {
+        let mask = |set: &Self, node: Option<usize>| node.map_or(0, |i| set.nodes[i].mask);
+        let (mask_a, mask_b) = (mask(a, nodes.0), mask(b, nodes.1));
+        let child = |set: &Self, node: Option<usize>, mask: u64, digit: usize| {
+            node.filter(|_| mask >> digit & 1 == 1)
+                .map(|i| set.nodes[i].children[digit] as usize)
+        };
+        let mut rest = visit(mask_a, mask_b);
+        while rest != 0 {
+            let digit = rest.trailing_zeros() as usize;
+            rest &= rest - 1;
+            let children = (
+                child(a, nodes.0, mask_a, digit),
+                child(b, nodes.1, mask_b, digit),
+            );
+            let upper = upper << 6 | digit;
+            if level + 2 == self.levels {
+                let word = |set: &Self, leaf: Option<usize>| leaf.map_or(0, |i| set.leaves[i]);
+                let word = op(word(a, children.0), word(b, children.1));
+                if word != 0 {
+                    let index = self.leaf_mut(upper << 6);
+                    self.leaves[index] = word;
+                    self.len += word.count_ones() as usize;
+                }
+            } else {
+                self.combine_node(a, b, children, level + 1, upper, visit, op);
+            }
+        }
+    }
+
+    /// cells in either set. panic if the depths differ.
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b, |a, b| a | b)
+    }
+    /// cells in both sets. panic if the depths differ.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b, |a, b| a & b)
+    }
+    /// cells in `self` but not in `other`. panic if the depths differ.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, _| a, |a, b| a & !b)
+    }
+    /// cells in exactly one of the sets. panic if the depths differ.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b, |a, b| a ^ b)
+    }
+}
+
+impl PartialEq for MortonSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.depth == other.depth && self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for MortonSet {}
+
+impl Hash for MortonSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.depth.hash(state);
+        self.len.hash(state);
+        for code in self.iter() {
+            code.hash(state);
+        }
+    }
+}
+
+impl Extend<Morton3D> for MortonSet {
+    fn extend<I: IntoIterator<Item = Morton3D>>(&mut self, iter: I) {
+        for code in iter {
+            self.insert(code);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a MortonSet {
+    type Item = Morton3D;
+    type IntoIter = SetIter<'a>;
+    fn into_iter(self) -> SetIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the cells of a [`MortonSet`], in curve order.
+#[derive(Debug, Clone)]
+pub struct SetIter<'a> {
+    set: &'a MortonSet,
+    /// inner nodes being visited, as (index, children not visited yet, upper bits)
+    stack: Vec<(u32, u64, usize)>,
+    /// leaf word being visited, as (cells not visited yet, upper bits)
+    leaf: Option<(u64, usize)>,
+}
+
+impl Iterator for SetIter<'_> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        loop {
+            if let Some((rest, upper)) = &mut self.leaf {
+                if *rest != 0 {
+                    let bit = rest.trailing_zeros() as usize;
+                    *rest &= *rest - 1;
+                    return Some(Morton3D::from(*upper << 6 | bit));
+                }
+                self.leaf = None;
+            }
+            let depth = self.stack.len();
+            let (index, rest, upper) = self.stack.last_mut()?;
+            if *rest == 0 {
+                self.stack.pop();
+                continue;
+            }
+            let digit = rest.trailing_zeros() as usize;
+            *rest &= *rest - 1;
+            let child = self.set.nodes[*index as usize].children[digit];
+            let upper = *upper << 6 | digit;
+            if depth + 1 == self.set.levels as usize {
+                self.leaf = Some((self.set.leaves[child as usize], upper));
+            } else {
+                let mask = self.set.nodes[child as usize].mask;
+                self.stack.push((child, mask, upper));
+            }
+        }
+    }
+}
+
+impl core::iter::FusedIterator for SetIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use super::MortonSet;
+    use crate::{Morton3D, MortonKey};
+
+    /// pseudo-random codes fitting `depth`
+    fn codes(depth: u32, count: usize) -> Vec<Morton3D> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..count)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                Morton3D::from((state as usize) & ((1 << (3 * depth)) - 1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_set() {
+        for depth in [0, 1, 2, 3, 5, Morton3D::MAX_DEPTH] {
+            let mut set = MortonSet::new(depth);
+            let mut expected = BTreeSet::new();
+            for code in codes(depth, 300) {
+                assert_eq!(set.insert(code), expected.insert(code));
+            }
+            for code in codes(depth, 100) {
+                assert_eq!(set.remove(code), expected.remove(&code));
+                assert!(!set.contains(code));
+            }
+            assert_eq!(set.len(), expected.len());
+            assert!(expected.iter().all(|&code| set.contains(code)));
+            assert_eq!(
+                set.iter().collect::<Vec<_>>(),
+                expected.iter().copied().collect::<Vec<_>>()
+            );
+        }
+
+        let mut set = MortonSet::new(3);
+        assert!(set.is_empty());
+        let mut flagged = Morton3D::from_coords(7, 0, 1);
+        flagged.set_flag();
+        set.extend([flagged, Morton3D::from_coords(7, 0, 1)]);
+        assert_eq!(set.len(), 1);
+        assert!(set.remove(Morton3D::from_coords(7, 0, 1)));
+        assert!(set.is_empty() && set.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_eq() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let hash = |set: &MortonSet| {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        };
+        let codes = codes(6, 200);
+        let (mut a, mut b) = (MortonSet::new(6), MortonSet::new(6));
+        a.extend(codes.iter().copied());
+        b.extend(codes.iter().rev().copied());
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+
+        // emptied nodes and words are left in place
+        let mut c = a.clone();
+        c.insert(Morton3D::from_coords(63, 63, 63));
+        assert_ne!(a, c);
+        c.remove(Morton3D::from_coords(63, 63, 63));
+        for &code in codes.iter() {
+            a.remove(code);
+            c.remove(code);
+        }
+        assert_eq!(a, MortonSet::new(6));
+        assert_eq!(c, MortonSet::new(6));
+        assert_eq!(hash(&c), hash(&MortonSet::new(6)));
+        assert_ne!(MortonSet::new(5), MortonSet::new(6));
+    }
+
+    #[test]
+    fn test_algebra() {
+        for depth in [2, 3, 6] {
+            let (mut a, mut b) = (MortonSet::new(depth), MortonSet::new(depth));
+            let codes = codes(depth, 200);
+            a.extend(codes[..120].iter().copied());
+            b.extend(codes[80..].iter().copied());
+            let set_a: BTreeSet<_> = a.iter().collect();
+            let set_b: BTreeSet<_> = b.iter().collect();
+            let check = |result: MortonSet, expected: Vec<Morton3D>| {
+                assert_eq!(result.len(), expected.len());
+                assert_eq!(result.iter().collect::<Vec<_>>(), expected);
+                if let Some(&first) = expected.first() {
+                    assert!(result.any_in(MortonKey::new(first, depth)));
+                }
+            };
+            check(a.union(&b), set_a.union(&set_b).copied().collect());
+            check(
+                a.intersection(&b),
+                set_a.intersection(&set_b).copied().collect(),
+            );
+            check(
+                a.difference(&b),
+                set_a.difference(&set_b).copied().collect(),
+            );
+            check(
+                a.symmetric_difference(&b),
+                set_a.symmetric_difference(&set_b).copied().collect(),
+            );
+            assert!(a.difference(&a).is_empty());
+            assert!(!a.difference(&a).any_in(MortonKey::ROOT));
+        }
+    }
+
+    #[test]
+    fn test_any_in() {
+        for depth in [1, 4, 7] {
+            let mut set = MortonSet::new(depth);
+            set.extend(codes(depth, 20));
+            let covered = |key: MortonKey| {
+                set.iter().any(|code| {
+                    let leaf = MortonKey::new(code, depth);
+                    leaf == key || key.is_ancestor_of(leaf) || leaf.is_ancestor_of(key)
+                })
+            };
+            let keys = MortonKey::ROOT.walk_depth_first(depth.min(4) + 1, |_| false);
+            for key in keys {
+                assert_eq!(set.any_in(key), covered(key), "{:?}", key);
+            }
+            set.extend(codes(depth, 20));
+            for code in codes(depth, 20) {
+                set.remove(code);
+            }
+            assert!(!set.any_in(MortonKey::ROOT));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+
+use crate::Morton3D;
+
+/// number of shifted copies, `D + 2` for `D = 3` since shifts need an odd denominator
+const SHIFTS: usize = 5;
+
+/// Index for approximate nearest-neighbor queries made of several shifted copies of the codes.
+///
+/// Copy `j` translates every cell by `j * ShiftedCurves::LIMIT / 5` along each axis and sorts the
+/// codes along the curve. Two cells can be far apart along one curve if a boundary of a large
+/// octant passes between them, but not in all of the copies: for one of the shifts they share a
+/// cell whose size is within a constant factor (depending only on the dimension) of their distance.
+/// So looking at the codes next to the query along each curve finds a neighbor whose distance is
+/// within a constant factor of the nearest distance, with a few binary searches and no backtracking.
+///
+/// Coordinates must be below [`ShiftedCurves::LIMIT`] to leave room for the shifts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShiftedCurves {
+    /// sorted pairs of shifted and original codes, for each shift
+    curves: [Vec<(Morton3D, Morton3D)>; SHIFTS],
+}
+
+impl Default for ShiftedCurves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShiftedCurves {
+    /// coordinates of indexed and query cells must be below this
+    pub const LIMIT: u32 = 1 << (Morton3D::MAX_DEPTH - 1);
+
+    pub fn new() -> Self {
+        Self {
+            curves: Default::default(),
+        }
+    }
+
+    /// the cell translated by shift `j`
+    ///
+    /// panic if a coordinate isn't below `LIMIT`.
+    fn shift(code: Morton3D, j: usize) -> Morton3D {
+        let (x, y, z) = code.to_coords();
+        assert!(
+            x < Self::LIMIT && y < Self::LIMIT && z < Self::LIMIT,
+            "coordinate out of range"
+        );
+        // `j / 5` has a repeating binary expansion, so the shift moves cells at every depth
+        let s = j as u32 * Self::LIMIT / SHIFTS as u32;
+        Morton3D::from_coords(x + s, y + s, z + s)
+    }
+
+    pub fn len(&self) -> usize {
+        self.curves[0].len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.curves[0].is_empty()
+    }
+
+    /// add a cell, duplicates are kept.
+    ///
+    /// panic if a coordinate isn't below [`ShiftedCurves::LIMIT`].
+    pub fn insert(&mut self, code: Morton3D) {
+        for (j, curve) in self.curves.iter_mut().enumerate() {
+            let item = (Self::shift(code, j), code);
+            let i = curve.partition_point(|&v| v < item);
+            curve.insert(i, item);
+        }
+    }
+
+    /// cell approximately closest to `query`, see [`ShiftedCurves`].
+    ///
+    /// panic if a coordinate isn't below [`ShiftedCurves::LIMIT`].
+    pub fn nearest(&self, query: Morton3D) -> Option<Morton3D> {
+        self.knn(query, 1).first().copied()
+    }
+
+    /// `k` cells approximately closest to `query`, closest first, ties are broken by the smaller code.
+    ///
+    /// Candidates are the `k` cells before and after `query` along each curve.
+    ///
+    /// panic if a coordinate isn't below [`ShiftedCurves::LIMIT`].
+    pub fn knn(&self, query: Morton3D, k: usize) -> Vec<Morton3D> {
+        let mut candidates = Vec::with_capacity(2 * k * SHIFTS);
+        for (j, curve) in self.curves.iter().enumerate() {
+            let shifted = Self::shift(query, j);
+            let i = curve.partition_point(|&(v, _)| v < shifted);
+            let window = &curve[i.saturating_sub(k)..curve.len().min(i + k)];
+            candidates.extend(window.iter().map(|&(_, code)| code));
+        }
+        candidates.sort_unstable_by_key(|&v| (v.euclidean_distance_squared(query), v));
+        candidates.dedup();
+        candidates.truncate(k);
+        candidates
+    }
+}
+
+impl Extend<Morton3D> for ShiftedCurves {
+    fn extend<I: IntoIterator<Item = Morton3D>>(&mut self, iter: I) {
+        let codes: Vec<_> = iter.into_iter().collect();
+        for (j, curve) in self.curves.iter_mut().enumerate() {
+            curve.extend(codes.iter().map(|&code| (Self::shift(code, j), code)));
+            curve.sort_unstable();
+        }
+    }
+}
+
+impl FromIterator<Morton3D> for ShiftedCurves {
+    fn from_iter<I: IntoIterator<Item = Morton3D>>(iter: I) -> Self {
+        let mut index = Self::new();
+        index.extend(iter);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShiftedCurves;
+    use crate::{knn, Morton3D};
+
+    #[test]
+    fn test_shifted_curves() {
+        let mut codes: Vec<_> = (0..2000u32)
+            .map(|i| {
+                Morton3D::from_coords((i * 7919) % 4093, (i * 104_729) % 4091, (i * 31) % 4079)
+            })
+            .collect();
+        let index: ShiftedCurves = codes.iter().copied().collect();
+        assert_eq!(index.len(), 2000);
+        codes.sort_unstable();
+
+        for i in 0..200u32 {
+            let query = Morton3D::from_coords((i * 383) % 4096, (i * 577) % 4096, (i * 89) % 4096);
+            let exact = knn(&codes, query, 1)[0];
+            let approx = index.nearest(query).unwrap();
+            // within a factor of 3 of the nearest distance
+            assert!(
+                approx.euclidean_distance_squared(query)
+                    <= 9 * exact.euclidean_distance_squared(query)
+            );
+
+            let found = index.knn(query, 5);
+            assert_eq!(found.len(), 5);
+            assert!(found
+                .windows(2)
+                .all(|w| w[0].euclidean_distance_squared(query)
+                    <= w[1].euclidean_distance_squared(query)));
+        }
+        assert_eq!(index.nearest(codes[42]), Some(codes[42]));
+
+        let mut single = ShiftedCurves::new();
+        assert_eq!(single.nearest(codes[0]), None);
+        single.insert(codes[0]);
+        single.insert(codes[1]);
+        assert_eq!(single.knn(codes[0], 5), vec![codes[0], codes[1]]);
+    }
+}
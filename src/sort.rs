@@ -0,0 +1,132 @@
+use crate::Morton3D;
+
+/// Point with coordinates in cells at [`Morton3D::MAX_DEPTH`], see [`SortByMorton`].
+pub trait Point3D {
+    /// coordinates `(x, y, z)`, only the lower `MAX_DEPTH` bits are used
+    fn coords(&self) -> (u32, u32, u32);
+
+    /// code of the cell of this point
+    fn morton(&self) -> Morton3D {
+        let (x, y, z) = self.coords();
+        Morton3D::from_coords(x, y, z)
+    }
+}
+
+impl Point3D for (u32, u32, u32) {
+    fn coords(&self) -> (u32, u32, u32) {
+        *self
+    }
+}
+
+impl Point3D for [u32; 3] {
+    fn coords(&self) -> (u32, u32, u32) {
+        (self[0], self[1], self[2])
+    }
+}
+
+impl Point3D for Morton3D {
+    fn coords(&self) -> (u32, u32, u32) {
+        self.to_coords()
+    }
+    fn morton(&self) -> Morton3D {
+        let mut code = *self;
+        code.unset_flag();
+        code
+    }
+}
+
+impl<P: Point3D + ?Sized> Point3D for &P {
+    fn coords(&self) -> (u32, u32, u32) {
+        (**self).coords()
+    }
+    fn morton(&self) -> Morton3D {
+        (**self).morton()
+    }
+}
+
+/// Extension of slices of points to sort them in Z-order.
+pub trait SortByMorton {
+    /// sort in Z-order in place, encoding the points on every comparison.
+    ///
+    /// Points in the same cell may be reordered, like [`slice::sort_unstable`].
+    /// Cheap when the coordinates are at hand, see `sort_by_morton_cached` otherwise.
+    fn sort_by_morton(&mut self);
+
+    /// sort in Z-order in place, encoding each point once into a buffer of codes.
+    ///
+    /// Points in the same cell keep their order, like [`slice::sort_by_cached_key`].
+    #[cfg(feature = "alloc")]
+    fn sort_by_morton_cached(&mut self);
+}
+
+impl<P: Point3D> SortByMorton for [P] {
+    fn sort_by_morton(&mut self) {
+        self.sort_unstable_by_key(P::morton);
+    }
+    #[cfg(feature = "alloc")]
+    fn sort_by_morton_cached(&mut self) {
+        self.sort_by_cached_key(P::morton);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point3D, SortByMorton};
+    #[cfg(feature = "alloc")]
+    use crate::Morton3D;
+
+    #[cfg(feature = "alloc")]
+    struct Particle {
+        position: [u32; 3],
+        id: usize,
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Point3D for Particle {
+        fn coords(&self) -> (u32, u32, u32) {
+            self.position.coords()
+        }
+    }
+
+    #[test]
+    fn test_sort_by_morton() {
+        let mut points: Vec<_> = (0..200u32)
+            .map(|i| ((i * 37) % 64, (i * 11) % 64, (i * 5) % 64))
+            .collect();
+        let mut expected: Vec<_> = points.iter().map(Point3D::morton).collect();
+        expected.sort_unstable();
+        points.sort_by_morton();
+        assert!(points
+            .iter()
+            .map(Point3D::morton)
+            .eq(expected.iter().copied()));
+
+        // the flag is ignored
+        expected.dedup();
+        let mut codes: Vec<_> = expected.iter().rev().copied().collect();
+        codes[0].set_flag();
+        codes.sort_by_morton();
+        assert!(codes
+            .iter()
+            .map(Point3D::morton)
+            .eq(expected.iter().copied()));
+        assert!(codes[codes.len() - 1].is_flag_set());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_sort_by_morton_cached() {
+        let mut particles: Vec<_> = (0..100)
+            .map(|id| Particle {
+                position: [(id as u32 * 7) % 4, 1, (id as u32 * 3) % 4],
+                id,
+            })
+            .collect();
+        particles.sort_by_morton_cached();
+        assert!(particles.windows(2).all(|w| {
+            let (a, b) = (w[0].morton(), w[1].morton());
+            a < b || (a == b && w[0].id < w[1].id)
+        }));
+        assert_eq!(particles[0].morton(), Morton3D::from_coords(0, 1, 0));
+    }
+}
@@ -0,0 +1,36 @@
+use core::fmt::Debug;
+use core::hash::Hash;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Unsigned integer used as backing storage of [`MortonND`](crate::MortonND).
+///
+/// The highest bit is used for 1-bit flag, and `(BITS - 1) / D` levels are stored below it.
+/// This trait is sealed, it is implemented for `u16`, `u32`, `u64`, `u128` and `usize`.
+pub trait MortonStorage:
+    private::Sealed + Copy + Default + Debug + Hash + Eq + Ord + Send + Sync + 'static
+{
+    /// Type of a coordinate, wide enough to hold `(BITS - 1) / 2` bits.
+    type Coord: Copy + Default + Debug + Hash + Eq + Ord + Send + Sync + 'static;
+
+    /// Number of bits.
+    const BITS: u32;
+}
+
+macro_rules! impl_storage {
+    ($t:ty, $c:ty) => {
+        impl private::Sealed for $t {}
+        impl MortonStorage for $t {
+            type Coord = $c;
+            const BITS: u32 = <$t>::BITS;
+        }
+    };
+}
+
+impl_storage!(u16, u16);
+impl_storage!(u32, u32);
+impl_storage!(u64, u32);
+impl_storage!(u128, u64);
+impl_storage!(usize, u32);
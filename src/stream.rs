@@ -0,0 +1,115 @@
+use core::iter::FusedIterator;
+
+use crate::Morton3D;
+
+/// encode points `[x, y, z]` lazily, the same as [`Morton3D::from_array`] on each point.
+pub fn morton_codes<I: IntoIterator<Item = [u32; 3]>>(points: I) -> MortonCodes<I::IntoIter> {
+    MortonCodes {
+        points: points.into_iter(),
+    }
+}
+
+/// decode codes into points `[x, y, z]` lazily, the same as [`Morton3D::to_array`] on each code.
+/// The flag is ignored.
+pub fn morton_points<I: IntoIterator<Item = Morton3D>>(codes: I) -> MortonPoints<I::IntoIter> {
+    MortonPoints {
+        codes: codes.into_iter(),
+    }
+}
+
+/// Iterator encoding points, see [`morton_codes`].
+#[derive(Debug, Clone)]
+pub struct MortonCodes<I> {
+    points: I,
+}
+
+/// Iterator decoding codes, see [`morton_points`].
+#[derive(Debug, Clone)]
+pub struct MortonPoints<I> {
+    codes: I,
+}
+
+impl<I: Iterator<Item = [u32; 3]>> Iterator for MortonCodes<I> {
+    type Item = Morton3D;
+    fn next(&mut self) -> Option<Morton3D> {
+        self.points.next().map(Morton3D::from_array)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.points.size_hint()
+    }
+    fn nth(&mut self, n: usize) -> Option<Morton3D> {
+        self.points.nth(n).map(Morton3D::from_array)
+    }
+    fn fold<B, F: FnMut(B, Morton3D) -> B>(self, init: B, mut f: F) -> B {
+        self.points
+            .fold(init, |acc, p| f(acc, Morton3D::from_array(p)))
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = [u32; 3]>> DoubleEndedIterator for MortonCodes<I> {
+    fn next_back(&mut self) -> Option<Morton3D> {
+        self.points.next_back().map(Morton3D::from_array)
+    }
+}
+
+impl<I: ExactSizeIterator<Item = [u32; 3]>> ExactSizeIterator for MortonCodes<I> {}
+
+impl<I: FusedIterator<Item = [u32; 3]>> FusedIterator for MortonCodes<I> {}
+
+impl<I: Iterator<Item = Morton3D>> Iterator for MortonPoints<I> {
+    type Item = [u32; 3];
+    fn next(&mut self) -> Option<[u32; 3]> {
+        self.codes.next().map(Morton3D::to_array)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.codes.size_hint()
+    }
+    fn nth(&mut self, n: usize) -> Option<[u32; 3]> {
+        self.codes.nth(n).map(Morton3D::to_array)
+    }
+    fn fold<B, F: FnMut(B, [u32; 3]) -> B>(self, init: B, mut f: F) -> B {
+        self.codes.fold(init, |acc, v| f(acc, v.to_array()))
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = Morton3D>> DoubleEndedIterator for MortonPoints<I> {
+    fn next_back(&mut self) -> Option<[u32; 3]> {
+        self.codes.next_back().map(Morton3D::to_array)
+    }
+}
+
+impl<I: ExactSizeIterator<Item = Morton3D>> ExactSizeIterator for MortonPoints<I> {}
+
+impl<I: FusedIterator<Item = Morton3D>> FusedIterator for MortonPoints<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{morton_codes, morton_points};
+    use crate::Morton3D;
+
+    #[test]
+    fn test_stream() {
+        let points = (0..100u32).map(|i| [i * 3, (i * 7919) % 4093, !i]);
+        let codes = morton_codes(points.clone());
+        assert_eq!(codes.len(), 100);
+        assert!(codes
+            .clone()
+            .zip(points.clone())
+            .all(|(v, [x, y, z])| v == Morton3D::from_coords(x, y, z)));
+        assert_eq!(
+            codes.clone().next_back(),
+            Some(Morton3D::from_coords(297, (99 * 7919) % 4093, !99))
+        );
+
+        let decoded: Vec<_> = morton_points(codes.map(|mut v| {
+            v.set_flag();
+            v
+        }))
+        .collect();
+        assert!(decoded
+            .iter()
+            .zip(points)
+            .all(|(&p, [x, y, z])| p == Morton3D::from_coords(x, y, z).to_array()));
+        assert_eq!(morton_points(Vec::new()).next(), None);
+    }
+}
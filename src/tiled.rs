@@ -0,0 +1,207 @@
+use crate::Morton3D;
+
+/// Layout of a `size_x * size_y * size_z` volume in cubic tiles `2^tile_depth` cells wide,
+/// cells in Z-order within a tile and tiles in row-major order with x varying fastest.
+///
+/// Small tiles keep neighbor cells close in memory like Z-order does, while the row-major order
+/// of tiles avoids the padding of a volume to a power of two cube and keeps the index computation
+/// cheap for large volumes. Tiles at the upper edges are padded, so the tiled buffer has
+/// [`len`](Self::len) cells, which can be more than the cells of the volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TiledLayout {
+    size: (u32, u32, u32),
+    tile_depth: u32,
+    /// number of tiles along each axis
+    tiles: (u32, u32, u32),
+}
+
+impl TiledLayout {
+    /// panic if `tile_depth` exceeds `Morton3D::MAX_DEPTH`
+    /// or the tiled buffer would have more than `usize::MAX` cells.
+    pub fn new(size_x: u32, size_y: u32, size_z: u32, tile_depth: u32) -> Self {
+        assert!(tile_depth <= Morton3D::MAX_DEPTH, "depth out of range");
+        let count = |size: u32| ((u64::from(size) + (1 << tile_depth) - 1) >> tile_depth) as u32;
+        let layout = Self {
+            size: (size_x, size_y, size_z),
+            tile_depth,
+            tiles: (count(size_x), count(size_y), count(size_z)),
+        };
+        let (tx, ty, tz) = layout.tiles;
+        let len = [ty, tz]
+            .iter()
+            .try_fold(tx as usize, |n, &t| n.checked_mul(t as usize))
+            .and_then(|n| n.checked_mul(layout.tile_len()));
+        assert!(len.is_some(), "volume too large");
+        layout
+    }
+
+    pub fn size(&self) -> (u32, u32, u32) {
+        self.size
+    }
+    pub fn tile_depth(&self) -> u32 {
+        self.tile_depth
+    }
+    /// number of tiles along each axis
+    pub fn tiles(&self) -> (u32, u32, u32) {
+        self.tiles
+    }
+    /// number of cells of a tile
+    pub fn tile_len(&self) -> usize {
+        1 << (3 * self.tile_depth)
+    }
+    /// number of cells of the tiled buffer, padding included
+    pub fn len(&self) -> usize {
+        let (tx, ty, tz) = self.tiles;
+        tx as usize * ty as usize * tz as usize * self.tile_len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// index of the tile and offset of the cell in the tile, `None` outside of the volume.
+    pub fn locate(&self, x: u32, y: u32, z: u32) -> Option<(usize, usize)> {
+        let (sx, sy, sz) = self.size;
+        if x >= sx || y >= sy || z >= sz {
+            return None;
+        }
+        let d = self.tile_depth;
+        let (tx, ty, _) = self.tiles;
+        let tile =
+            ((z >> d) as usize * ty as usize + (y >> d) as usize) * tx as usize + (x >> d) as usize;
+        let low = (1 << d) - 1;
+        let offset = Morton3D::from_coords(x & low, y & low, z & low);
+        Some((tile, usize::from(offset)))
+    }
+
+    /// index of the cell in the tiled buffer, `None` outside of the volume.
+    pub fn index(&self, x: u32, y: u32, z: u32) -> Option<usize> {
+        self.locate(x, y, z)
+            .map(|(tile, offset)| tile * self.tile_len() + offset)
+    }
+
+    /// coordinates of the cell at `index` in the tiled buffer,
+    /// `None` for padding cells and indices out of range.
+    pub fn coords(&self, index: usize) -> Option<(u32, u32, u32)> {
+        if index >= self.len() {
+            return None;
+        }
+        let (tile, offset) = (index / self.tile_len(), index % self.tile_len());
+        let (tx, ty, _) = self.tiles;
+        let (tx, ty) = (tx as usize, ty as usize);
+        let d = self.tile_depth;
+        let (x, y, z) = Morton3D::from(offset).to_coords();
+        let (x, y, z) = (
+            ((tile % tx) as u32) << d | x,
+            ((tile / tx % ty) as u32) << d | y,
+            ((tile / tx / ty) as u32) << d | z,
+        );
+        let (sx, sy, sz) = self.size;
+        if x < sx && y < sy && z < sz {
+            Some((x, y, z))
+        } else {
+            None
+        }
+    }
+
+    /// copy the cells of `linear`, in row-major order with x varying fastest, into `tiled`.
+    /// Padding cells of `tiled` are left as they are.
+    ///
+    /// panic if `linear` doesn't have the cells of the volume or `tiled` doesn't have
+    /// [`len`](Self::len) cells.
+    pub fn linear_to_tiled<T: Clone>(&self, linear: &[T], tiled: &mut [T]) {
+        let (sx, sy, sz) = self.size;
+        assert_eq!(
+            linear.len() as u64,
+            u64::from(sx) * u64::from(sy) * u64::from(sz),
+            "wrong linear length"
+        );
+        assert_eq!(tiled.len(), self.len(), "wrong tiled length");
+        let mut cells = linear.iter();
+        for z in 0..sz {
+            for y in 0..sy {
+                for (x, v) in (0..sx).zip(cells.by_ref()) {
+                    if let Some(i) = self.index(x, y, z) {
+                        tiled[i] = v.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// copy the cells of `tiled` into `linear`, in row-major order with x varying fastest.
+    /// This is the inverse of [`linear_to_tiled`](Self::linear_to_tiled).
+    ///
+    /// panic if `tiled` doesn't have [`len`](Self::len) cells or `linear` doesn't have
+    /// the cells of the volume.
+    pub fn tiled_to_linear<T: Clone>(&self, tiled: &[T], linear: &mut [T]) {
+        let (sx, sy, sz) = self.size;
+        assert_eq!(tiled.len(), self.len(), "wrong tiled length");
+        assert_eq!(
+            linear.len() as u64,
+            u64::from(sx) * u64::from(sy) * u64::from(sz),
+            "wrong linear length"
+        );
+        let mut cells = linear.iter_mut();
+        for z in 0..sz {
+            for y in 0..sy {
+                for (x, v) in (0..sx).zip(cells.by_ref()) {
+                    if let Some(i) = self.index(x, y, z) {
+                        *v = tiled[i].clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TiledLayout;
+    use crate::Morton3D;
+
+    #[test]
+    fn test_tiled_layout() {
+        let layout = TiledLayout::new(10, 5, 7, 2);
+        assert_eq!(layout.tiles(), (3, 2, 2));
+        assert_eq!(layout.tile_len(), 64);
+        assert_eq!(layout.len(), 12 * 64);
+        assert_eq!(layout.locate(0, 0, 0), Some((0, 0)));
+        assert_eq!(
+            layout.locate(9, 4, 6),
+            Some((3 * 2 + 3 + 2, usize::from(Morton3D::from_coords(1, 0, 2))))
+        );
+        assert_eq!(layout.locate(10, 0, 0), None);
+
+        // every cell of the volume has its own index
+        let mut seen = vec![false; layout.len()];
+        for z in 0..7 {
+            for y in 0..5 {
+                for x in 0..10 {
+                    let i = layout.index(x, y, z).unwrap();
+                    assert!(!seen[i]);
+                    seen[i] = true;
+                    assert_eq!(layout.coords(i), Some((x, y, z)));
+                }
+            }
+        }
+        assert_eq!(seen.iter().filter(|&&v| v).count(), 350);
+        assert!((0..layout.len()).all(|i| seen[i] == layout.coords(i).is_some()));
+        assert_eq!(layout.coords(layout.len()), None);
+
+        let linear: Vec<_> = (0..350).collect();
+        let mut tiled = vec![usize::MAX; layout.len()];
+        layout.linear_to_tiled(&linear, &mut tiled);
+        assert_eq!(tiled[layout.index(3, 2, 1).unwrap()], 3 + 10 * (2 + 5));
+        let mut back = vec![0; 350];
+        layout.tiled_to_linear(&tiled, &mut back);
+        assert_eq!(back, linear);
+
+        // a single tile is plain Z-order
+        let cube = TiledLayout::new(8, 8, 8, 3);
+        assert_eq!(
+            cube.index(5, 6, 7),
+            Some(usize::from(Morton3D::from_coords(5, 6, 7)))
+        );
+        assert!(TiledLayout::new(0, 3, 3, 4).is_empty());
+    }
+}
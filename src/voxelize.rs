@@ -0,0 +1,193 @@
+#[cfg(feature = "alloc")]
+use crate::MortonSet;
+use crate::{MortonKey, Raycast, Region, RegionCells};
+
+/// codes of the cells at `depth` a segment touches, in order from `p0` to `p1`.
+///
+/// Positions are in units of cells at `depth` like [`crate::raycast`], and the parts of
+/// the segment outside of the grid are skipped. Unlike a ray, the segment is a supercover:
+/// when it crosses an edge or a corner exactly, every cell touching it there is included,
+/// so the cells are 26-connected and contain the whole segment.
+///
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn voxelize_segment(p0: [f64; 3], p1: [f64; 3], depth: u32) -> Raycast {
+    let direction = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+    Raycast::new(p0, direction, 1.0, depth, true)
+}
+
+/// Triangle given by its vertices, see [`Region`] for the units.
+///
+/// A triangle has no volume, so it never contains a cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub vertices: [[f64; 3]; 3],
+}
+
+impl Triangle {
+    pub fn new(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> Self {
+        Self {
+            vertices: [a, b, c],
+        }
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+impl Region for Triangle {
+    fn contains_key(&self, _key: MortonKey) -> bool {
+        false
+    }
+    fn intersects_key(&self, key: MortonKey) -> bool {
+        // separating axis test of Akenine-Möller, relative to the center of the cell
+        let center = key.center();
+        let half = f64::from(key.size()) / 2.0;
+        let v = self.vertices.map(|p| sub(p, center));
+        let edges = [sub(v[1], v[0]), sub(v[2], v[1]), sub(v[0], v[2])];
+        let units = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let separates = |axis: [f64; 3]| {
+            let r = half * (axis[0].abs() + axis[1].abs() + axis[2].abs());
+            let p = v.map(|p| dot(p, axis));
+            p[0].min(p[1]).min(p[2]) > r || p[0].max(p[1]).max(p[2]) < -r
+        };
+        // faces of the cell, the plane of the triangle, then the edge pairs
+        !(units.iter().any(|&u| separates(u))
+            || separates(cross(edges[0], edges[1]))
+            || edges
+                .iter()
+                .any(|&e| units.iter().any(|&u| separates(cross(e, u)))))
+    }
+}
+
+/// codes of the cells at `depth` intersecting the triangle `a`, `b`, `c`, in order.
+///
+/// Vertices are in units of cells at `depth`. Cells are tested hierarchically, so only
+/// the cells near the triangle are visited.
+/// panic if `depth` exceeds `MortonKey::MAX_DEPTH`.
+pub fn voxelize_triangle(
+    a: [f64; 3],
+    b: [f64; 3],
+    c: [f64; 3],
+    depth: u32,
+) -> RegionCells<Triangle> {
+    assert!(depth <= MortonKey::MAX_DEPTH, "depth out of range");
+    let scale = f64::from(1u32 << (MortonKey::MAX_DEPTH - depth));
+    let [a, b, c] = [a, b, c].map(|p| p.map(|v| v * scale));
+    RegionCells::new(Triangle::new(a, b, c), depth)
+}
+
+/// insert the cells intersecting the triangles of a mesh into `set`,
+/// see [`voxelize_triangle`].
+///
+/// `triangles` are indices into `vertices`, which are in units of cells at `set.depth()`.
+/// panic if an index is out of range.
+#[cfg(feature = "alloc")]
+pub fn voxelize_mesh(vertices: &[[f64; 3]], triangles: &[[usize; 3]], set: &mut MortonSet) {
+    for &[a, b, c] in triangles {
+        let cells = voxelize_triangle(vertices[a], vertices[b], vertices[c], set.depth());
+        set.extend(cells);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    use super::voxelize_mesh;
+    use super::{voxelize_segment, voxelize_triangle};
+    use crate::Morton3D;
+    #[cfg(feature = "alloc")]
+    use crate::MortonSet;
+
+    fn coords(p0: [f64; 3], p1: [f64; 3], depth: u32) -> Vec<(u32, u32, u32)> {
+        voxelize_segment(p0, p1, depth)
+            .map(Morton3D::to_coords)
+            .collect()
+    }
+
+    #[test]
+    fn test_voxelize_segment() {
+        assert_eq!(coords([1.5; 3], [1.5; 3], 2), vec![(1, 1, 1)]);
+        assert_eq!(
+            coords([0.5, 1.5, 0.5], [3.2, 1.5, 0.5], 2),
+            vec![(0, 1, 0), (1, 1, 0), (2, 1, 0), (3, 1, 0)]
+        );
+        // through an edge, both cells beside it are touched
+        assert_eq!(
+            coords([0.5, 0.5, 0.5], [1.5, 1.5, 0.5], 2),
+            vec![(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]
+        );
+        // through a corner
+        let cells = coords([0.5; 3], [1.5; 3], 2);
+        assert_eq!(cells.len(), 8);
+        assert_eq!(cells.first(), Some(&(0, 0, 0)));
+        assert_eq!(cells.last(), Some(&(1, 1, 1)));
+        // backward and clipped by the grid
+        assert_eq!(
+            coords([1.5, 0.5, 0.5], [-3.0, 0.5, 0.5], 2),
+            vec![(1, 0, 0), (0, 0, 0)]
+        );
+        assert!(coords([5.0; 3], [6.0; 3], 2).is_empty());
+
+        // a generic segment is the same as the ray
+        let (p0, p1) = ([0.3, 5.7, 2.2], [11.1, 1.5, 9.4]);
+        let direction = [0, 1, 2].map(|a| p1[a] - p0[a]);
+        let ray: Vec<_> = crate::raycast(p0, direction, 1.0, 4).collect();
+        assert_eq!(voxelize_segment(p0, p1, 4).collect::<Vec<_>>(), ray);
+    }
+
+    #[test]
+    fn test_voxelize_triangle() {
+        let cells: Vec<_> = voxelize_triangle([0.5, 0.5, 2.5], [6.5, 0.5, 2.5], [0.5, 6.5, 2.5], 3)
+            .map(Morton3D::to_coords)
+            .collect();
+        // cells whose lower corner is on the triangle side of `x + y = 7`
+        assert_eq!(cells.len(), 34);
+        assert!(cells.iter().all(|&(x, y, z)| z == 2 && x + y <= 7));
+        assert!(cells
+            .windows(2)
+            .all(|w| Morton3D::from_coords(w[0].0, w[0].1, w[0].2)
+                < Morton3D::from_coords(w[1].0, w[1].1, w[1].2)));
+
+        // sampled points of a tilted triangle are in the cells
+        let (a, b, c) = ([1.2, 3.3, 0.4], [14.7, 6.1, 9.9], [4.4, 15.2, 13.1]);
+        let cells: Vec<_> = voxelize_triangle(a, b, c, 4)
+            .map(Morton3D::to_coords)
+            .collect();
+        for i in 0..=50 {
+            for j in 0..=(50 - i) {
+                let (u, v) = (f64::from(i) / 50.0, f64::from(j) / 50.0);
+                let p = [0, 1, 2].map(|k| (a[k] + u * (b[k] - a[k]) + v * (c[k] - a[k])) as u32);
+                assert!(cells.contains(&(p[0], p[1], p[2])));
+            }
+        }
+        // but not the whole bounding box
+        assert!(cells.len() < 14 * 13 * 13 / 4);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_voxelize_mesh() {
+        // a square in the plane `z = 1.5` made of two triangles
+        let vertices = [
+            [0.5, 0.5, 1.5],
+            [3.5, 0.5, 1.5],
+            [3.5, 3.5, 1.5],
+            [0.5, 3.5, 1.5],
+        ];
+        let mut set = MortonSet::new(3);
+        voxelize_mesh(&vertices, &[[0, 1, 2], [0, 2, 3]], &mut set);
+        assert_eq!(set.len(), 16);
+        assert!(set.iter().all(|v| v.to_coords().2 == 1));
+    }
+}
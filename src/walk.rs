@@ -0,0 +1,282 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+use core::iter::FusedIterator;
+
+use crate::MortonKey;
+
+/// Depth-first (pre-order) traversal of the implicit full octree below a [`MortonKey`].
+///
+/// Cells for which `prune` returns `true` are skipped together with their descendants.
+/// No allocation is needed, the next cell is computed from the current one.
+#[derive(Debug, Clone)]
+pub struct DepthFirstWalk<F> {
+    root: MortonKey,
+    max_depth: u32,
+    next: Option<MortonKey>,
+    prune: F,
+}
+
+impl<F: FnMut(MortonKey) -> bool> DepthFirstWalk<F> {
+    /// walk the cells below `root` (including itself) down to `max_depth`.
+    ///
+    /// panic if `max_depth` is not in `root.depth()..=MortonKey::MAX_DEPTH`.
+    pub fn new(root: MortonKey, max_depth: u32, prune: F) -> Self {
+        assert!(
+            root.depth() <= max_depth && max_depth <= MortonKey::MAX_DEPTH,
+            "depth out of range"
+        );
+        Self {
+            root,
+            max_depth,
+            next: Some(root),
+            prune,
+        }
+    }
+}
+
+impl<F: FnMut(MortonKey) -> bool> Iterator for DepthFirstWalk<F> {
+    type Item = MortonKey;
+    fn next(&mut self) -> Option<MortonKey> {
+        loop {
+            let current = self.next?;
+            if (self.prune)(current) {
+                self.next = current.next_subtree(self.root);
+                continue;
+            }
+            self.next = if current.depth() < self.max_depth {
+                Some(current.child(0))
+            } else {
+                current.next_subtree(self.root)
+            };
+            return Some(current);
+        }
+    }
+}
+
+impl<F: FnMut(MortonKey) -> bool> FusedIterator for DepthFirstWalk<F> {}
+
+/// Breadth-first (level by level) traversal of the implicit full octree below a [`MortonKey`].
+///
+/// Cells for which `prune` returns `true` are skipped together with their descendants.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct BreadthFirstWalk<F> {
+    max_depth: u32,
+    queue: VecDeque<MortonKey>,
+    prune: F,
+}
+
+#[cfg(feature = "alloc")]
+impl<F: FnMut(MortonKey) -> bool> BreadthFirstWalk<F> {
+    /// walk the cells below `root` (including itself) down to `max_depth`.
+    ///
+    /// panic if `max_depth` is not in `root.depth()..=MortonKey::MAX_DEPTH`.
+    pub fn new(root: MortonKey, max_depth: u32, prune: F) -> Self {
+        assert!(
+            root.depth() <= max_depth && max_depth <= MortonKey::MAX_DEPTH,
+            "depth out of range"
+        );
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        Self {
+            max_depth,
+            queue,
+            prune,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: FnMut(MortonKey) -> bool> Iterator for BreadthFirstWalk<F> {
+    type Item = MortonKey;
+    fn next(&mut self) -> Option<MortonKey> {
+        loop {
+            let current = self.queue.pop_front()?;
+            if (self.prune)(current) {
+                continue;
+            }
+            if current.depth() < self.max_depth {
+                self.queue.extend(current.children().iter());
+            }
+            return Some(current);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: FnMut(MortonKey) -> bool> FusedIterator for BreadthFirstWalk<F> {}
+
+/// Decision of the callback of [`visit`] about a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visit {
+    /// walk the children of the cell, treated as `Accept` for a cell at `MAX_DEPTH`.
+    Descend,
+    /// yield the cell and don't walk its children.
+    Accept,
+    /// neither yield the cell nor walk its children.
+    Skip,
+}
+
+/// Top-down traversal of the implicit octree below a [`MortonKey`], driven by a callback.
+///
+/// The callback decides for each cell whether to refine it, take it or drop it (see [`Visit`]),
+/// the accepted cells are yielded in pre-order. No allocation is needed.
+#[derive(Debug, Clone)]
+pub struct Visitor<F> {
+    root: MortonKey,
+    next: Option<MortonKey>,
+    visit: F,
+}
+
+impl<F: FnMut(MortonKey) -> Visit> Iterator for Visitor<F> {
+    type Item = MortonKey;
+    fn next(&mut self) -> Option<MortonKey> {
+        loop {
+            let current = self.next?;
+            match (self.visit)(current) {
+                Visit::Descend if current.depth() < MortonKey::MAX_DEPTH => {
+                    self.next = Some(current.child(0));
+                }
+                Visit::Skip => self.next = current.next_subtree(self.root),
+                Visit::Descend | Visit::Accept => {
+                    self.next = current.next_subtree(self.root);
+                    return Some(current);
+                }
+            }
+        }
+    }
+}
+
+impl<F: FnMut(MortonKey) -> Visit> FusedIterator for Visitor<F> {}
+
+/// walk the cells below `root` (including itself) top-down, yielding the ones accepted by `visit`,
+/// see [`Visitor`].
+///
+/// This is the recursion of culling passes (occlusion, LOD, ...), only the decision is left to `visit`.
+pub fn visit<F: FnMut(MortonKey) -> Visit>(root: MortonKey, visit: F) -> Visitor<F> {
+    Visitor {
+        root,
+        next: Some(root),
+        visit,
+    }
+}
+
+impl MortonKey {
+    /// next cell in pre-order below `root` which is not below this cell, `None` at the end of `root`.
+    pub(crate) fn next_subtree(self, root: MortonKey) -> Option<MortonKey> {
+        let mut v = self;
+        while v != root {
+            let parent = v.parent()?;
+            if v.octant() < 7 {
+                return Some(parent.child(v.octant() + 1));
+            }
+            v = parent;
+        }
+        None
+    }
+
+    /// walk the cells below this cell (including itself) down to `max_depth` in depth-first order,
+    /// see [`DepthFirstWalk`].
+    pub fn walk_depth_first<F: FnMut(MortonKey) -> bool>(
+        self,
+        max_depth: u32,
+        prune: F,
+    ) -> DepthFirstWalk<F> {
+        DepthFirstWalk::new(self, max_depth, prune)
+    }
+
+    /// walk the cells below this cell (including itself) down to `max_depth` in breadth-first order,
+    /// see [`BreadthFirstWalk`].
+    #[cfg(feature = "alloc")]
+    pub fn walk_breadth_first<F: FnMut(MortonKey) -> bool>(
+        self,
+        max_depth: u32,
+        prune: F,
+    ) -> BreadthFirstWalk<F> {
+        BreadthFirstWalk::new(self, max_depth, prune)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{visit, Visit};
+    use crate::{Morton3D, MortonKey};
+
+    #[test]
+    fn test_depth_first() {
+        let keys: Vec<_> = MortonKey::ROOT.walk_depth_first(2, |_| false).collect();
+        assert_eq!(keys.len(), 1 + 8 + 64);
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(keys[1], MortonKey::ROOT.child(0));
+        assert_eq!(keys[2], MortonKey::ROOT.child(0).child(0));
+
+        // subtree only
+        let root = MortonKey::new(Morton3D::from_coords(1, 0, 1), 1);
+        let keys: Vec<_> = root.walk_depth_first(3, |_| false).collect();
+        assert_eq!(keys.len(), 1 + 8 + 64);
+        assert!(keys.iter().skip(1).all(|k| root.is_ancestor_of(*k)));
+        assert_eq!(
+            root.walk_depth_first(1, |_| false).collect::<Vec<_>>(),
+            [root]
+        );
+
+        // prune the subtree of the octant 0 and every cell at depth 2 with octant 7
+        let keys: Vec<_> = MortonKey::ROOT
+            .walk_depth_first(2, |k| {
+                k == MortonKey::ROOT.child(0) || (k.depth() == 2 && k.octant() == 7)
+            })
+            .collect();
+        assert_eq!(keys.len(), 1 + 7 + 7 * 7);
+        assert_eq!(MortonKey::ROOT.walk_depth_first(5, |_| true).count(), 0);
+    }
+
+    #[test]
+    fn test_visit() {
+        // refine toward the origin as in a LOD pass
+        let keys: Vec<_> = visit(MortonKey::ROOT, |k| {
+            if k.depth() >= 3 {
+                Visit::Accept
+            } else if k.octant() == 0 {
+                Visit::Descend
+            } else if k.octant() == 7 {
+                Visit::Skip
+            } else {
+                Visit::Accept
+            }
+        })
+        .collect();
+        // the root has octant 0
+        assert_eq!(keys.len(), 6 + 6 + 8);
+        assert!(keys
+            .windows(2)
+            .all(|w| w[0] < w[1] && !w[0].is_ancestor_of(w[1])));
+        assert_eq!(keys[0], MortonKey::ROOT.child(0).child(0).child(0));
+
+        assert_eq!(visit(MortonKey::ROOT, |_| Visit::Skip).count(), 0);
+        assert_eq!(
+            visit(MortonKey::ROOT, |_| Visit::Accept).collect::<Vec<_>>(),
+            [MortonKey::ROOT]
+        );
+        let leaf = MortonKey::new(Morton3D::from_coords(1, 2, 3), MortonKey::MAX_DEPTH);
+        assert_eq!(visit(leaf, |_| Visit::Descend).collect::<Vec<_>>(), [leaf]);
+        let root = leaf.parent().unwrap();
+        assert_eq!(visit(root, |_| Visit::Descend).count(), 8);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_breadth_first() {
+        let keys: Vec<_> = MortonKey::ROOT.walk_breadth_first(2, |_| false).collect();
+        assert_eq!(keys.len(), 1 + 8 + 64);
+        assert!(keys.windows(2).all(|w| w[0].depth() <= w[1].depth()));
+        assert_eq!(&keys[1..9], &MortonKey::ROOT.children());
+
+        let prune = |k: MortonKey| k.depth() == 1 && k.octant() % 2 == 1;
+        let mut dfs: Vec<_> = MortonKey::ROOT.walk_depth_first(3, prune).collect();
+        let mut bfs: Vec<_> = MortonKey::ROOT.walk_breadth_first(3, prune).collect();
+        assert_eq!(bfs.len(), 1 + 4 + 4 * 8 + 4 * 64);
+        dfs.sort();
+        bfs.sort();
+        assert_eq!(dfs, bfs);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{MortonError, MortonND, MortonStorage};
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+/// 3-dimension morton code(xyz) backed by `S`, first bit is used for 1-bit flag.
+///
+/// Twin of [`MortonND<3, S>`](MortonND) with the reversed interleave order:
+/// x is stored in the highest bit of each 3-bit group and z in the lowest,
+/// which is the order used by many GPU LBVH builders and existing databases.
+pub struct MortonXyz<S: MortonStorage = usize>(MortonND<3, S>);
+
+/// 3-dimension morton code(xyz) backed by `usize`, see [`MortonXyz`].
+pub type Morton3DXyz = MortonXyz<usize>;
+
+/// Implement [`MortonXyz`] operations for storage `$t` with coordinates of type `$c`.
+macro_rules! morton_xyz {
+    ($t:ty, $c:ty) => {
+        impl MortonXyz<$t> {
+            /// Max number of depth (bits per axis).
+            pub const MAX_DEPTH: u32 = MortonND::<3, $t>::MAX_DEPTH;
+
+            /// Max coordinate of each axis.
+            pub const MAX_COORD: $c = MortonND::<3, $t>::MAX_COORD;
+
+            /// encode coordinates into morton code.
+            ///
+            /// Only the lower `MAX_DEPTH` bits of each coordinate are used,
+            /// so the flag bit is never set by encoding.
+            pub const fn from_coords(x: $c, y: $c, z: $c) -> Self {
+                // same bits as zyx order with x and z swapped
+                Self(MortonND::<3, $t>::from_coords(z, y, x))
+            }
+
+            /// encode coordinates into morton code,
+            /// return error if any coordinate exceeds `MAX_COORD`.
+            pub const fn try_from_coords(x: $c, y: $c, z: $c) -> Result<Self, MortonError> {
+                match MortonND::<3, $t>::try_from_coords(z, y, x) {
+                    Ok(v) => Ok(Self(v)),
+                    Err(MortonError::CoordinateOutOfRange { axis, value, max }) => {
+                        Err(MortonError::CoordinateOutOfRange {
+                            axis: 2 - axis,
+                            value,
+                            max,
+                        })
+                    }
+                }
+            }
+
+            /// decode morton code into coordinates `(x, y, z)`, flag bit is ignored.
+            pub const fn to_coords(self) -> ($c, $c, $c) {
+                (self.x(), self.y(), self.z())
+            }
+
+            /// convert to zyx order, coordinates and flag are kept.
+            pub const fn to_zyx(self) -> MortonND<3, $t> {
+                let mut v = MortonND::<3, $t>::from_coords(self.x(), self.y(), self.z());
+                if self.is_flag_set() {
+                    v.set_flag();
+                }
+                v
+            }
+
+            /// convert from zyx order, coordinates and flag are kept.
+            pub const fn from_zyx(v: MortonND<3, $t>) -> Self {
+                let mut xyz = Self::from_coords(v.x(), v.y(), v.z());
+                if v.is_flag_set() {
+                    xyz.set_flag();
+                }
+                xyz
+            }
+
+            pub const fn is_flag_set(self) -> bool {
+                self.0.is_flag_set()
+            }
+            pub const fn set_flag(&mut self) {
+                self.0.set_flag()
+            }
+            pub const fn unset_flag(&mut self) {
+                self.0.unset_flag()
+            }
+
+            pub const fn x(self) -> $c {
+                self.0.z()
+            }
+            pub const fn y(self) -> $c {
+                self.0.y()
+            }
+            pub const fn z(self) -> $c {
+                self.0.x()
+            }
+
+            pub const fn with_x(self, x: $c) -> Self {
+                Self(self.0.with_z(x))
+            }
+            pub const fn with_y(self, y: $c) -> Self {
+                Self(self.0.with_y(y))
+            }
+            pub const fn with_z(self, z: $c) -> Self {
+                Self(self.0.with_x(z))
+            }
+
+            pub const fn set_x(&mut self, x: $c) {
+                self.0.set_z(x)
+            }
+            pub const fn set_y(&mut self, y: $c) {
+                self.0.set_y(y)
+            }
+            pub const fn set_z(&mut self, z: $c) {
+                self.0.set_x(z)
+            }
+
+            pub const fn decrease_x(self) -> Self {
+                Self(self.0.decrease_z())
+            }
+            pub const fn decrease_y(self) -> Self {
+                Self(self.0.decrease_y())
+            }
+            pub const fn decrease_z(self) -> Self {
+                Self(self.0.decrease_x())
+            }
+
+            pub const fn increase_x(self) -> Self {
+                Self(self.0.increase_z())
+            }
+            pub const fn increase_y(self) -> Self {
+                Self(self.0.increase_y())
+            }
+            pub const fn increase_z(self) -> Self {
+                Self(self.0.increase_x())
+            }
+        }
+
+        impl From<$t> for MortonXyz<$t> {
+            fn from(v: $t) -> Self {
+                Self(MortonND::from(v))
+            }
+        }
+
+        impl From<MortonXyz<$t>> for $t {
+            fn from(v: MortonXyz<$t>) -> Self {
+                <$t>::from(v.0)
+            }
+        }
+
+        impl From<MortonND<3, $t>> for MortonXyz<$t> {
+            fn from(v: MortonND<3, $t>) -> Self {
+                Self::from_zyx(v)
+            }
+        }
+
+        impl From<MortonXyz<$t>> for MortonND<3, $t> {
+            fn from(v: MortonXyz<$t>) -> Self {
+                v.to_zyx()
+            }
+        }
+
+        impl From<($c, $c, $c)> for MortonXyz<$t> {
+            fn from((x, y, z): ($c, $c, $c)) -> Self {
+                Self::from_coords(x, y, z)
+            }
+        }
+
+        impl From<MortonXyz<$t>> for ($c, $c, $c) {
+            fn from(v: MortonXyz<$t>) -> Self {
+                v.to_coords()
+            }
+        }
+    };
+}
+
+morton_xyz!(u16, u16);
+morton_xyz!(u32, u32);
+morton_xyz!(u64, u32);
+morton_xyz!(u128, u64);
+morton_xyz!(usize, u32);
+
+#[cfg(test)]
+mod tests {
+    use super::{Morton3DXyz, MortonXyz};
+    use crate::{Morton3D, Morton3D30, MortonError};
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(usize::from(Morton3DXyz::from_coords(1, 0, 0)), 0b100);
+        assert_eq!(usize::from(Morton3DXyz::from_coords(0, 1, 0)), 0b010);
+        assert_eq!(usize::from(Morton3DXyz::from_coords(0, 0, 1)), 0b001);
+        assert_eq!(usize::from(Morton3DXyz::from_coords(3, 2, 1)), 0b110_101);
+
+        // 10-level xyz code is the GPU layout
+        let morton = MortonXyz::<u32>::from_coords(1000, 20, 3);
+        assert_eq!(
+            u32::from(morton),
+            u32::from(Morton3D30::from_coords(1000, 20, 3))
+        );
+    }
+
+    #[test]
+    fn test_coords() {
+        let mut morton = Morton3DXyz::from_coords(7, 8, 9);
+        assert_eq!(morton.to_coords(), (7, 8, 9));
+        morton.set_flag();
+        assert!(morton.is_flag_set());
+        assert_eq!(morton.increase_x().decrease_z().to_coords(), (8, 8, 8));
+        morton.set_y(1);
+        assert_eq!(morton.with_z(0).to_coords(), (7, 1, 0));
+        assert_eq!(
+            Morton3DXyz::try_from_coords(0, 0, Morton3DXyz::MAX_COORD + 1),
+            Err(MortonError::CoordinateOutOfRange {
+                axis: 2,
+                value: u64::from(Morton3DXyz::MAX_COORD) + 1,
+                max: u64::from(Morton3DXyz::MAX_COORD)
+            })
+        );
+    }
+
+    #[test]
+    fn test_conversion() {
+        let mut zyx = Morton3D::from_coords(5, 6, 7);
+        zyx.set_flag();
+        let xyz = Morton3DXyz::from(zyx);
+        assert_eq!(xyz.to_coords(), (5, 6, 7));
+        assert!(xyz.is_flag_set());
+        assert_eq!(Morton3D::from(xyz), zyx);
+    }
+}